@@ -1,6 +1,14 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use dive_deco::{BuehlmannConfig, BuehlmannModel, DecoModel, Depth, Gas, Time};
 
+const MAX_GASSES: usize = 16;
+
+fn build_gasses(gasses: &[Gas]) -> [Gas; MAX_GASSES] {
+    let mut gas_mixes = [Gas::default(); MAX_GASSES];
+    gas_mixes[..gasses.len()].copy_from_slice(gasses);
+    gas_mixes
+}
+
 pub fn buehlmann_ndl_benchmark(c: &mut Criterion) {
     c.bench_function("Buehlmann NDL", |b| {
         b.iter(|| {
@@ -17,7 +25,7 @@ pub fn buehlmann_deco_benchmark(c: &mut Criterion) {
     let ean_50 = Gas::new(0.50, 0.);
     model.record(Depth::from_meters(40.0001), Time::from_minutes(20.), &air);
     c.bench_function("Buehlmann deco", |b| {
-        b.iter(|| model.deco(vec![air, ean_50]))
+        b.iter(|| model.deco(build_gasses(&[air, ean_50])))
     });
 }
 
@@ -30,15 +38,15 @@ pub fn buehlmann_deco_adaptive_recalc(c: &mut Criterion) {
 
     let air = Gas::air();
     let ean50 = Gas::new(0.50, 0.);
-    let available_gasses = vec![air, ean50];
+    let available_gasses = build_gasses(&[air, ean50]);
 
     c.bench_function("Record and deco", |b| {
         b.iter(|| {
             model.record(Depth::from_meters(40.), Time::from_seconds(1.), &air);
-            model.deco(available_gasses.clone()).unwrap();
+            model.deco(available_gasses).unwrap();
             model.record(Depth::from_meters(40.), Time::from_seconds(1.), &air);
             model.record(Depth::from_meters(40.), Time::from_seconds(1.), &air);
-            model.deco(available_gasses.clone()).unwrap();
+            model.deco(available_gasses).unwrap();
         });
     });
 }
@@ -54,19 +62,19 @@ pub fn buehlmann_full(c: &mut Criterion) {
     let air = Gas::air();
     let ean50 = Gas::new(0.50, 0.);
     let o2 = Gas::new(1., 0.);
-    let available_gasses = vec![air, ean50, o2];
+    let available_gasses = build_gasses(&[air, ean50, o2]);
 
     c.bench_function("Buehlmann full", |b| {
         b.iter(|| {
             model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
-            model.deco(available_gasses.clone()).unwrap();
+            model.deco(available_gasses).unwrap();
             model.record(Depth::from_meters(40.), Time::from_minutes(5.), &air);
             model.record_travel_with_rate(Depth::from_meters(35.), 10., &air);
             model.record_travel_with_rate(Depth::from_meters(21.), 10., &air);
             model.record(Depth::from_meters(21.), Time::from_seconds(60.), &ean50);
             model.supersaturation();
             model.ceiling();
-            model.deco(available_gasses.clone()).unwrap();
+            model.deco(available_gasses).unwrap();
             model.in_deco();
             model.ndl();
             model.cns();
@@ -1,4 +1,4 @@
-use dive_deco::{BuehlmannConfig, BuehlmannModel, CeilingType, DecoModel};
+use dive_deco::{BuehlmannConfig, BuehlmannModel, CeilingType, DecoModel, SafetyStopConfig, Time};
 
 fn main() {
     // model with default config (GF 100/100)
@@ -14,6 +14,11 @@ fn main() {
         ceiling_type: CeilingType::Actual,
         round_ceiling: false,
         recalc_all_tissues_m_values: true,
+        max_gas_density: None,
+        deco_max_ppo2: 1.6,
+        ndl_type: None,
+        ndl_cut_off: Time::from_minutes(99.),
+        safety_stop: SafetyStopConfig::default(),
     };
     let model_2 = BuehlmannModel::new(config_instance);
     println!("{:?}", model_2.config());
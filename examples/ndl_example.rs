@@ -14,5 +14,5 @@ fn main() {
 
     // current NDL (no-decompression limit)
     let current_ndl = model.ndl();
-    println!("NDL: {} min", current_ndl.as_minutes()); // output: NDL: 5 min
+    println!("NDL: {} s", current_ndl.as_seconds()); // output: NDL: 392 s
 }
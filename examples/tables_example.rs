@@ -0,0 +1,32 @@
+use dive_deco::{
+    BuehlmannConfig, BuehlmannModel, Depth, DepthRange, DiveTables, Gas, TableFormat, Time,
+};
+
+fn main() {
+    // club training tables: air, GF 30/70, 12-30m every 3m
+    let config = BuehlmannConfig::new().with_gradient_factors(30, 70);
+    let depths = DepthRange::new(
+        Depth::from_meters(12.),
+        Depth::from_meters(30.),
+        Depth::from_meters(3.),
+    );
+    let bottom_times = [
+        Time::from_minutes(20.),
+        Time::from_minutes(30.),
+        Time::from_minutes(40.),
+    ];
+    let tables: DiveTables<BuehlmannModel> =
+        DiveTables::new(config, depths, &bottom_times, Gas::air());
+
+    let mut ndl_table = String::new();
+    tables
+        .write_ndl_table(&mut ndl_table, TableFormat::Markdown)
+        .unwrap();
+    println!("{}", ndl_table);
+
+    let mut deco_table = String::new();
+    tables
+        .write_deco_table(&mut deco_table, TableFormat::Csv)
+        .unwrap();
+    println!("{}", deco_table);
+}
@@ -1,10 +1,14 @@
 use crate::{
     common::{
-        AscentRatePerMinute, ConfigValidationErr, ConfigValidationErrorReason, ConfigValidationErrorField, DecoModelConfig, GradientFactors, MbarPressure,
+        AscentRatePerMinute, ConfigValidationErr, ConfigValidationErrorField,
+        ConfigValidationErrorReason, DecoModelConfig, Depth, GasDensity, GradientFactors,
+        MbarPressure, NDLType, Pressure, SafetyStopConfig, Time, DEFAULT_NDL_CUT_OFF_MINS,
     },
     CeilingType,
 };
 
+const DEFAULT_DECO_MAX_PPO2: Pressure = 1.6;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct BuehlmannConfig {
     pub gf: GradientFactors,
@@ -13,6 +17,12 @@ pub struct BuehlmannConfig {
     pub ceiling_type: CeilingType,
     pub round_ceiling: bool,
     pub recalc_all_tissues_m_values: bool,
+    pub max_gas_density: Option<GasDensity>,
+    pub deco_max_ppo2: Pressure,
+    // None - derived from ceiling type (Adaptive: Actual NDL, Actual: NDL by ceiling)
+    pub ndl_type: Option<NDLType>,
+    pub ndl_cut_off: Time,
+    pub safety_stop: SafetyStopConfig,
 }
 
 impl BuehlmannConfig {
@@ -49,6 +59,31 @@ impl BuehlmannConfig {
         self.recalc_all_tissues_m_values = recalc_all_tissues_m_values;
         self
     }
+
+    pub fn with_max_gas_density(mut self, max_gas_density: Option<GasDensity>) -> Self {
+        self.max_gas_density = max_gas_density;
+        self
+    }
+
+    pub fn with_deco_max_ppo2(mut self, deco_max_ppo2: Pressure) -> Self {
+        self.deco_max_ppo2 = deco_max_ppo2;
+        self
+    }
+
+    pub fn with_ndl_type(mut self, ndl_type: NDLType) -> Self {
+        self.ndl_type = Some(ndl_type);
+        self
+    }
+
+    pub fn with_ndl_cut_off(mut self, ndl_cut_off: Time) -> Self {
+        self.ndl_cut_off = ndl_cut_off;
+        self
+    }
+
+    pub fn with_safety_stop(mut self, safety_stop: SafetyStopConfig) -> Self {
+        self.safety_stop = safety_stop;
+        self
+    }
 }
 
 impl Default for BuehlmannConfig {
@@ -60,6 +95,11 @@ impl Default for BuehlmannConfig {
             ceiling_type: CeilingType::Actual,
             round_ceiling: false,
             recalc_all_tissues_m_values: true,
+            max_gas_density: None,
+            deco_max_ppo2: DEFAULT_DECO_MAX_PPO2,
+            ndl_type: None,
+            ndl_cut_off: Time::from_minutes(DEFAULT_NDL_CUT_OFF_MINS),
+            safety_stop: SafetyStopConfig::default(),
         }
     }
 }
@@ -70,12 +110,20 @@ impl DecoModelConfig for BuehlmannConfig {
             gf,
             surface_pressure,
             deco_ascent_rate,
+            max_gas_density,
+            deco_max_ppo2,
+            ndl_cut_off,
+            safety_stop,
             ..
         } = self;
 
         self.validate_gradient_factors(gf)?;
         self.validate_surface_pressure(surface_pressure)?;
         self.validate_deco_ascent_rate(deco_ascent_rate)?;
+        self.validate_max_gas_density(max_gas_density)?;
+        self.validate_deco_max_ppo2(deco_max_ppo2)?;
+        self.validate_ndl_cut_off(ndl_cut_off)?;
+        self.validate_safety_stop(safety_stop)?;
 
         Ok(())
     }
@@ -95,6 +143,30 @@ impl DecoModelConfig for BuehlmannConfig {
     fn round_ceiling(&self) -> bool {
         self.round_ceiling
     }
+
+    fn max_gas_density(&self) -> Option<GasDensity> {
+        self.max_gas_density
+    }
+
+    fn deco_max_ppo2(&self) -> Pressure {
+        self.deco_max_ppo2
+    }
+
+    fn ndl_type(&self) -> NDLType {
+        match (self.ndl_type, self.ceiling_type) {
+            (Some(ndl_type), _) => ndl_type,
+            (None, CeilingType::Adaptive) => NDLType::Actual,
+            (None, CeilingType::Actual) => NDLType::ByCeiling,
+        }
+    }
+
+    fn ndl_cut_off(&self) -> Time {
+        self.ndl_cut_off
+    }
+
+    fn safety_stop(&self) -> SafetyStopConfig {
+        self.safety_stop
+    }
 }
 
 impl BuehlmannConfig {
@@ -103,11 +175,17 @@ impl BuehlmannConfig {
         let gf_range = 1..=100;
 
         if !gf_range.contains(gf_low) || !gf_range.contains(gf_high) {
-            return Err(ConfigValidationErr::new(ConfigValidationErrorField::GradientFactors, ConfigValidationErrorReason::GF_RANGE_ERR_MSG));
+            return Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::GradientFactors,
+                ConfigValidationErrorReason::GF_RANGE_ERR_MSG,
+            ));
         }
 
         if gf_low > gf_high {
-            return Err(ConfigValidationErr::new(ConfigValidationErrorField::GradientFactors, ConfigValidationErrorReason::GF_ORDER_ERR_MSG));
+            return Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::GradientFactors,
+                ConfigValidationErrorReason::GF_ORDER_ERR_MSG,
+            ));
         }
 
         Ok(())
@@ -142,6 +220,74 @@ impl BuehlmannConfig {
 
         Ok(())
     }
+
+    fn validate_max_gas_density(
+        &self,
+        max_gas_density: &Option<GasDensity>,
+    ) -> Result<(), ConfigValidationErr> {
+        if let Some(max_gas_density) = max_gas_density {
+            let gas_density_range = 1.0..=6.2;
+            if !gas_density_range.contains(max_gas_density) {
+                return Err(ConfigValidationErr::new(
+                    ConfigValidationErrorField::MaxGasDensity,
+                    ConfigValidationErrorReason::MAX_GAS_DENSITY_ERR_MSG,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_deco_max_ppo2(&self, deco_max_ppo2: &Pressure) -> Result<(), ConfigValidationErr> {
+        let deco_max_ppo2_range = 1.0..=1.6;
+        if !deco_max_ppo2_range.contains(deco_max_ppo2) {
+            return Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::DecoMaxPpO2,
+                ConfigValidationErrorReason::DECO_MAX_PPO2_ERR_MSG,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn validate_ndl_cut_off(&self, ndl_cut_off: &Time) -> Result<(), ConfigValidationErr> {
+        let ndl_cut_off_range = 1.0..=999.0;
+        if !ndl_cut_off_range.contains(&ndl_cut_off.as_minutes()) {
+            return Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::NdlCutOff,
+                ConfigValidationErrorReason::NDL_CUT_OFF_ERR_MSG,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn validate_safety_stop(
+        &self,
+        safety_stop: &SafetyStopConfig,
+    ) -> Result<(), ConfigValidationErr> {
+        let SafetyStopConfig {
+            trigger_depth,
+            stop_depth,
+            min_depth,
+            max_depth,
+            duration,
+            ..
+        } = *safety_stop;
+        let depths_valid = min_depth > Depth::zero()
+            && min_depth <= stop_depth
+            && stop_depth <= max_depth
+            && max_depth <= trigger_depth;
+        let duration_range = 1.0..=1800.0;
+        if !depths_valid || !duration_range.contains(&duration.as_seconds()) {
+            return Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::SafetyStop,
+                ConfigValidationErrorReason::SAFETY_STOP_ERR_MSG,
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -156,6 +302,10 @@ mod tests {
         assert_eq!(config.deco_ascent_rate, 10.);
         assert_eq!(config.ceiling_type, CeilingType::Actual);
         assert!(!config.round_ceiling);
+        assert_eq!(config.ndl_type, None);
+        assert_eq!(config.ndl_type(), NDLType::ByCeiling);
+        assert_eq!(config.ndl_cut_off, Time::from_minutes(99.));
+        assert!(!config.safety_stop.planned);
     }
 
     #[test]
@@ -173,7 +323,10 @@ mod tests {
             let config = BuehlmannConfig::new().with_gradient_factors(gf_low, gf_high);
             assert_eq!(
                 config.validate(),
-                Err(ConfigValidationErr::new(ConfigValidationErrorField::GradientFactors, ConfigValidationErrorReason::GF_RANGE_ERR_MSG))
+                Err(ConfigValidationErr::new(
+                    ConfigValidationErrorField::GradientFactors,
+                    ConfigValidationErrorReason::GF_RANGE_ERR_MSG
+                ))
             );
         }
     }
@@ -183,7 +336,10 @@ mod tests {
         let config = BuehlmannConfig::new().with_gradient_factors(90, 80);
         assert_eq!(
             config.validate(),
-            Err(ConfigValidationErr::new(ConfigValidationErrorField::GradientFactors, ConfigValidationErrorReason::GF_ORDER_ERR_MSG))
+            Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::GradientFactors,
+                ConfigValidationErrorReason::GF_ORDER_ERR_MSG
+            ))
         );
     }
 
@@ -230,4 +386,109 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_max_gas_density_config() {
+        let config = BuehlmannConfig::new().with_max_gas_density(Some(5.2));
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.max_gas_density, Some(5.2));
+        assert_eq!(BuehlmannConfig::default().max_gas_density, None);
+    }
+
+    #[test]
+    fn test_invalid_max_gas_density_values() {
+        let invalid_max_gas_density_cases = [-1., 0., 0.5, 6.3, 10.];
+        for invalid_case in invalid_max_gas_density_cases {
+            let config = BuehlmannConfig::new().with_max_gas_density(Some(invalid_case));
+            assert_eq!(
+                config.validate(),
+                Err(ConfigValidationErr::new(
+                    ConfigValidationErrorField::MaxGasDensity,
+                    ConfigValidationErrorReason::MAX_GAS_DENSITY_ERR_MSG
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_deco_max_ppo2_config() {
+        let config = BuehlmannConfig::new().with_deco_max_ppo2(1.4);
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.deco_max_ppo2, 1.4);
+        assert_eq!(BuehlmannConfig::default().deco_max_ppo2, 1.6);
+    }
+
+    #[test]
+    fn test_invalid_deco_max_ppo2_values() {
+        let invalid_deco_max_ppo2_cases = [-1., 0., 0.9, 1.7];
+        for invalid_case in invalid_deco_max_ppo2_cases {
+            let config = BuehlmannConfig::new().with_deco_max_ppo2(invalid_case);
+            assert_eq!(
+                config.validate(),
+                Err(ConfigValidationErr::new(
+                    ConfigValidationErrorField::DecoMaxPpO2,
+                    ConfigValidationErrorReason::DECO_MAX_PPO2_ERR_MSG
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_ndl_config() {
+        let config = BuehlmannConfig::new()
+            .with_ndl_type(NDLType::Actual)
+            .with_ndl_cut_off(Time::from_minutes(200.));
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.ndl_type(), NDLType::Actual);
+        assert_eq!(config.ndl_cut_off, Time::from_minutes(200.));
+    }
+
+    #[test]
+    fn test_ndl_type_derived_from_ceiling_type() {
+        let adaptive = BuehlmannConfig::new().with_ceiling_type(CeilingType::Adaptive);
+        assert_eq!(adaptive.ndl_type(), NDLType::Actual);
+        assert_eq!(
+            adaptive.with_ndl_type(NDLType::ByCeiling).ndl_type(),
+            NDLType::ByCeiling
+        );
+        let actual = BuehlmannConfig::new().with_ceiling_type(CeilingType::Actual);
+        assert_eq!(actual.ndl_type(), NDLType::ByCeiling);
+    }
+
+    #[test]
+    fn test_invalid_ndl_cut_off_values() {
+        let invalid_ndl_cut_off_cases = [-1., 0., 0.5, 1000.];
+        for invalid_case in invalid_ndl_cut_off_cases {
+            let config = BuehlmannConfig::new().with_ndl_cut_off(Time::from_minutes(invalid_case));
+            assert_eq!(
+                config.validate(),
+                Err(ConfigValidationErr::new(
+                    ConfigValidationErrorField::NdlCutOff,
+                    ConfigValidationErrorReason::NDL_CUT_OFF_ERR_MSG
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_safety_stop_values() {
+        let m = Depth::from_meters;
+        let invalid_safety_stop_cases = [
+            SafetyStopConfig::new().with_stop_depth(m(5.), (m(0.), m(6.))),
+            SafetyStopConfig::new().with_stop_depth(m(7.), (m(3.), m(6.))),
+            SafetyStopConfig::new().with_trigger_depth(m(5.)),
+            SafetyStopConfig::new().with_duration(Time::zero()),
+            SafetyStopConfig::new().with_duration(Time::from_minutes(31.)),
+        ];
+        for invalid_case in invalid_safety_stop_cases {
+            let config = BuehlmannConfig::new().with_safety_stop(invalid_case);
+            assert_eq!(
+                config.validate(),
+                Err(ConfigValidationErr::new(
+                    ConfigValidationErrorField::SafetyStop,
+                    ConfigValidationErrorReason::SAFETY_STOP_ERR_MSG
+                ))
+            );
+        }
+    }
 }
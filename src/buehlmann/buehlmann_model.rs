@@ -1,15 +1,19 @@
 use crate::buehlmann::buehlmann_config::BuehlmannConfig;
-use crate::buehlmann::compartment::{Compartment, Supersaturation};
+use crate::buehlmann::compartment::{
+    Compartment, CompartmentLoad, CompartmentSupersaturation, MValueLine, Supersaturation,
+};
 use crate::buehlmann::zhl_values::{ZHLParams, ZHL_16C_N2_16A_HE_VALUES};
 use crate::common::{
-    AscentRatePerMinute, Cns, ConfigValidationErr, ConfigValidationErrorReason, ConfigValidationErrorField, Deco, DecoModel, DecoModelConfig, Depth,
-    DiveState, Gas, GradientFactor, OxTox, RecordData,
+    math, Cns, ConfigValidationErr, Deco, DecoModel, DecoModelConfig, DecoStageBuffer, Depth,
+    DiveState, Float, Gas, GasDensity, GradientFactor, OxTox, RecordData, SafetyStop,
+    SafetyStopState,
+};
+use crate::{
+    CeilingType, DecoCalculationError, DecoRuntime, DecoStage, GradientFactors, NDLType, Sim, Time,
+    MAX_DECO_STAGE,
 };
-use crate::{CeilingType, DecoCalculationError, DecoRuntime, GradientFactors, Sim, Time};
 use core::cmp::Ordering;
 
-const NDL_CUT_OFF_MINS: u8 = 99;
-
 #[derive(Clone, Debug)]
 pub struct BuehlmannModel {
     pub config: BuehlmannConfig,
@@ -25,6 +29,7 @@ pub struct BuehlmannState {
     gas: Gas,
     gf_low_depth: Option<Depth>,
     ox_tox: OxTox,
+    safety_stop: SafetyStop,
 }
 
 impl Default for BuehlmannState {
@@ -35,12 +40,33 @@ impl Default for BuehlmannState {
             gas: Gas::air(),
             gf_low_depth: None,
             ox_tox: OxTox::default(),
+            safety_stop: SafetyStop::default(),
         }
     }
 }
 
+/// model state checkpoint (compartments loading and dive state)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BuehlmannCheckpoint {
+    loads: [CompartmentLoad; 16],
+    state: BuehlmannState,
+}
+
+impl BuehlmannCheckpoint {
+    /// model time at checkpoint
+    pub fn time(&self) -> Time {
+        self.state.time
+    }
+
+    /// compartments loading at checkpoint
+    pub fn loads(&self) -> [CompartmentLoad; 16] {
+        self.loads
+    }
+}
+
 impl DecoModel for BuehlmannModel {
     type ConfigType = BuehlmannConfig;
+    type Checkpoint = BuehlmannCheckpoint;
 
     // initialize with default config
     fn default() -> Self {
@@ -48,11 +74,9 @@ impl DecoModel for BuehlmannModel {
     }
 
     /// initialize new Buehlmann (ZH-L16C) model with gradient factors
-    fn new(config: BuehlmannConfig) -> Self {
+    fn try_new(config: BuehlmannConfig) -> Result<Self, ConfigValidationErr> {
         // validate config
-        if let Err(e) = config.validate() {
-            panic!("Config error [{:?}]: {:?}", e.field, e.reason);
-        }
+        config.validate()?;
         // air as a default init gas
         let initial_model_state = BuehlmannState::default();
         let mut model = Self {
@@ -63,23 +87,39 @@ impl DecoModel for BuehlmannModel {
         };
         model.create_compartments(ZHL_16C_N2_16A_HE_VALUES, config);
 
-        model
+        Ok(model)
     }
 
     /// record data: depth (meters), time (seconds), gas
-    fn record(&mut self, depth: Depth, time: Time, gas: &Gas) {
-        self.validate_depth(depth);
+    fn try_record(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        self.validate_depth(depth)?;
+        self.validate_time(time)?;
+        self.validate_gas(gas)?;
         self.state.depth = depth;
         self.state.gas = *gas;
         self.state.time += time;
         let record = RecordData { depth, time, gas };
         self.recalculate(record);
+
+        Ok(())
     }
 
     /// model travel between depths in 1s intervals
     // @todo: Schreiner equation instead of Haldane to avoid imprecise intervals
-    fn record_travel(&mut self, target_depth: Depth, time: Time, gas: &Gas) {
-        self.validate_depth(target_depth);
+    fn try_record_travel(
+        &mut self,
+        target_depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        self.validate_depth(target_depth)?;
+        self.validate_time(time)?;
+        self.validate_gas(gas)?;
         self.state.gas = *gas;
         let mut current_depth = self.state.depth;
         let distance = target_depth - current_depth;
@@ -100,43 +140,46 @@ impl DecoModel for BuehlmannModel {
 
         // align with target depth with lost precision @todo: round / bignumber?
         self.state.depth = target_depth;
-    }
 
-    fn record_travel_with_rate(
-        &mut self,
-        target_depth: Depth,
-        // @todo ascent rate units
-        rate: AscentRatePerMinute,
-        gas: &Gas,
-    ) {
-        let mut distance = (target_depth - self.state.depth).as_meters();
-        if(distance < 0.) {
-            distance = -distance;
-        }
-        self.record_travel(target_depth, Time::from_seconds(distance / rate * 60.), gas);
+        Ok(())
     }
 
     fn ndl(&self) -> Time {
-        let mut ndl = Time::from_minutes(NDL_CUT_OFF_MINS.into());
-
-        if self.in_deco() {
+        // simulation model based on current model's state
+        let sim_model = self.fork();
+        if sim_model.ndl_exceeded() {
             return Time::zero();
         }
 
-        // create a simulation model based on current model's state
-        let mut sim_model = self.fork();
+        // constant depth and gas for given number of seconds, rolled back to checkpoint for each probe
+        let checkpoint = sim_model.checkpoint();
+        let mut sim_model = sim_model;
+        let mut ndl_exceeded_after = |seconds: u32| {
+            sim_model.restore(&checkpoint);
+            sim_model.record(
+                self.state.depth,
+                Time::from_seconds(seconds as Float),
+                &self.state.gas,
+            );
+            sim_model.ndl_exceeded()
+        };
+
+        let cut_off = math::floor(self.config.ndl_cut_off.as_seconds()) as u32;
+        if !ndl_exceeded_after(cut_off) {
+            return self.config.ndl_cut_off;
+        }
 
-        // iterate simulation model over 1min records until NDL cut-off or in deco
-        let interval = Time::from_minutes(1.);
-        for i in 0..NDL_CUT_OFF_MINS {
-            // @todo
-            sim_model.record(self.state.depth, interval, &self.state.gas);
-            if sim_model.in_deco() {
-                ndl = interval * i;
-                break;
+        // bisect to the last second without NDL exceeded
+        let (mut within, mut exceeded) = (0, cut_off);
+        while exceeded - within > 1 {
+            let mid = within + (exceeded - within) / 2;
+            if ndl_exceeded_after(mid) {
+                exceeded = mid;
+            } else {
+                within = mid;
             }
         }
-        ndl
+        Time::from_seconds(within as Float)
     }
 
     fn ceiling(&self) -> Depth {
@@ -160,18 +203,21 @@ impl DecoModel for BuehlmannModel {
                     let sim_depth = sim_model.dive_state().depth;
                     let sim_depth_cmp = sim_depth.partial_cmp(&Depth::zero());
                     let sim_depth_at_surface = match sim_depth_cmp {
-                        Some(Ordering::Equal | Ordering::Less) => true,
                         Some(Ordering::Greater) => false,
-                        None => panic!("Simulation depth incomparable to surface"),
+                        // incomparable simulation depth treated as surface
+                        Some(Ordering::Equal | Ordering::Less) | None => true,
                     };
                     if sim_depth_at_surface || sim_depth <= calculated_ceiling {
                         break;
                     }
-                    sim_model.record_travel_with_rate(
+                    let travel = sim_model.try_record_travel_with_rate(
                         calculated_ceiling,
                         deco_ascent_rate,
                         &sim_gas,
                     );
+                    if travel.is_err() {
+                        break;
+                    }
                     calculated_ceiling = sim_model.ceiling();
                 }
                 calculated_ceiling
@@ -179,17 +225,33 @@ impl DecoModel for BuehlmannModel {
         };
 
         if self.config().round_ceiling() {
-            ceiling = Depth::from_meters(libm::ceil(ceiling.as_meters() as f64) as f32);
+            ceiling = Depth::from_meters(math::ceil(ceiling.as_meters()));
         }
 
         ceiling
     }
 
-    fn deco(&self, gas_mixes: [Gas; crate::common::MAX_GASSES]) -> Result<DecoRuntime, DecoCalculationError> {
-        let mut deco = Deco::default();
+    /// GF slope point between GF low depth and the surface (GF high if not in deco)
+    fn gradient_factor_at(&self, depth: Depth) -> GradientFactor {
+        let mut sim_model = self.fork();
+        sim_model.max_gf(self.config.gf, depth)
+    }
+
+    fn deco_with_buffer<S: DecoStageBuffer>(
+        &self,
+        gas_mixes: &[Gas],
+    ) -> Result<DecoRuntime<S>, DecoCalculationError> {
+        let mut deco = Deco::<S>::default();
         deco.calc(self.fork(), gas_mixes)
     }
 
+    fn in_deco(&self) -> bool {
+        match self.config.ceiling_type() {
+            CeilingType::Actual => self.ceiling() > Depth::zero(),
+            CeilingType::Adaptive => self.deco_obligation(),
+        }
+    }
+
     fn config(&self) -> BuehlmannConfig {
         self.config
     }
@@ -217,6 +279,24 @@ impl DecoModel for BuehlmannModel {
     fn otu(&self) -> Cns {
         self.state.ox_tox.otu()
     }
+
+    fn safety_stop(&self) -> SafetyStopState {
+        self.state.safety_stop.state()
+    }
+
+    fn checkpoint(&self) -> BuehlmannCheckpoint {
+        BuehlmannCheckpoint {
+            loads: self.compartments.map(|comp| comp.load()),
+            state: self.state,
+        }
+    }
+
+    fn restore(&mut self, checkpoint: &BuehlmannCheckpoint) {
+        for (compartment, load) in self.compartments.iter_mut().zip(checkpoint.loads.iter()) {
+            compartment.set_load(load);
+        }
+        self.state = checkpoint.state;
+    }
 }
 
 impl Sim for BuehlmannModel {
@@ -253,8 +333,37 @@ impl BuehlmannModel {
         }
     }
 
+    /// supersaturation (GF99, GFsurf) and M-values (raw, GF adjusted) at current ambient pressure for each compartment
+    pub fn compartments_supersaturation(&self) -> [CompartmentSupersaturation; 16] {
+        self.compartments.map(|comp| {
+            comp.supersaturation_details(self.config.surface_pressure, self.state.depth)
+        })
+    }
+
+    /// M-value lines (raw, adjusted to gradient factor in effect at given depth) for each compartment
+    pub fn m_value_lines(&self, depth: Depth) -> [(MValueLine, MValueLine); 16] {
+        let gf = self.gradient_factor_at(depth);
+        self.compartments
+            .map(|comp| (comp.m_value_line(100), comp.m_value_line(gf)))
+    }
+
+    /// current breathing gas density (g/L)
+    pub fn gas_density(&self) -> GasDensity {
+        self.state
+            .gas
+            .density(self.state.depth, self.config.surface_pressure)
+    }
+
+    /// current breathing gas density exceeds configured max gas density
+    pub fn gas_density_exceeded(&self) -> bool {
+        match self.config.max_gas_density {
+            Some(max_gas_density) => self.gas_density() > max_gas_density,
+            None => false,
+        }
+    }
+
     pub fn tissues(&self) -> [Compartment; 16] {
-        self.compartments.clone()
+        self.compartments
     }
 
     pub fn update_config(
@@ -266,10 +375,47 @@ impl BuehlmannModel {
         Ok(())
     }
 
+    // tissues at equilibrium with gas breathed at depth (saturation)
+    pub(crate) fn saturate(&mut self, depth: Depth, gas: &Gas) -> Result<(), DecoCalculationError> {
+        self.validate_depth(depth)?;
+        for compartment in self.compartments.iter_mut() {
+            compartment.saturate(depth, gas, self.config.surface_pressure);
+        }
+        // tolerable pressures for saturated tissues
+        self.try_record(depth, Time::zero(), gas)
+    }
+
+    // NDL condition by configured NDL type, expects a simulation model (actual ceiling)
+    fn ndl_exceeded(&self) -> bool {
+        match self.config.ndl_type() {
+            NDLType::ByCeiling => self.ceiling() > Depth::zero(),
+            NDLType::Actual => self.deco_obligation(),
+        }
+    }
+
+    // deco obligation on direct ascent with current gas, falls back to ceiling if deco runtime
+    // can't be calculated
+    fn deco_obligation(&self) -> bool {
+        match self.probe_deco() {
+            Ok(runtime) => runtime.is_deco(),
+            // more stages than a no-deco ascent takes
+            Err(DecoCalculationError::StageBufferOverflow) => true,
+            Err(_) => self.ceiling() > Depth::zero(),
+        }
+    }
+
+    // deco runtime with current gas for internal probes (simulation deco, no TTS @+5)
+    fn probe_deco(&self) -> Result<DecoRuntime<[DecoStage; MAX_DECO_STAGE]>, DecoCalculationError> {
+        let mut deco = Deco::new_sim();
+        deco.calc(self.fork(), &[self.state.gas])
+    }
+
     fn leading_comp(&self) -> &Compartment {
         let mut leading_comp: &Compartment = &self.compartments[0];
         for compartment in &self.compartments[1..] {
-            if compartment.min_tolerable_amb_pressure > leading_comp.min_tolerable_amb_pressure {
+            if compartment.load.min_tolerable_amb_pressure
+                > leading_comp.load.min_tolerable_amb_pressure
+            {
                 leading_comp = compartment;
             }
         }
@@ -281,8 +427,8 @@ impl BuehlmannModel {
         let comps = &mut self.compartments;
         let mut leading_comp_index = 0;
         for (i, compartment) in comps.iter().enumerate().skip(1) {
-            if compartment.min_tolerable_amb_pressure
-                > comps[leading_comp_index].min_tolerable_amb_pressure
+            if compartment.load.min_tolerable_amb_pressure
+                > comps[leading_comp_index].load.min_tolerable_amb_pressure
             {
                 leading_comp_index = i;
             }
@@ -302,9 +448,10 @@ impl BuehlmannModel {
 
     fn recalculate(&mut self, record: RecordData) {
         self.recalculate_compartments(&record);
-        if !self.is_sim() {
-            self.recalculate_ox_tox(&record);
-        }
+        self.state
+            .safety_stop
+            .record(&self.config.safety_stop, record.depth, record.time);
+        self.recalculate_ox_tox(&record);
     }
 
     fn recalculate_compartments(&mut self, record: &RecordData) {
@@ -405,15 +552,34 @@ impl BuehlmannModel {
         depth: Depth,
     ) -> GradientFactor {
         let (gf_low, gf_high) = gf;
-        let slope_point: f32 = gf_high as f32
-            - (((gf_high - gf_low) as f32) / gf_low_depth.as_meters()) * depth.as_meters();
+        let slope_point: Float = gf_high as Float
+            - (((gf_high - gf_low) as Float) / gf_low_depth.as_meters()) * depth.as_meters();
 
-        slope_point as u8
+        // depths representation error (fixed-point) absorbed before truncating
+        (slope_point + 1e-4) as u8
+    }
+
+    fn validate_depth(&self, depth: Depth) -> Result<(), DecoCalculationError> {
+        // negative or NaN depth
+        match depth.partial_cmp(&Depth::zero()) {
+            Some(Ordering::Greater | Ordering::Equal) => Ok(()),
+            _ => Err(DecoCalculationError::InvalidDepth),
+        }
+    }
+
+    fn validate_time(&self, time: Time) -> Result<(), DecoCalculationError> {
+        // negative, NaN or infinite time
+        match time.as_seconds().is_finite() && time >= Time::zero() {
+            true => Ok(()),
+            false => Err(DecoCalculationError::InvalidTime),
+        }
     }
 
-    fn validate_depth(&self, depth: Depth) {
-        if depth < Depth::zero() {
-            panic!("Invalid depth [{}]", depth);
+    fn validate_gas(&self, gas: &Gas) -> Result<(), DecoCalculationError> {
+        // uninitialized gas (eg. Gas::default())
+        match gas.is_valid() {
+            true => Ok(()),
+            false => Err(DecoCalculationError::InvalidGas),
         }
     }
 }
@@ -423,6 +589,7 @@ mod tests {
     // use alloc::string::ToString;
 
     use super::*;
+    use crate::common::{ConfigValidationErrorField, ConfigValidationErrorReason};
 
     #[test]
     fn test_state() {
@@ -438,6 +605,18 @@ mod tests {
         assert_ne!(model.state.ox_tox, OxTox::default());
     }
 
+    #[test]
+    fn test_sim_ox_tox() {
+        let mut model = BuehlmannModel::new(BuehlmannConfig::default());
+        let mut sim_model = model.fork();
+        let ean32 = Gas::new(0.32, 0.);
+        model.record(Depth::from_meters(30.), Time::from_minutes(30.), &ean32);
+        sim_model.record(Depth::from_meters(30.), Time::from_minutes(30.), &ean32);
+        assert!(sim_model.cns() > 0.);
+        assert_eq!(sim_model.cns(), model.cns());
+        assert_eq!(sim_model.otu(), model.otu());
+    }
+
     #[test]
     fn test_max_gf_within_ndl() {
         let gf = (50, 100);
@@ -504,10 +683,11 @@ mod tests {
         fn extract_supersaturations(model: BuehlmannModel) -> [Supersaturation; 16] {
             let mut supersaturations: [Supersaturation; 16] = [Supersaturation::default(); 16];
             for (i, comp) in model.compartments.iter().enumerate() {
-                supersaturations[i] = comp.supersaturation(model.config().surface_pressure, Depth::zero());
+                supersaturations[i] =
+                    comp.supersaturation(model.config().surface_pressure, Depth::zero());
             }
 
-            return supersaturations;
+            supersaturations
         }
 
         let model_initial = BuehlmannModel::default();
@@ -561,4 +741,27 @@ mod tests {
         let ndl = model.ndl();
         assert_eq!(ndl, Time::zero());
     }
+
+    #[test]
+    fn test_probe_deco_without_tts_at_5() {
+        let mut model = BuehlmannModel::new(
+            BuehlmannConfig::default()
+                .with_ndl_type(NDLType::Actual)
+                .with_ceiling_type(CeilingType::Adaptive),
+        );
+        let air = Gas::air();
+        model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+
+        // NDL / in deco probes skip TTS @+5 calculated for a regular deco runtime
+        let probe = model.probe_deco().unwrap();
+        assert!(probe.is_deco());
+        assert_eq!(probe.tts_at_5, Time::zero());
+        let runtime = model
+            .deco_with_buffer::<[DecoStage; MAX_DECO_STAGE]>(&[air])
+            .unwrap();
+        assert_eq!(probe.tts, runtime.tts);
+        assert!(runtime.tts_at_5 > probe.tts);
+        assert_eq!(model.ndl(), Time::zero());
+        assert!(model.in_deco());
+    }
 }
@@ -1,53 +1,72 @@
-use super::zhl_values::{ZHLParam, ZHLParams};
+use super::zhl_values::ZHLParams;
 use crate::{
     common::{
-        Depth, GradientFactor, InertGas, MbarPressure, PartialPressures, Pressure, RecordData,
+        real::{self, Real},
+        Depth, Float, GradientFactor, MbarPressure, Pressure, RecordData,
     },
     BuehlmannConfig, Gas, Time,
 };
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug)]
 pub struct Compartment {
     // tissue number
     pub no: u8,
-    // tolerable tissue ambient pressure
-    pub min_tolerable_amb_pressure: Pressure,
-    // helium saturation pressure
-    pub he_ip: Pressure,
-    // nitrogen saturation pressure
-    pub n2_ip: Pressure,
-    // total inert gas pressure (He + N2)
-    pub total_ip: Pressure,
-    // M-value (original)
-    pub m_value_raw: Pressure,
-    // M-value (calculated considering gradient factors)
-    pub m_value_calc: Pressure,
     // compartment'a Buehlmann params (N2 half time, n2 'a' coefficient, n2 'b' coefficient, He half time, ..)
     pub params: ZHLParams,
     // Buehlmann model config (gradient factors, surface pressure)
     pub model_config: BuehlmannConfig,
+    // loading state (inert gasses pressures, M-values, tolerable pressure)
+    pub(crate) load: CompartmentLoad,
+    // params in numeric core type
+    real_params: RealZHLParams,
+    // precomputed per second loading factors (N2, He) for params half-times
+    #[cfg(feature = "fixed-point")]
+    haldane_factors: (u64, u64),
+    // loading decays (N2, He) precomputed for last record's interval
+    #[cfg(feature = "fixed-point")]
+    haldane_decays: (Time, u64, u64),
 }
 
+// compartments equal by params and loading state, regardless of precomputed values
+impl PartialEq for Compartment {
+    fn eq(&self, other: &Self) -> bool {
+        self.no == other.no
+            && self.params == other.params
+            && self.model_config == other.model_config
+            && self.load == other.load
+    }
+}
+
+// (N2 half time, N2 a, N2 b, He half time, He a, He b)
+type RealZHLParams = (Real, Real, Real, Real, Real, Real);
+
 impl Default for Compartment {
     fn default() -> Self {
         Self {
             no: 0,
-            min_tolerable_amb_pressure: 0.,
-            he_ip: 0.,
-            n2_ip: 0.,
-            total_ip: 0.,
-            m_value_raw: 0.,
-            m_value_calc: 0.,
             params: (0., 0., 0., 0., 0., 0.),
             model_config: BuehlmannConfig::default(),
+            load: CompartmentLoad::default(),
+            real_params: (
+                real::zero(),
+                real::zero(),
+                real::zero(),
+                real::zero(),
+                real::zero(),
+                real::zero(),
+            ),
+            #[cfg(feature = "fixed-point")]
+            haldane_factors: (0, 0),
+            #[cfg(feature = "fixed-point")]
+            haldane_decays: (Time::zero(), 0, 0),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Supersaturation {
-    pub gf_99: f32,
-    pub gf_surf: f32,
+    pub gf_99: Float,
+    pub gf_surf: Float,
 }
 
 impl Default for Supersaturation {
@@ -59,36 +78,182 @@ impl Default for Supersaturation {
     }
 }
 
+/// compartment loading state (inert gasses pressures, M-values, tolerable pressure)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CompartmentLoad {
+    pub(crate) he_ip: Real,
+    pub(crate) n2_ip: Real,
+    pub(crate) total_ip: Real,
+    pub(crate) m_value_raw: Real,
+    pub(crate) m_value_calc: Real,
+    pub(crate) min_tolerable_amb_pressure: Real,
+}
+
+impl Default for CompartmentLoad {
+    fn default() -> Self {
+        Self {
+            he_ip: real::zero(),
+            n2_ip: real::zero(),
+            total_ip: real::zero(),
+            m_value_raw: real::zero(),
+            m_value_calc: real::zero(),
+            min_tolerable_amb_pressure: real::zero(),
+        }
+    }
+}
+
+impl CompartmentLoad {
+    /// helium saturation pressure
+    pub fn he_ip(&self) -> Pressure {
+        real::to_float(self.he_ip)
+    }
+
+    /// nitrogen saturation pressure
+    pub fn n2_ip(&self) -> Pressure {
+        real::to_float(self.n2_ip)
+    }
+
+    /// total inert gas pressure (He + N2)
+    pub fn total_ip(&self) -> Pressure {
+        real::to_float(self.total_ip)
+    }
+
+    /// M-value (original)
+    pub fn m_value_raw(&self) -> Pressure {
+        real::to_float(self.m_value_raw)
+    }
+
+    /// M-value (calculated considering gradient factors)
+    pub fn m_value_calc(&self) -> Pressure {
+        real::to_float(self.m_value_calc)
+    }
+
+    /// tolerable tissue ambient pressure
+    pub fn min_tolerable_amb_pressure(&self) -> Pressure {
+        real::to_float(self.min_tolerable_amb_pressure)
+    }
+}
+
+/// per-compartment supersaturation and M-values at current ambient pressure
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CompartmentSupersaturation {
+    // tissue number
+    pub no: u8,
+    pub gf_99: Float,
+    pub gf_surf: Float,
+    // total inert gas pressure (He + N2)
+    pub total_ip: Pressure,
+    pub ambient_pressure: Pressure,
+    // M-value (original)
+    pub m_value_raw: Pressure,
+    // M-value (calculated considering gradient factors)
+    pub m_value_calc: Pressure,
+}
+
+/// M-value line, max tolerated tissue inert gas pressure: a + ambient pressure / b
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MValueLine {
+    pub a: Pressure,
+    pub b: Pressure,
+}
+
+impl MValueLine {
+    /// M-value at given ambient pressure (bar)
+    pub fn m_value(&self, ambient_pressure: Pressure) -> Pressure {
+        self.a + (ambient_pressure / self.b)
+    }
+
+    /// M-value at given depth
+    pub fn m_value_at_depth(&self, depth: Depth, surface_pressure: MbarPressure) -> Pressure {
+        let p_amb = (surface_pressure as Float) / 1000. + (depth.as_meters() / 10.);
+        self.m_value(p_amb)
+    }
+}
+
+// absolute pressure (bar) at depth given surface pressure
+fn ambient_pressure(depth: Depth, surface_pressure: MbarPressure) -> Real {
+    surface_pressure_bar(surface_pressure) + depth.as_real_meters() / real::from_int(10)
+}
+
+fn surface_pressure_bar(surface_pressure: MbarPressure) -> Real {
+    real::from_int(surface_pressure as i32) / real::from_int(1000)
+}
+
 impl Compartment {
     pub fn new(no: u8, params: ZHLParams, model_config: BuehlmannConfig) -> Self {
         let init_gas = Gas::air();
-        let init_gas_compound_pressures =
-            init_gas.inspired_partial_pressures(Depth::zero(), model_config.surface_pressure);
-        let n2_ip = init_gas_compound_pressures.n2;
-        let he_ip = init_gas_compound_pressures.he;
+        let (_, n2_ip, he_ip) =
+            init_gas.inspired_partial_pressures_real(Depth::zero(), model_config.surface_pressure);
+        let (n2_half_time, n2_a, n2_b, he_half_time, he_a, he_b) = params;
 
         let mut compartment = Self {
             no,
             params,
-            n2_ip,
-            he_ip,
-            total_ip: he_ip + n2_ip,
-            m_value_raw: 0.,  // initial, recalculated later
-            m_value_calc: 0., // initial, recalculated later
-            min_tolerable_amb_pressure: 0.,
             model_config,
+            load: CompartmentLoad {
+                he_ip,
+                n2_ip,
+                total_ip: he_ip + n2_ip,
+                ..CompartmentLoad::default()
+            },
+            real_params: (
+                real::from_float(n2_half_time),
+                real::from_float(n2_a),
+                real::from_float(n2_b),
+                real::from_float(he_half_time),
+                real::from_float(he_a),
+                real::from_float(he_b),
+            ),
+            #[cfg(feature = "fixed-point")]
+            haldane_factors: (
+                super::fixed_point::per_second_factor(n2_half_time),
+                super::fixed_point::per_second_factor(he_half_time),
+            ),
+            #[cfg(feature = "fixed-point")]
+            haldane_decays: (Time::zero(), 0, 0),
         };
 
         // calculate initial minimal tolerable ambient pressure
         let (_, gf_high) = model_config.gf;
-        compartment.m_value_raw =
+        compartment.load.m_value_raw =
             compartment.m_value(Depth::zero(), model_config.surface_pressure, 100);
-        compartment.m_value_calc = compartment.m_value_raw;
-        compartment.min_tolerable_amb_pressure = compartment.min_tolerable_amb_pressure(gf_high);
+        compartment.load.m_value_calc = compartment.load.m_value_raw;
+        compartment.load.min_tolerable_amb_pressure =
+            compartment.calc_min_tolerable_amb_pressure(gf_high);
 
         compartment
     }
 
+    /// tolerable tissue ambient pressure
+    pub fn min_tolerable_amb_pressure(&self) -> Pressure {
+        self.load.min_tolerable_amb_pressure()
+    }
+
+    /// helium saturation pressure
+    pub fn he_ip(&self) -> Pressure {
+        self.load.he_ip()
+    }
+
+    /// nitrogen saturation pressure
+    pub fn n2_ip(&self) -> Pressure {
+        self.load.n2_ip()
+    }
+
+    /// total inert gas pressure (He + N2)
+    pub fn total_ip(&self) -> Pressure {
+        self.load.total_ip()
+    }
+
+    /// M-value (original)
+    pub fn m_value_raw(&self) -> Pressure {
+        self.load.m_value_raw()
+    }
+
+    /// M-value (calculated considering gradient factors)
+    pub fn m_value_calc(&self) -> Pressure {
+        self.load.m_value_calc()
+    }
+
     // recalculate tissue inert gasses saturation and tolerable pressure
     pub fn recalculate(
         &mut self,
@@ -99,40 +264,98 @@ impl Compartment {
         let (he_inert_pressure, n2_inert_pressure) =
             self.compartment_inert_pressure(record, surface_pressure);
 
-        self.he_ip = he_inert_pressure;
-        self.n2_ip = n2_inert_pressure;
-        self.total_ip = he_inert_pressure + n2_inert_pressure;
+        self.load.he_ip = he_inert_pressure;
+        self.load.n2_ip = n2_inert_pressure;
+        self.load.total_ip = he_inert_pressure + n2_inert_pressure;
 
         // @todo m_value tuple
-        self.m_value_raw = self.m_value(record.depth, surface_pressure, 100);
-        self.m_value_calc = self.m_value(record.depth, surface_pressure, max_gf);
+        self.load.m_value_raw = self.m_value(record.depth, surface_pressure, 100);
+        self.load.m_value_calc = self.m_value(record.depth, surface_pressure, max_gf);
+
+        self.load.min_tolerable_amb_pressure = self.calc_min_tolerable_amb_pressure(max_gf);
+    }
 
-        self.min_tolerable_amb_pressure = self.min_tolerable_amb_pressure(max_gf);
+    // current loading state
+    pub fn load(&self) -> CompartmentLoad {
+        self.load
+    }
+
+    // restore loading state
+    pub fn set_load(&mut self, load: &CompartmentLoad) {
+        self.load = *load;
+    }
+
+    // tissues at equilibrium with gas breathed at depth, tolerable pressure recalculated on next record
+    pub(crate) fn saturate(&mut self, depth: Depth, gas: &Gas, surface_pressure: MbarPressure) {
+        let (_, n2_ip, he_ip) = gas.inspired_partial_pressures_real(depth, surface_pressure);
+        self.load.he_ip = he_ip;
+        self.load.n2_ip = n2_ip;
+        self.load.total_ip = he_ip + n2_ip;
     }
 
     // tissue ceiling as depth
     pub fn ceiling(&self) -> Depth {
-        let mut ceil = (self.min_tolerable_amb_pressure
-            - (self.model_config.surface_pressure as f32 / 1000.))
-            * 10.;
+        let mut ceil = (self.load.min_tolerable_amb_pressure
+            - surface_pressure_bar(self.model_config.surface_pressure))
+            * real::from_int(10);
         // cap ceiling at 0 if min tolerable leading compartment pressure depth equivalent negative
-        if ceil < 0. {
-            ceil = 0.;
+        if ceil < real::zero() {
+            ceil = real::zero();
         }
 
-        Depth::from_meters(ceil)
+        Depth::from_real_meters(ceil)
     }
 
     // tissue supersaturation (gf99, surface gf)
     pub fn supersaturation(&self, surface_pressure: MbarPressure, depth: Depth) -> Supersaturation {
-        let p_surf = (surface_pressure as f32) / 1000.;
-        let p_amb = p_surf + (depth.as_meters() / 10.);
-        let m_value = self.m_value_raw;
+        let p_surf = surface_pressure_bar(surface_pressure);
+        let p_amb = ambient_pressure(depth, surface_pressure);
+        let m_value = self.load.m_value_raw;
         let m_value_surf = self.m_value(Depth::zero(), surface_pressure, 100);
-        let gf_99 = ((self.total_ip - p_amb) / (m_value - p_amb)) * 100.;
-        let gf_surf = ((self.total_ip - p_surf) / (m_value_surf - p_surf)) * 100.;
+        let hundred = real::from_int(100);
+        let gf_99 = ((self.load.total_ip - p_amb) / (m_value - p_amb)) * hundred;
+        let gf_surf = ((self.load.total_ip - p_surf) / (m_value_surf - p_surf)) * hundred;
+
+        Supersaturation {
+            gf_99: real::to_float(gf_99),
+            gf_surf: real::to_float(gf_surf),
+        }
+    }
+
+    // tissue supersaturation and M-values at given depth
+    pub fn supersaturation_details(
+        &self,
+        surface_pressure: MbarPressure,
+        depth: Depth,
+    ) -> CompartmentSupersaturation {
+        let Supersaturation { gf_99, gf_surf } = self.supersaturation(surface_pressure, depth);
+
+        CompartmentSupersaturation {
+            no: self.no,
+            gf_99,
+            gf_surf,
+            total_ip: self.total_ip(),
+            ambient_pressure: real::to_float(ambient_pressure(depth, surface_pressure)),
+            m_value_raw: self.m_value_raw(),
+            m_value_calc: self.m_value_calc(),
+        }
+    }
+
+    // M-value line (a, b) weighted by current tissue inert gasses proportions, adjusted to given GF
+    pub fn m_value_line(&self, gf: GradientFactor) -> MValueLine {
+        let (a, b) = self.real_m_value_line(gf);
 
-        Supersaturation { gf_99, gf_surf }
+        MValueLine {
+            a: real::to_float(a),
+            b: real::to_float(b),
+        }
+    }
+
+    fn real_m_value_line(&self, gf: GradientFactor) -> (Real, Real) {
+        let weighted_zhl_params = self.weighted_zhl_params(self.load.he_ip, self.load.n2_ip);
+        let (_, a, b) = self.max_gf_adjusted_zhl_params(weighted_zhl_params, gf);
+
+        (a, b)
     }
 
     fn m_value(
@@ -140,98 +363,78 @@ impl Compartment {
         depth: Depth,
         surface_pressure: MbarPressure,
         max_gf: GradientFactor,
-    ) -> Pressure {
-        let weighted_zhl_params = self.weighted_zhl_params(self.he_ip, self.n2_ip);
-        let (_, a_coeff_adjusted, b_coeff_adjusted) =
-            self.max_gf_adjusted_zhl_params(weighted_zhl_params, max_gf);
-        let p_surf = (surface_pressure as f32) / 1000.;
-        let p_amb = p_surf + (depth.as_meters() / 10.);
-
-        a_coeff_adjusted + (p_amb / b_coeff_adjusted)
+    ) -> Real {
+        let (a, b) = self.real_m_value_line(max_gf);
+        a + (ambient_pressure(depth, surface_pressure) / b)
     }
 
     // tissue inert gasses pressure after record
     fn compartment_inert_pressure(
-        &self,
+        &mut self,
         record: &RecordData,
         surface_pressure: MbarPressure,
-    ) -> (Pressure, Pressure) {
+    ) -> (Real, Real) {
         // (he, n2)
         let RecordData { depth, time, gas } = record;
-        let PartialPressures {
-            n2: n2_pp,
-            he: he_pp,
-            ..
-        } = gas.inspired_partial_pressures(*depth, surface_pressure);
-
         // partial pressure of inert gases in inspired gas (adjusted alveoli water vapor pressure)
-        let he_inspired_pp = he_pp;
-        let n2_inspired = n2_pp;
-
-        // tissue saturation pressure change for inert gasses
-        let (n2_half_time, _, _, he_half_time, ..) = self.params;
-        let he_p_comp_delta = self.compartment_pressure_delta_haldane(
-            InertGas::Helium,
-            he_inspired_pp,
-            *time,
-            he_half_time,
-        );
-        let n2_p_comp_delta = self.compartment_pressure_delta_haldane(
-            InertGas::Nitrogen,
-            n2_inspired,
-            *time,
-            n2_half_time,
-        );
+        let (_, n2_inspired, he_inspired_pp) =
+            gas.inspired_partial_pressures_real(*depth, surface_pressure);
+
+        // tissue saturation pressure change for inert gasses (Pi - Po)(1 - e^(-0.693t/half-time))
+        let (he_loading_fraction, n2_loading_fraction) = self.loading_fractions(*time);
+        let he_p_comp_delta = (he_inspired_pp - self.load.he_ip) * he_loading_fraction;
+        let n2_p_comp_delta = (n2_inspired - self.load.n2_ip) * n2_loading_fraction;
 
         // inert gasses pressures after applying delta P
-        let he_final = self.he_ip + he_p_comp_delta;
-        let n2_final = self.n2_ip + n2_p_comp_delta;
+        let he_final = self.load.he_ip + he_p_comp_delta;
+        let n2_final = self.load.n2_ip + n2_p_comp_delta;
 
         (he_final, n2_final)
     }
 
-    // compartment pressure change for inert gas (Haldane equation)
-    fn compartment_pressure_delta_haldane(
-        &self,
-        inert_gas: InertGas,
-        gas_inspired_p: Pressure,
-        time: Time,
-        half_time: ZHLParam,
-    ) -> Pressure {
-        let inert_gas_load = match inert_gas {
-            InertGas::Helium => self.he_ip,
-            InertGas::Nitrogen => self.n2_ip,
-        };
+    // Haldane equation loading fractions (he, n2) for given time
+    #[cfg(not(feature = "fixed-point"))]
+    fn loading_fractions(&mut self, time: Time) -> (Real, Real) {
+        let (n2_half_time, _, _, he_half_time, ..) = self.real_params;
+        let minutes = time.as_real_minutes();
+        let one = real::from_int(1);
+        (
+            one - real::exp2(-minutes / he_half_time),
+            one - real::exp2(-minutes / n2_half_time),
+        )
+    }
+
+    // Haldane equation loading fractions (he, n2) for given time, decays reused for constant
+    // sample interval
+    #[cfg(feature = "fixed-point")]
+    fn loading_fractions(&mut self, time: Time) -> (Real, Real) {
+        use super::fixed_point::{haldane_decay, haldane_fraction};
+
+        let (interval, mut n2_decay, mut he_decay) = self.haldane_decays;
+        if time != interval || n2_decay == 0 {
+            let (n2_factor, he_factor) = self.haldane_factors;
+            n2_decay = haldane_decay(time, n2_factor);
+            he_decay = haldane_decay(time, he_factor);
+            self.haldane_decays = (time, n2_decay, he_decay);
+        }
 
-        // (Pi - Po)(1 - e^(-0.693t/half-time))
-        (gas_inspired_p - inert_gas_load) * (1. - (libm::powf(2.0, -(time.as_minutes()) / half_time)))
+        (haldane_fraction(he_decay), haldane_fraction(n2_decay))
     }
 
     // tissue tolerable ambient pressure using GF slope, weighted Buehlmann ZHL params based on tissue inert gasses saturation proportions
-    fn min_tolerable_amb_pressure(&self, max_gf: GradientFactor) -> Pressure {
-        let weighted_zhl_params = self.weighted_zhl_params(self.he_ip, self.n2_ip);
-        let (_, a_coefficient_adjusted, b_coefficient_adjusted) =
-            self.max_gf_adjusted_zhl_params(weighted_zhl_params, max_gf);
+    fn calc_min_tolerable_amb_pressure(&self, max_gf: GradientFactor) -> Real {
+        let (a_coefficient_adjusted, b_coefficient_adjusted) = self.real_m_value_line(max_gf);
 
-        (self.total_ip - a_coefficient_adjusted) * b_coefficient_adjusted
+        (self.load.total_ip - a_coefficient_adjusted) * b_coefficient_adjusted
     }
 
     // weighted ZHL params (half time, a coefficient, b coefficient) based on N2 and He params and inert gasses proportions in tissue
-    fn weighted_zhl_params(
-        &self,
-        he_pp: Pressure,
-        n2_pp: Pressure,
-    ) -> (ZHLParam, ZHLParam, ZHLParam) {
-        fn weighted_param(
-            he_param: ZHLParam,
-            he_pp: Pressure,
-            n2_param: ZHLParam,
-            n2_pp: Pressure,
-        ) -> ZHLParam {
+    fn weighted_zhl_params(&self, he_pp: Real, n2_pp: Real) -> (Real, Real, Real) {
+        fn weighted_param(he_param: Real, he_pp: Real, n2_param: Real, n2_pp: Real) -> Real {
             ((he_param * he_pp) + (n2_param * n2_pp)) / (he_pp + n2_pp)
         }
         let (n2_half_time, n2_a_coeff, n2_b_coeff, he_half_time, he_a_coeff, he_b_coeff) =
-            self.params;
+            self.real_params;
         (
             weighted_param(he_half_time, he_pp, n2_half_time, n2_pp),
             weighted_param(he_a_coeff, he_pp, n2_a_coeff, n2_pp),
@@ -242,11 +445,11 @@ impl Compartment {
     // adjust zhl params based on max gf
     fn max_gf_adjusted_zhl_params(
         &self,
-        params: (ZHLParam, ZHLParam, ZHLParam),
+        params: (Real, Real, Real),
         max_gf: GradientFactor,
-    ) -> (ZHLParam, ZHLParam, ZHLParam) {
+    ) -> (Real, Real, Real) {
         let (half_time, a_coeff, b_coeff) = params;
-        let max_gf_fraction = max_gf as f32 / 100.;
+        let max_gf_fraction = real::from_int(max_gf as i32) / real::from_int(100);
         let a_coefficient_adjusted = a_coeff * max_gf_fraction;
         let b_coefficient_adjusted =
             b_coeff / (max_gf_fraction - (max_gf_fraction * b_coeff) + b_coeff);
@@ -256,6 +459,7 @@ impl Compartment {
 }
 
 #[cfg(test)]
+#[allow(clippy::excessive_precision)]
 mod tests {
     use super::*;
     use crate::{common::Gas, Time};
@@ -273,21 +477,15 @@ mod tests {
     #[test]
     fn test_constructor() {
         let comp = comp_1();
-        assert_eq!(
-            comp,
-            Compartment {
-                no: 1,
-                min_tolerable_amb_pressure: -0.25712729,
-                he_ip: 0.0,
-                n2_ip: 0.750737,
-                total_ip: 0.750737,
-                m_value_raw: 3.265840594059406,
-                m_value_calc: 3.265840594059406,
-                params: (4.0, 1.2599, 0.505, 1.51, 1.7424, 0.4245),
-                // mocked config and state
-                model_config: BuehlmannConfig::default(),
-            }
-        );
+        assert_eq!(comp.no, 1);
+        assert_close_to_abs!(comp.min_tolerable_amb_pressure(), -0.25712731, 1e-6);
+        assert_eq!(comp.he_ip(), 0.0);
+        assert_close_to_abs!(comp.n2_ip(), 0.750737, 1e-6);
+        assert_close_to_abs!(comp.total_ip(), 0.750737, 1e-6);
+        assert_close_to_abs!(comp.m_value_raw(), 3.265840594059406, 1e-6);
+        assert_close_to_abs!(comp.m_value_calc(), 3.265840594059406, 1e-6);
+        assert_eq!(comp.params, (4.0, 1.2599, 0.505, 1.51, 1.7424, 0.4245));
+        assert_eq!(comp.model_config, BuehlmannConfig::default());
     }
 
     #[test]
@@ -302,8 +500,8 @@ mod tests {
         };
         comp_1.recalculate(&record, 100, 1000);
         comp_5.recalculate(&record, 100, 1000);
-        assert_eq!(comp_1.m_value_raw, 3.24009801980198);
-        assert_eq!(comp_5.m_value_raw, 1.8506177701206004);
+        assert_close_to_abs!(comp_1.m_value_raw(), 3.24009801980198, 1e-6);
+        assert_close_to_abs!(comp_5.m_value_raw(), 1.8506177701206004, 1e-6);
     }
 
     #[test]
@@ -318,8 +516,30 @@ mod tests {
         };
         comp_1.recalculate(&record, 70, 1000);
         comp_5.recalculate(&record, 70, 1000);
-        assert_eq!(comp_1.m_value_calc, 2.568068613861386);
-        assert_eq!(comp_5.m_value_calc, 1.5954324390844203);
+        assert_close_to_abs!(comp_1.m_value_calc(), 2.568068613861386, 1e-6);
+        assert_close_to_abs!(comp_5.m_value_calc(), 1.5954324390844203, 1e-6);
+    }
+
+    #[test]
+    fn test_m_value_line() {
+        let mut comp = comp_1();
+        let raw_line = comp.m_value_line(100);
+        assert_close_to_abs!(raw_line.a, 1.2599, 1e-6);
+        assert_close_to_abs!(raw_line.b, 0.5050, 1e-6);
+
+        let air = Gas::new(0.21, 0.);
+        let record = RecordData {
+            depth: Depth::zero(),
+            time: Time::from_seconds(1.),
+            gas: &air,
+        };
+        comp.recalculate(&record, 70, 1000);
+        assert_close_to_abs!(comp.m_value_line(100).m_value(1.), comp.m_value_raw(), 1e-6);
+        assert_close_to_abs!(
+            comp.m_value_line(70).m_value_at_depth(Depth::zero(), 1000),
+            comp.m_value_calc(),
+            1e-6
+        );
     }
 
     #[test]
@@ -332,17 +552,22 @@ mod tests {
             gas: &air,
         };
         comp.recalculate(&record, 100, 1000);
-        assert_eq!(comp.total_ip, 1.2850179204911072);
+        assert_close_to_abs!(comp.total_ip(), 1.2850179204911072, 1e-6);
     }
 
     #[test]
     fn test_weighted_params_trimix() {
         let comp = comp_1();
-        let weighted_params = comp.weighted_zhl_params(0.5, 1. - (0.18 + 0.5));
-        assert_eq!(
-            weighted_params,
-            (2.481707, 1.5541074, 0.45591462)
+        let (half_time, a_coeff, b_coeff) =
+            comp.weighted_zhl_params(real::from_float(0.5), real::from_float(1. - (0.18 + 0.5)));
+        let (half_time, a_coeff, b_coeff) = (
+            real::to_float(half_time),
+            real::to_float(a_coeff),
+            real::to_float(b_coeff),
         );
+        assert_close_to_abs!(half_time, 2.4817073, 1e-6);
+        assert_close_to_abs!(a_coeff, 1.5541073, 1e-6);
+        assert_close_to_abs!(b_coeff, 0.4559146, 1e-6);
     }
 
     #[test]
@@ -355,7 +580,7 @@ mod tests {
             gas: &air,
         };
         comp.recalculate(&recprd, 100, 100);
-        let min_tolerable_pressure = comp.min_tolerable_amb_pressure;
-        assert_eq!(min_tolerable_pressure, 0.40957972);
+        let min_tolerable_pressure = comp.min_tolerable_amb_pressure();
+        assert_close_to_abs!(min_tolerable_pressure, 0.4095797, 1e-6);
     }
 }
@@ -0,0 +1,363 @@
+use core::cmp::Ordering;
+
+use crate::buehlmann::{BuehlmannCheckpoint, BuehlmannModel, CompartmentLoad, Supersaturation};
+use crate::common::{
+    math, AscentRatePerMinute, DecoCalculationError, DecoModel, Depth, Gas, Pressure, Time,
+};
+
+// default snapshots capacity (1h 4min of 1 min snapshots, ~30 KB recorder with f32)
+pub const DEFAULT_HISTORY_CAPACITY: usize = 64;
+
+/// compartments loading, ceiling and supersaturation at given model time
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TissueSnapshot {
+    pub time: Time,
+    pub depth: Depth,
+    pub ceiling: Depth,
+    pub supersaturation: Supersaturation,
+    // model state to rewind to, compartments loading
+    checkpoint: Option<BuehlmannCheckpoint>,
+}
+
+impl Default for TissueSnapshot {
+    fn default() -> Self {
+        Self {
+            time: Time::zero(),
+            depth: Depth::zero(),
+            ceiling: Depth::zero(),
+            supersaturation: Supersaturation::default(),
+            checkpoint: None,
+        }
+    }
+}
+
+impl TissueSnapshot {
+    pub fn from_model(model: &BuehlmannModel) -> Self {
+        let state = model.dive_state();
+        Self {
+            time: state.time,
+            depth: state.depth,
+            ceiling: model.ceiling(),
+            supersaturation: model.supersaturation(),
+            checkpoint: Some(model.checkpoint()),
+        }
+    }
+
+    /// model state checkpoint at snapshot time
+    pub fn checkpoint(&self) -> Option<&BuehlmannCheckpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    /// helium loading by compartment
+    pub fn he_ip(&self) -> [Pressure; 16] {
+        self.inert_pressures(|load| load.he_ip())
+    }
+
+    /// nitrogen loading by compartment
+    pub fn n2_ip(&self) -> [Pressure; 16] {
+        self.inert_pressures(|load| load.n2_ip())
+    }
+
+    fn inert_pressures(&self, pressure: fn(CompartmentLoad) -> Pressure) -> [Pressure; 16] {
+        match &self.checkpoint {
+            Some(checkpoint) => checkpoint.loads().map(pressure),
+            None => [0.; 16],
+        }
+    }
+}
+
+/// bounded snapshots ring buffer, oldest snapshots overwritten when full
+#[derive(Clone, Debug, PartialEq)]
+pub struct TissueHistory<const N: usize = DEFAULT_HISTORY_CAPACITY> {
+    snapshots: [TissueSnapshot; N],
+    // oldest snapshot index
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for TissueHistory<N> {
+    fn default() -> Self {
+        Self {
+            snapshots: [TissueSnapshot::default(); N],
+            start: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<const N: usize> TissueHistory<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// snapshot by index, oldest first
+    pub fn get(&self, index: usize) -> Option<&TissueSnapshot> {
+        if index >= self.len {
+            return None;
+        }
+        Some(&self.snapshots[(self.start + index) % N])
+    }
+
+    pub fn first(&self) -> Option<&TissueSnapshot> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&TissueSnapshot> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// snapshots, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &TissueSnapshot> + '_ {
+        (0..self.len).filter_map(move |index| self.get(index))
+    }
+
+    /// add snapshot, oldest one overwritten if capacity exceeded
+    pub fn push(&mut self, snapshot: TissueSnapshot) {
+        if N == 0 {
+            return;
+        }
+        if self.len < N {
+            self.snapshots[(self.start + self.len) % N] = snapshot;
+            self.len += 1;
+        } else {
+            self.snapshots[self.start] = snapshot;
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    /// keep given number of oldest snapshots
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.len = len;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+}
+
+/// model records with tissue snapshots taken at configured interval of model time
+#[derive(Clone, Debug)]
+pub struct HistoryRecorder<const N: usize = DEFAULT_HISTORY_CAPACITY> {
+    model: BuehlmannModel,
+    history: TissueHistory<N>,
+    interval: Time,
+    last_snapshot: Time,
+}
+
+impl<const N: usize> HistoryRecorder<N> {
+    /// recorder with 1 min snapshots interval, initial snapshot of current model state
+    pub fn new(model: BuehlmannModel) -> Self {
+        let mut recorder = Self {
+            model,
+            history: TissueHistory::new(),
+            interval: Time::from_minutes(1.),
+            last_snapshot: Time::zero(),
+        };
+        recorder.snapshot();
+        recorder
+    }
+
+    /// snapshots interval (min 1s)
+    pub fn with_interval(mut self, interval: Time) -> Self {
+        self.interval = match interval < Time::from_seconds(1.) {
+            true => Time::from_seconds(1.),
+            false => interval,
+        };
+        self
+    }
+
+    pub fn model(&self) -> &BuehlmannModel {
+        &self.model
+    }
+
+    pub fn history(&self) -> &TissueHistory<N> {
+        &self.history
+    }
+
+    pub fn interval(&self) -> Time {
+        self.interval
+    }
+
+    /// recorded model and its tissue history, e.g. to continue with plain model records
+    pub fn into_inner(self) -> (BuehlmannModel, TissueHistory<N>) {
+        (self.model, self.history)
+    }
+
+    /// snapshot of current model state regardless of interval
+    pub fn snapshot(&mut self) {
+        let snapshot = TissueSnapshot::from_model(&self.model);
+        self.last_snapshot = snapshot.time;
+        self.history.push(snapshot);
+    }
+
+    /// constant depth record, split at snapshot times
+    pub fn record(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        let mut remaining = time;
+        loop {
+            let chunk = match remaining > self.until_snapshot() {
+                true => self.until_snapshot(),
+                false => remaining,
+            };
+            self.model.try_record(depth, chunk, gas)?;
+            remaining = remaining - chunk;
+            self.snapshot_if_due();
+            // non-comparable (NaN) time recorded once
+            match remaining.partial_cmp(&Time::zero()) {
+                Some(Ordering::Greater) => (),
+                _ => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// linear travel record given travel time, split at snapshot times
+    pub fn record_travel(
+        &mut self,
+        target_depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        // travel recorded in whole seconds
+        let travel_seconds = math::floor(time.as_seconds());
+        let start_depth = self.model.dive_state().depth;
+        let mut elapsed = 0.;
+        loop {
+            let until_snapshot = math::ceil(self.until_snapshot().as_seconds());
+            let mut chunk = travel_seconds - elapsed;
+            if chunk > until_snapshot {
+                chunk = until_snapshot;
+            }
+            elapsed += chunk;
+            let depth = match elapsed < travel_seconds {
+                true => start_depth + (target_depth - start_depth) * (elapsed / travel_seconds),
+                false => target_depth,
+            };
+            self.model
+                .try_record_travel(depth, Time::from_seconds(chunk), gas)?;
+            self.snapshot_if_due();
+            match elapsed.partial_cmp(&travel_seconds) {
+                Some(Ordering::Less) => (),
+                _ => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// linear travel record given travel rate, split at snapshot times
+    pub fn record_travel_with_rate(
+        &mut self,
+        target_depth: Depth,
+        rate: AscentRatePerMinute,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        if !(rate.is_finite() && rate > 0.) {
+            return Err(DecoCalculationError::InvalidTravelRate);
+        }
+        let mut distance = (target_depth - self.model.dive_state().depth).as_meters();
+        if distance < 0. {
+            distance = -distance;
+        }
+        self.record_travel(target_depth, Time::from_seconds(distance / rate * 60.), gas)
+    }
+
+    /// roll model back to the latest snapshot at or before given model time, later snapshots discarded.
+    /// Rewound model time returned, None if no such snapshot in history
+    pub fn rewind(&mut self, time: Time) -> Option<Time> {
+        let index = (0..self.history.len()).rev().find(
+            |index| matches!(self.history.get(*index), Some(snapshot) if snapshot.time <= time),
+        )?;
+        let checkpoint = *self.history.get(index)?.checkpoint()?;
+        self.model.restore(&checkpoint);
+        self.history.truncate(index + 1);
+        self.last_snapshot = checkpoint.time();
+
+        Some(checkpoint.time())
+    }
+
+    fn until_snapshot(&self) -> Time {
+        let next_snapshot = self.last_snapshot + self.interval;
+        let now = self.model.dive_state().time;
+        match next_snapshot > now {
+            true => next_snapshot - now,
+            false => Time::zero(),
+        }
+    }
+
+    fn snapshot_if_due(&mut self) {
+        if self.until_snapshot() <= Time::zero() {
+            self.snapshot();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot_at(seconds: crate::common::Float) -> TissueSnapshot {
+        TissueSnapshot {
+            time: Time::from_seconds(seconds),
+            ..TissueSnapshot::default()
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut history = TissueHistory::<3>::new();
+        assert!(history.is_empty());
+        assert_eq!(history.last(), None);
+        for seconds in [1., 2., 3., 4., 5.] {
+            history.push(snapshot_at(seconds));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.capacity(), 3);
+        let times: [Time; 3] = core::array::from_fn(|i| history.get(i).unwrap().time);
+        assert_eq!(
+            times,
+            [3., 4., 5.].map(Time::from_seconds),
+            "oldest snapshots overwritten"
+        );
+        assert_eq!(history.first().unwrap().time, Time::from_seconds(3.));
+        assert_eq!(history.last().unwrap().time, Time::from_seconds(5.));
+        assert_eq!(history.iter().count(), 3);
+        assert_eq!(history.get(3), None);
+
+        history.truncate(2);
+        assert_eq!(history.last().unwrap().time, Time::from_seconds(4.));
+        history.push(snapshot_at(6.));
+        assert_eq!(history.last().unwrap().time, Time::from_seconds(6.));
+        assert_eq!(history.first().unwrap().time, Time::from_seconds(3.));
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut history = TissueHistory::<0>::new();
+        history.push(snapshot_at(1.));
+        assert!(history.is_empty());
+    }
+}
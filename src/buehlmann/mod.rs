@@ -1,8 +1,21 @@
 mod buehlmann_config;
 mod buehlmann_model;
 mod compartment;
+#[cfg(feature = "fixed-point")]
+mod fixed_point;
+mod history;
+mod saturation;
+mod summary;
 mod zhl_values;
 
 pub use buehlmann_config::BuehlmannConfig;
-pub use buehlmann_model::BuehlmannModel;
-pub use compartment::{Compartment, Supersaturation};
\ No newline at end of file
+pub use buehlmann_model::{BuehlmannCheckpoint, BuehlmannModel};
+pub use compartment::{
+    Compartment, CompartmentLoad, CompartmentSupersaturation, MValueLine, Supersaturation,
+};
+pub use history::{HistoryRecorder, TissueHistory, TissueSnapshot, DEFAULT_HISTORY_CAPACITY};
+pub use saturation::{
+    RestPeriod, SatAscentRate, Saturation, SaturationConfig, SaturationError, SaturationRuntime,
+    MAX_SAT_DECO_STAGE,
+};
+pub use summary::{DiveSummary, GasUse, LoggedAlarm, SummaryRecorder, MAX_SUMMARY_ALARMS};
@@ -0,0 +1,468 @@
+use core::fmt;
+
+use crate::buehlmann::{BuehlmannConfig, BuehlmannModel};
+use crate::common::{
+    math, Cns, ConfigValidationErr, DecoCalculationError, DecoModel, DecoModelConfig, DecoStage,
+    DecoStageBuffer, DecoStageType, Depth, Float, Gas, MbarPressure, Otu, Pressure, Time,
+};
+
+/// default saturation deco stages capacity, ascent and rest period stages from max storage depth
+pub const MAX_SAT_DECO_STAGE: usize = 128;
+
+const SECONDS_PER_DAY: Float = 24. * 60. * 60.;
+// max simulated saturation decompression runtime
+const MAX_SAT_DECO_RUNTIME_DAYS: Float = 60.;
+// downward excursion limit search resolution (m)
+const EXCURSION_LIMIT_RESOLUTION: Float = 0.1;
+
+// seconds wrapped to a single day
+fn time_of_day(seconds: Float) -> Float {
+    seconds - math::floor(seconds / SECONDS_PER_DAY) * SECONDS_PER_DAY
+}
+
+/// continuous ascent rate (m/h) applied below given depth
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SatAscentRate {
+    pub min_depth: Depth,
+    pub rate: Float,
+}
+
+/// daily rest period without ascent (start as time of day)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RestPeriod {
+    pub start: Time,
+    pub duration: Time,
+}
+
+impl RestPeriod {
+    pub fn new(start_hour: Float, duration_hours: Float) -> Self {
+        Self {
+            start: Time::from_minutes(start_hour * 60.),
+            duration: Time::from_minutes(duration_hours * 60.),
+        }
+    }
+
+    fn contains(&self, clock: Time) -> bool {
+        let since_start = time_of_day(clock.as_seconds() - self.start.as_seconds());
+        since_start < self.duration.as_seconds()
+    }
+}
+
+/// saturation dive config
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SaturationConfig {
+    pub storage_depth: Depth,
+    // chamber ppO2 at storage depth
+    pub storage_pp_o2: Pressure,
+    // chamber ppO2 during decompression
+    pub deco_pp_o2: Pressure,
+    // max chamber O2 fraction (fire hazard), balance helium
+    pub max_o2_fraction: Float,
+    // ascent rates by depth, deepest band first
+    pub ascent_rates: [SatAscentRate; 4],
+    // no ascent within rest periods, zero duration disables a period
+    pub rest_periods: [RestPeriod; 2],
+    // schedule resolution
+    pub step: Time,
+}
+
+impl Default for SaturationConfig {
+    fn default() -> Self {
+        // U.S. Navy saturation decompression rates (6, 5, 4 and 3 fsw/h) and rest periods
+        Self {
+            storage_depth: Depth::from_meters(100.),
+            storage_pp_o2: 0.44,
+            deco_pp_o2: 0.5,
+            max_o2_fraction: 0.23,
+            ascent_rates: [
+                SatAscentRate {
+                    min_depth: Depth::from_meters(61.),
+                    rate: 1.83,
+                },
+                SatAscentRate {
+                    min_depth: Depth::from_meters(30.5),
+                    rate: 1.52,
+                },
+                SatAscentRate {
+                    min_depth: Depth::from_meters(15.2),
+                    rate: 1.22,
+                },
+                SatAscentRate {
+                    min_depth: Depth::zero(),
+                    rate: 0.91,
+                },
+            ],
+            rest_periods: [RestPeriod::new(0., 6.), RestPeriod::new(14., 2.)],
+            step: Time::from_minutes(1.),
+        }
+    }
+}
+
+impl SaturationConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_storage_depth(mut self, storage_depth: Depth) -> Self {
+        self.storage_depth = storage_depth;
+        self
+    }
+
+    pub fn with_pp_o2(mut self, storage_pp_o2: Pressure, deco_pp_o2: Pressure) -> Self {
+        self.storage_pp_o2 = storage_pp_o2;
+        self.deco_pp_o2 = deco_pp_o2;
+        self
+    }
+
+    pub fn with_max_o2_fraction(mut self, max_o2_fraction: Float) -> Self {
+        self.max_o2_fraction = max_o2_fraction;
+        self
+    }
+
+    pub fn with_ascent_rates(mut self, ascent_rates: [SatAscentRate; 4]) -> Self {
+        self.ascent_rates = ascent_rates;
+        self
+    }
+
+    pub fn with_rest_periods(mut self, rest_periods: [RestPeriod; 2]) -> Self {
+        self.rest_periods = rest_periods;
+        self
+    }
+
+    pub fn with_step(mut self, step: Time) -> Self {
+        self.step = step;
+        self
+    }
+
+    fn validate(&self) -> Result<(), SaturationError> {
+        let storage_depth_range = 1.0..=700.0;
+        let pp_o2_range = 0.2..=1.0;
+        let o2_fraction_range = 0.16..=1.0;
+        let step_range = 1.0..=3600.0;
+        let valid = storage_depth_range.contains(&self.storage_depth.as_meters())
+            && pp_o2_range.contains(&self.storage_pp_o2)
+            && pp_o2_range.contains(&self.deco_pp_o2)
+            && o2_fraction_range.contains(&self.max_o2_fraction)
+            && step_range.contains(&self.step.as_seconds())
+            && self
+                .ascent_rates
+                .iter()
+                .all(|band| band.rate > 0. && band.rate.is_finite())
+            && self
+                .rest_periods
+                .iter()
+                .all(|period| period.duration.as_seconds() < SECONDS_PER_DAY);
+        match valid {
+            true => Ok(()),
+            false => Err(SaturationError::InvalidConfig),
+        }
+    }
+
+    fn ascent_rate(&self, depth: Depth) -> Float {
+        self.ascent_rates
+            .iter()
+            .find(|band| depth > band.min_depth)
+            .unwrap_or(&self.ascent_rates[self.ascent_rates.len() - 1])
+            .rate
+    }
+
+    fn in_rest_period(&self, clock: Time) -> bool {
+        self.rest_periods
+            .iter()
+            .any(|period| period.contains(clock))
+    }
+
+    // heliox with given ppO2 at depth, O2 fraction capped
+    fn chamber_gas(&self, depth: Depth, pp_o2: Pressure, surface_pressure: MbarPressure) -> Gas {
+        let ambient_pressure = surface_pressure as Pressure / 1000. + depth.as_meters() / 10.;
+        let mut o2 = pp_o2 / ambient_pressure;
+        if o2 > self.max_o2_fraction {
+            o2 = self.max_o2_fraction;
+        }
+        Gas::new(o2, 1. - o2)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SaturationError {
+    InvalidConfig,
+    InvalidModelConfig(ConfigValidationErr),
+    UpwardExcursionLimit,
+    DownwardExcursionLimit,
+    Calculation(DecoCalculationError),
+}
+
+impl fmt::Display for SaturationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaturationError::InvalidConfig => write!(f, "Invalid saturation config"),
+            SaturationError::InvalidModelConfig(e) => {
+                write!(f, "Invalid model config [{:?}]: {:?}", e.field, e.reason)
+            }
+            SaturationError::UpwardExcursionLimit => {
+                write!(f, "Depth above upward excursion limit")
+            }
+            SaturationError::DownwardExcursionLimit => {
+                write!(f, "Depth below downward excursion limit")
+            }
+            SaturationError::Calculation(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<DecoCalculationError> for SaturationError {
+    fn from(e: DecoCalculationError) -> Self {
+        SaturationError::Calculation(e)
+    }
+}
+
+impl From<ConfigValidationErr> for SaturationError {
+    fn from(e: ConfigValidationErr) -> Self {
+        SaturationError::InvalidModelConfig(e)
+    }
+}
+
+/// continuous saturation decompression schedule (ascent and hold / rest stages)
+#[derive(Debug, PartialEq, Clone)]
+pub struct SaturationRuntime<S: DecoStageBuffer = [DecoStage; MAX_SAT_DECO_STAGE]> {
+    // ascent stages with chamber gas at stage start, rest periods and holds as stops
+    pub deco_stages: S,
+    // total decompression time
+    pub tts: Time,
+    // time of day at surface
+    pub end_clock: Time,
+    pub cns: Cns,
+    pub otu: Otu,
+}
+
+impl<S: DecoStageBuffer> SaturationRuntime<S> {
+    pub fn stages(&self) -> &[DecoStage] {
+        self.deco_stages.stages()
+    }
+}
+
+/// saturation dive from storage depth equilibrium
+#[derive(Clone, Debug)]
+pub struct Saturation {
+    model: BuehlmannModel,
+    config: SaturationConfig,
+    excursion_limits: (Depth, Depth),
+}
+
+impl Saturation {
+    /// saturation dive (panics on invalid config)
+    pub fn new(model_config: BuehlmannConfig, config: SaturationConfig) -> Self {
+        Self::try_new(model_config, config).unwrap_or_else(|e| panic!("Saturation error: {}", e))
+    }
+
+    pub fn try_new(
+        model_config: BuehlmannConfig,
+        config: SaturationConfig,
+    ) -> Result<Self, SaturationError> {
+        config.validate()?;
+        let mut model = BuehlmannModel::try_new(model_config)?;
+        let storage_gas = config.chamber_gas(
+            config.storage_depth,
+            config.storage_pp_o2,
+            model_config.surface_pressure(),
+        );
+        model.saturate(config.storage_depth, &storage_gas)?;
+        let excursion_limits = Self::calc_excursion_limits(&model, &config, &storage_gas)?;
+
+        Ok(Self {
+            model,
+            config,
+            excursion_limits,
+        })
+    }
+
+    pub fn model(&self) -> &BuehlmannModel {
+        &self.model
+    }
+
+    pub fn config(&self) -> SaturationConfig {
+        self.config
+    }
+
+    /// chamber gas at storage depth
+    pub fn storage_gas(&self) -> Gas {
+        self.config.chamber_gas(
+            self.config.storage_depth,
+            self.config.storage_pp_o2,
+            self.model.config().surface_pressure(),
+        )
+    }
+
+    /// (upward, downward) excursion limits. Upward limit is the current ceiling (updated after each
+    /// record), downward limit the deepest equilibrium depth allowing return to storage depth
+    pub fn excursion_limits(&self) -> (Depth, Depth) {
+        self.excursion_limits
+    }
+
+    /// depth within excursion limits
+    pub fn check_excursion(&self, depth: Depth) -> Result<(), SaturationError> {
+        let (upward_limit, downward_limit) = self.excursion_limits;
+        if depth < upward_limit {
+            return Err(SaturationError::UpwardExcursionLimit);
+        }
+        if depth > downward_limit {
+            return Err(SaturationError::DownwardExcursionLimit);
+        }
+        Ok(())
+    }
+
+    /// record excursion (or storage) record within excursion limits
+    pub fn record(&mut self, depth: Depth, time: Time, gas: &Gas) -> Result<(), SaturationError> {
+        self.check_excursion(depth)?;
+        self.model.try_record(depth, time, gas)?;
+        self.excursion_limits.0 = self.model.ceiling();
+        Ok(())
+    }
+
+    /// continuous decompression schedule from current state, starting at given time of day
+    pub fn deco(&self, start_clock: Time) -> Result<SaturationRuntime, SaturationError> {
+        self.deco_with_buffer(start_clock)
+    }
+
+    /// continuous decompression schedule using given stages buffer
+    pub fn deco_with_buffer<S: DecoStageBuffer>(
+        &self,
+        start_clock: Time,
+    ) -> Result<SaturationRuntime<S>, SaturationError> {
+        let config = self.config;
+        let surface_pressure = self.model.config().surface_pressure();
+        let step = config.step;
+        let mut model = self.model.clone();
+        let mut deco_stages = S::empty();
+        let mut tts = Time::zero();
+        let mut clock = start_clock;
+
+        loop {
+            let depth = model.dive_state().depth;
+            if depth <= Depth::zero() {
+                break;
+            }
+            if tts > Time::from_minutes(MAX_SAT_DECO_RUNTIME_DAYS * 24. * 60.) {
+                return Err(DecoCalculationError::RuntimeLimitExceeded.into());
+            }
+
+            // ascent at band rate, held within rest periods and below ceiling
+            let mut next_depth = depth;
+            if !config.in_rest_period(clock) {
+                let ascent = config.ascent_rate(depth) * step.as_minutes() / 60.;
+                next_depth = Depth::from_meters(depth.as_meters() - ascent);
+                let ceiling = model.ceiling();
+                if next_depth < ceiling {
+                    next_depth = ceiling;
+                }
+                if next_depth > depth {
+                    next_depth = depth;
+                }
+                if next_depth < Depth::zero() {
+                    next_depth = Depth::zero();
+                }
+            }
+            let stage_type = match next_depth < depth {
+                true => DecoStageType::Ascent,
+                false => DecoStageType::DecoStop,
+            };
+            if deco_stages
+                .stages()
+                .last()
+                .is_some_and(|last_stage| last_stage.stage_type != stage_type)
+            {
+                Self::close_stage(&mut deco_stages, &model);
+            }
+
+            let gas = config.chamber_gas(depth, config.deco_pp_o2, surface_pressure);
+            model.try_record_travel(next_depth, step, &gas)?;
+            tts += step;
+            clock += step;
+            Self::register_stage(
+                &mut deco_stages,
+                DecoStage {
+                    stage_type,
+                    start_depth: depth,
+                    end_depth: next_depth,
+                    duration: step,
+                    gas,
+                    runtime: model.dive_state().time,
+                    pp_o2: gas.partial_pressures(next_depth, surface_pressure).o2,
+                    end: gas.equivalent_narcotic_depth(next_depth),
+                    // set once the stage is closed
+                    gf: 0,
+                    cns: model.cns(),
+                    otu: model.otu(),
+                    valid: true,
+                },
+            )?;
+        }
+        Self::close_stage(&mut deco_stages, &model);
+
+        let end_clock = Time::from_seconds(time_of_day(clock.as_seconds()));
+        Ok(SaturationRuntime {
+            deco_stages,
+            tts,
+            end_clock,
+            cns: model.cns(),
+            otu: model.otu(),
+        })
+    }
+
+    // merge consecutive steps of the same stage type
+    fn register_stage<S: DecoStageBuffer>(
+        deco_stages: &mut S,
+        stage: DecoStage,
+    ) -> Result<(), DecoCalculationError> {
+        if let Some(last_stage) = deco_stages.stages_mut().last_mut() {
+            if last_stage.stage_type == stage.stage_type {
+                *last_stage = DecoStage {
+                    start_depth: last_stage.start_depth,
+                    duration: last_stage.duration + stage.duration,
+                    gas: last_stage.gas,
+                    ..stage
+                };
+                return Ok(());
+            }
+        }
+        deco_stages.push(stage)
+    }
+
+    // gradient factor at the end depth of the last stage, computed once per stage
+    fn close_stage<S: DecoStageBuffer>(deco_stages: &mut S, model: &BuehlmannModel) {
+        if let Some(last_stage) = deco_stages.stages_mut().last_mut() {
+            last_stage.gf = model.gradient_factor_at(last_stage.end_depth);
+        }
+    }
+
+    // upward: ceiling at storage equilibrium, downward: deepest equilibrium allowing return to storage
+    fn calc_excursion_limits(
+        model: &BuehlmannModel,
+        config: &SaturationConfig,
+        storage_gas: &Gas,
+    ) -> Result<(Depth, Depth), DecoCalculationError> {
+        let upward_limit = model.ceiling();
+
+        let storage_depth = config.storage_depth.as_meters();
+        let return_allowed = |excursion_depth: Float| {
+            let mut excursion_model = model.clone();
+            excursion_model.saturate(Depth::from_meters(excursion_depth), storage_gas)?;
+            Ok(excursion_model.ceiling() <= config.storage_depth)
+        };
+        let (mut allowed, mut exceeded) = (storage_depth, storage_depth * 2. + 30.);
+        if return_allowed(exceeded)? {
+            return Ok((upward_limit, Depth::from_meters(exceeded)));
+        }
+        while exceeded - allowed > EXCURSION_LIMIT_RESOLUTION {
+            let mid = (allowed + exceeded) / 2.;
+            match return_allowed(mid)? {
+                true => allowed = mid,
+                false => exceeded = mid,
+            }
+        }
+        let downward_limit =
+            math::floor(allowed / EXCURSION_LIMIT_RESOLUTION) * EXCURSION_LIMIT_RESOLUTION;
+
+        Ok((upward_limit, Depth::from_meters(downward_limit)))
+    }
+}
@@ -0,0 +1,337 @@
+use crate::buehlmann::{BuehlmannCheckpoint, BuehlmannModel, Supersaturation};
+use crate::common::{
+    AlarmConfig, AlarmEvent, AlarmEvents, AlarmKind, AlarmMonitor, AscentRatePerMinute, Cns,
+    DecoCalculationError, DecoModel, Depth, Float, Gas, Otu, Sim, Time, MAX_GASSES,
+};
+
+// max alarm events kept in summary
+pub const MAX_SUMMARY_ALARMS: usize = 32;
+
+/// time breathed on gas
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GasUse {
+    pub gas: Gas,
+    pub time: Time,
+}
+
+/// alarm event at model time
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoggedAlarm {
+    pub time: Time,
+    pub event: AlarmEvent,
+}
+
+/// post-dive summary of recorded profile
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DiveSummary {
+    pub max_depth: Depth,
+    pub avg_depth: Depth,
+    pub runtime: Time,
+    // max GF99 and GFsurf after any record
+    pub max_supersaturation: Supersaturation,
+    pub final_supersaturation: Supersaturation,
+    // ceiling, CNS and OTU at last surfacing (current values if not surfaced)
+    pub surfaced: bool,
+    pub surfacing_ceiling: Depth,
+    pub surfacing_cns: Cns,
+    pub surfacing_otu: Otu,
+    pub deco_time: Time,
+    gases: [Option<GasUse>; MAX_GASSES],
+    alarms: [Option<LoggedAlarm>; MAX_SUMMARY_ALARMS],
+    // alarm events not kept (summary alarms capacity exceeded)
+    pub alarms_dropped: usize,
+    // records on gases not kept (summary gases capacity exceeded)
+    pub gases_dropped: usize,
+}
+
+impl DiveSummary {
+    /// gases used with time on each, in order of first use
+    pub fn gases(&self) -> impl Iterator<Item = &GasUse> + '_ {
+        self.gases.iter().flatten()
+    }
+
+    /// alarm events (raised and cleared) in order of occurrence
+    pub fn alarms(&self) -> impl Iterator<Item = &LoggedAlarm> + '_ {
+        self.alarms.iter().flatten()
+    }
+}
+
+// surfacing state snapshot
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Surfacing {
+    ceiling: Depth,
+    cns: Cns,
+    otu: Otu,
+}
+
+// model state before record, checkpoint only if deco state may change within the record
+#[derive(Clone, Debug)]
+struct RecordStart {
+    depth: Depth,
+    travel: bool,
+    in_deco: bool,
+    checkpoint: Option<BuehlmannCheckpoint>,
+}
+
+/// model records (with alarms monitoring) accumulated into a dive summary
+#[derive(Clone, Debug)]
+pub struct SummaryRecorder {
+    monitor: AlarmMonitor<BuehlmannModel>,
+    start_time: Time,
+    last_time: Time,
+    max_depth: Depth,
+    // depth (m) x time (s) integral
+    depth_time: Float,
+    max_supersaturation: Supersaturation,
+    surfacing: Option<Surfacing>,
+    deco_time: Time,
+    gases: [Option<GasUse>; MAX_GASSES],
+    alarms: [Option<LoggedAlarm>; MAX_SUMMARY_ALARMS],
+    alarms_count: usize,
+    gases_dropped: usize,
+}
+
+impl SummaryRecorder {
+    pub fn new(model: BuehlmannModel) -> Self {
+        let state = model.dive_state();
+        Self {
+            monitor: AlarmMonitor::new(model),
+            start_time: state.time,
+            last_time: state.time,
+            max_depth: state.depth,
+            depth_time: 0.,
+            max_supersaturation: Supersaturation::default(),
+            surfacing: None,
+            deco_time: Time::zero(),
+            gases: [None; MAX_GASSES],
+            alarms: [None; MAX_SUMMARY_ALARMS],
+            alarms_count: 0,
+            gases_dropped: 0,
+        }
+    }
+
+    pub fn with_alarm_config(mut self, config: AlarmConfig) -> Self {
+        self.monitor = self.monitor.with_config(config);
+        self
+    }
+
+    pub fn model(&self) -> &BuehlmannModel {
+        self.monitor.model()
+    }
+
+    /// record (depth: meters, time: seconds)
+    pub fn record(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<AlarmEvents, DecoCalculationError> {
+        let start = self.record_start(depth, time, gas, false);
+        let events = self.monitor.record(depth, time, gas)?;
+        self.accumulate(start, gas, events);
+        Ok(events)
+    }
+
+    /// record linear ascent / descent record given travel time
+    pub fn record_travel(
+        &mut self,
+        target_depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<AlarmEvents, DecoCalculationError> {
+        let start = self.record_start(target_depth, time, gas, true);
+        let events = self.monitor.record_travel(target_depth, time, gas)?;
+        self.accumulate(start, gas, events);
+        Ok(events)
+    }
+
+    /// record linear ascent / descent record given rate
+    pub fn record_travel_with_rate(
+        &mut self,
+        target_depth: Depth,
+        rate: AscentRatePerMinute,
+        gas: &Gas,
+    ) -> Result<AlarmEvents, DecoCalculationError> {
+        let depth_change = target_depth - self.model().dive_state().depth;
+        let time = Time::from_minutes(depth_change.as_meters().abs() / rate);
+        let start = self.record_start(target_depth, time, gas, true);
+        let events = self
+            .monitor
+            .record_travel_with_rate(target_depth, rate, gas)?;
+        self.accumulate(start, gas, events);
+        Ok(events)
+    }
+
+    /// summary of records so far
+    pub fn summary(&self) -> DiveSummary {
+        let model = self.model();
+        let runtime = model.dive_state().time - self.start_time;
+        let avg_depth = match runtime > Time::zero() {
+            true => Depth::from_meters(self.depth_time / runtime.as_seconds()),
+            false => Depth::zero(),
+        };
+        let surfacing = self.surfacing.unwrap_or(Surfacing {
+            ceiling: model.ceiling(),
+            cns: model.cns(),
+            otu: model.otu(),
+        });
+
+        DiveSummary {
+            max_depth: self.max_depth,
+            avg_depth,
+            runtime,
+            max_supersaturation: self.max_supersaturation,
+            final_supersaturation: model.supersaturation(),
+            surfaced: self.surfacing.is_some(),
+            surfacing_ceiling: surfacing.ceiling,
+            surfacing_cns: surfacing.cns,
+            surfacing_otu: surfacing.otu,
+            deco_time: self.deco_time,
+            gases: self.gases,
+            alarms: self.alarms,
+            alarms_dropped: self.alarms_count.saturating_sub(MAX_SUMMARY_ALARMS),
+            gases_dropped: self.gases_dropped,
+        }
+    }
+
+    // model state before record, in deco as evaluated by alarm monitor on previous record
+    fn record_start(&self, depth: Depth, time: Time, gas: &Gas, travel: bool) -> RecordStart {
+        let in_deco = self.monitor.is_active(AlarmKind::Deco);
+        let checkpoint = match in_deco || self.deco_entry_possible(depth, time, gas) {
+            true => Some(self.model().checkpoint()),
+            false => None,
+        };
+        RecordStart {
+            depth: self.model().dive_state().depth,
+            travel,
+            in_deco,
+            checkpoint,
+        }
+    }
+
+    // out of deco, deco can't be entered within a record shorter than NDL (above NDL warning
+    // on previous record) without descending or switching gas
+    fn deco_entry_possible(&self, depth: Depth, time: Time, gas: &Gas) -> bool {
+        let state = self.model().dive_state();
+        let ndl_checked = state.time > self.start_time && state.depth > Depth::zero();
+        !ndl_checked
+            || self.monitor.is_active(AlarmKind::NdlLow)
+            || time >= self.monitor.config().ndl_warning
+            || depth > state.depth
+            || *gas != state.gas
+    }
+
+    // record outcome, travel as linear depth change from start depth over recorded model time
+    fn accumulate(&mut self, start: RecordStart, gas: &Gas, events: AlarmEvents) {
+        let start_depth = start.depth;
+        let model = self.monitor.model();
+        let state = model.dive_state();
+        let time = state.time - self.last_time;
+        self.last_time = state.time;
+
+        if state.depth > self.max_depth {
+            self.max_depth = state.depth;
+        }
+        let record_avg_depth = match start.travel {
+            true => (start_depth.as_meters() + state.depth.as_meters()) / 2.,
+            false => state.depth.as_meters(),
+        };
+        self.depth_time += record_avg_depth * time.as_seconds();
+
+        let Supersaturation { gf_99, gf_surf } = model.supersaturation();
+        if gf_99 > self.max_supersaturation.gf_99 {
+            self.max_supersaturation.gf_99 = gf_99;
+        }
+        if gf_surf > self.max_supersaturation.gf_surf {
+            self.max_supersaturation.gf_surf = gf_surf;
+        }
+
+        if state.depth <= Depth::zero() && start_depth > Depth::zero() {
+            self.surfacing = Some(Surfacing {
+                ceiling: model.ceiling(),
+                cns: model.cns(),
+                otu: model.otu(),
+            });
+        }
+
+        // in deco part of the record only if deco entered or cleared within the record
+        let in_deco = self.monitor.is_active(AlarmKind::Deco);
+        self.deco_time += match (start.in_deco, in_deco) {
+            (true, true) => time,
+            (false, false) => Time::zero(),
+            (true, false) => self.deco_transition_time(&start, state.depth, gas, time),
+            (false, true) => time - self.deco_transition_time(&start, state.depth, gas, time),
+        };
+
+        if time > Time::zero() {
+            self.add_gas_time(gas, time);
+        }
+        for event in events {
+            if self.alarms_count < MAX_SUMMARY_ALARMS {
+                self.alarms[self.alarms_count] = Some(LoggedAlarm {
+                    time: state.time,
+                    event,
+                });
+            }
+            self.alarms_count += 1;
+        }
+    }
+
+    // model time into the record deco state changed at (1s resolution), record replayed from start checkpoint
+    fn deco_transition_time(
+        &self,
+        start: &RecordStart,
+        end_depth: Depth,
+        gas: &Gas,
+        time: Time,
+    ) -> Time {
+        // deco state not expected to change, at the end of the record
+        let Some(checkpoint) = &start.checkpoint else {
+            return time;
+        };
+        let mut probe_model = self.model().fork();
+        let mut deco_state_changed = |seconds: Float| -> bool {
+            probe_model.restore(checkpoint);
+            let probe_time = Time::from_seconds(seconds);
+            let res = match start.travel {
+                true => {
+                    let depth =
+                        start.depth + (end_depth - start.depth) * (seconds / time.as_seconds());
+                    probe_model.try_record_travel(depth, probe_time, gas)
+                }
+                false => probe_model.try_record(end_depth, probe_time, gas),
+            };
+            res.is_err() || probe_model.in_deco() != start.in_deco
+        };
+
+        let mut unchanged: Float = 0.;
+        let mut changed = time.as_seconds();
+        while changed - unchanged > 1. {
+            let mid = unchanged + (changed - unchanged) / 2.;
+            if deco_state_changed(mid) {
+                changed = mid;
+            } else {
+                unchanged = mid;
+            }
+        }
+
+        Time::from_seconds(changed)
+    }
+
+    fn add_gas_time(&mut self, gas: &Gas, time: Time) {
+        for gas_use in self.gases.iter_mut() {
+            match gas_use {
+                Some(gas_use) if gas_use.gas == *gas => {
+                    gas_use.time += time;
+                    return;
+                }
+                Some(_) => (),
+                None => {
+                    *gas_use = Some(GasUse { gas: *gas, time });
+                    return;
+                }
+            }
+        }
+        self.gases_dropped += 1;
+    }
+}
@@ -1,4 +1,6 @@
-pub type ZHLParam = f32;
+use crate::common::Float;
+
+pub type ZHLParam = Float;
 // N2 half-time, N2 a coefficient, N2 b coefficient, He half-time, He a coefficient, H2 b coefficient
 pub type ZHLParams = (ZHLParam, ZHLParam, ZHLParam, ZHLParam, ZHLParam, ZHLParam);
 
@@ -0,0 +1,473 @@
+use crate::common::deco::DecoCalculationError;
+use crate::common::{
+    AscentRatePerMinute, Cns, DecoModel, DecoModelConfig, Depth, Gas, GasDensity, Otu, Pressure,
+    Time,
+};
+
+const ALARM_KINDS_COUNT: usize = 9;
+
+/// alarm thresholds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AlarmConfig {
+    // max ascent rate (m/min)
+    pub max_ascent_rate: AscentRatePerMinute,
+    pub max_pp_o2: Pressure,
+    pub min_pp_o2: Pressure,
+    // CNS (%)
+    pub max_cns: Cns,
+    pub max_otu: Otu,
+    // NDL warning if below (not in deco)
+    pub ndl_warning: Time,
+    // max gas density (g/L)
+    pub max_gas_density: GasDensity,
+}
+
+impl Default for AlarmConfig {
+    fn default() -> Self {
+        Self {
+            max_ascent_rate: 10.,
+            max_pp_o2: 1.6,
+            min_pp_o2: 0.16,
+            max_cns: 80.,
+            max_otu: 300.,
+            ndl_warning: Time::from_minutes(5.),
+            max_gas_density: 5.2,
+        }
+    }
+}
+
+impl AlarmConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_ascent_rate(mut self, max_ascent_rate: AscentRatePerMinute) -> Self {
+        self.max_ascent_rate = max_ascent_rate;
+        self
+    }
+
+    pub fn with_pp_o2_limits(mut self, min_pp_o2: Pressure, max_pp_o2: Pressure) -> Self {
+        self.min_pp_o2 = min_pp_o2;
+        self.max_pp_o2 = max_pp_o2;
+        self
+    }
+
+    pub fn with_max_cns(mut self, max_cns: Cns) -> Self {
+        self.max_cns = max_cns;
+        self
+    }
+
+    pub fn with_max_otu(mut self, max_otu: Otu) -> Self {
+        self.max_otu = max_otu;
+        self
+    }
+
+    pub fn with_ndl_warning(mut self, ndl_warning: Time) -> Self {
+        self.ndl_warning = ndl_warning;
+        self
+    }
+
+    pub fn with_max_gas_density(mut self, max_gas_density: GasDensity) -> Self {
+        self.max_gas_density = max_gas_density;
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AlarmKind {
+    AscentRate,
+    CeilingBreach,
+    PpO2High,
+    PpO2Low,
+    Cns,
+    Otu,
+    Deco,
+    NdlLow,
+    GasDensity,
+}
+
+impl AlarmKind {
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// alarm with value that triggered it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Alarm {
+    AscentRate(AscentRatePerMinute),
+    // missed deco stop (depth above ceiling)
+    CeilingBreach { depth: Depth, ceiling: Depth },
+    PpO2High(Pressure),
+    PpO2Low(Pressure),
+    Cns(Cns),
+    Otu(Otu),
+    // entered decompression (no NDL)
+    Deco,
+    NdlLow(Time),
+    GasDensity(GasDensity),
+}
+
+impl Alarm {
+    pub fn kind(&self) -> AlarmKind {
+        match self {
+            Alarm::AscentRate(_) => AlarmKind::AscentRate,
+            Alarm::CeilingBreach { .. } => AlarmKind::CeilingBreach,
+            Alarm::PpO2High(_) => AlarmKind::PpO2High,
+            Alarm::PpO2Low(_) => AlarmKind::PpO2Low,
+            Alarm::Cns(_) => AlarmKind::Cns,
+            Alarm::Otu(_) => AlarmKind::Otu,
+            Alarm::Deco => AlarmKind::Deco,
+            Alarm::NdlLow(_) => AlarmKind::NdlLow,
+            Alarm::GasDensity(_) => AlarmKind::GasDensity,
+        }
+    }
+}
+
+/// alarm state change
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AlarmEvent {
+    Raised(Alarm),
+    Cleared(AlarmKind),
+}
+
+/// alarm events emitted by a single record (at most one per alarm kind)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AlarmEvents {
+    events: [Option<AlarmEvent>; ALARM_KINDS_COUNT],
+    len: usize,
+    pos: usize,
+}
+
+impl AlarmEvents {
+    fn new() -> Self {
+        Self {
+            events: [None; ALARM_KINDS_COUNT],
+            len: 0,
+            pos: 0,
+        }
+    }
+
+    fn push(&mut self, event: AlarmEvent) {
+        self.events[self.len] = Some(event);
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len - self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// alarm of given kind raised
+    pub fn is_raised(&self, kind: AlarmKind) -> bool {
+        self.events[self.pos..self.len]
+            .iter()
+            .any(|event| matches!(event, Some(AlarmEvent::Raised(alarm)) if alarm.kind() == kind))
+    }
+
+    /// alarm of given kind cleared
+    pub fn is_cleared(&self, kind: AlarmKind) -> bool {
+        self.events[self.pos..self.len].contains(&Some(AlarmEvent::Cleared(kind)))
+    }
+}
+
+impl Iterator for AlarmEvents {
+    type Item = AlarmEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.len {
+            return None;
+        }
+        self.pos += 1;
+        self.events[self.pos - 1]
+    }
+}
+
+/// deco model records watcher, emits alarm events on alarm state changes
+#[derive(Clone, Debug)]
+pub struct AlarmMonitor<T: DecoModel> {
+    model: T,
+    config: AlarmConfig,
+    active: [bool; ALARM_KINDS_COUNT],
+}
+
+impl<T: DecoModel> AlarmMonitor<T> {
+    pub fn new(model: T) -> Self {
+        Self {
+            model,
+            config: AlarmConfig::default(),
+            active: [false; ALARM_KINDS_COUNT],
+        }
+    }
+
+    pub fn with_config(mut self, config: AlarmConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// monitored deco model
+    pub fn model(&self) -> &T {
+        &self.model
+    }
+
+    pub fn config(&self) -> AlarmConfig {
+        self.config
+    }
+
+    /// alarm of given kind currently active
+    pub fn is_active(&self, kind: AlarmKind) -> bool {
+        self.active[kind.index()]
+    }
+
+    /// record (depth: meters, time: seconds), ascent rate from previous depth over record time
+    pub fn record(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<AlarmEvents, DecoCalculationError> {
+        let ascent_rate = self.ascent_rate(depth, time);
+        self.model.try_record(depth, time, gas)?;
+        Ok(self.check(ascent_rate))
+    }
+
+    /// record linear ascent / descent record given travel time
+    pub fn record_travel(
+        &mut self,
+        target_depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<AlarmEvents, DecoCalculationError> {
+        let ascent_rate = self.ascent_rate(target_depth, time);
+        self.model.try_record_travel(target_depth, time, gas)?;
+        Ok(self.check(ascent_rate))
+    }
+
+    /// record linear ascent / descent record given rate
+    pub fn record_travel_with_rate(
+        &mut self,
+        target_depth: Depth,
+        rate: AscentRatePerMinute,
+        gas: &Gas,
+    ) -> Result<AlarmEvents, DecoCalculationError> {
+        let ascending = target_depth < self.model.dive_state().depth;
+        self.model
+            .try_record_travel_with_rate(target_depth, rate, gas)?;
+        let ascent_rate = if ascending { rate } else { 0. };
+        Ok(self.check(Some(ascent_rate)))
+    }
+
+    // ascent rate (m/min) of depth change over time, None if no time elapsed
+    fn ascent_rate(&self, depth: Depth, time: Time) -> Option<AscentRatePerMinute> {
+        if time <= Time::zero() {
+            return None;
+        }
+        let ascent = self.model.dive_state().depth - depth;
+        Some(ascent.as_meters() / time.as_minutes())
+    }
+
+    // current alarms against active alarms, ascent rate alarm kept if rate unknown
+    fn check(&mut self, ascent_rate: Option<AscentRatePerMinute>) -> AlarmEvents {
+        let config = self.config;
+        let state = self.model.dive_state();
+        let surface_pressure = self.model.config().surface_pressure();
+        let mut events = AlarmEvents::new();
+
+        if let Some(rate) = ascent_rate {
+            let ascent_rate_alarm =
+                (rate > config.max_ascent_rate).then_some(Alarm::AscentRate(rate));
+            self.update(&mut events, AlarmKind::AscentRate, ascent_rate_alarm);
+        }
+
+        let in_deco = self.model.in_deco();
+        let ceiling = self.model.ceiling();
+        let ceiling_alarm = (in_deco && state.depth < ceiling).then_some(Alarm::CeilingBreach {
+            depth: state.depth,
+            ceiling,
+        });
+        self.update(&mut events, AlarmKind::CeilingBreach, ceiling_alarm);
+
+        let pp_o2 = state
+            .gas
+            .partial_pressures(state.depth, surface_pressure)
+            .o2;
+        let pp_o2_high = (pp_o2 > config.max_pp_o2).then_some(Alarm::PpO2High(pp_o2));
+        self.update(&mut events, AlarmKind::PpO2High, pp_o2_high);
+        let pp_o2_low = (pp_o2 < config.min_pp_o2).then_some(Alarm::PpO2Low(pp_o2));
+        self.update(&mut events, AlarmKind::PpO2Low, pp_o2_low);
+
+        let cns = self.model.cns();
+        let cns_alarm = (cns > config.max_cns).then_some(Alarm::Cns(cns));
+        self.update(&mut events, AlarmKind::Cns, cns_alarm);
+        let otu = self.model.otu();
+        let otu_alarm = (otu > config.max_otu).then_some(Alarm::Otu(otu));
+        self.update(&mut events, AlarmKind::Otu, otu_alarm);
+
+        self.update(&mut events, AlarmKind::Deco, in_deco.then_some(Alarm::Deco));
+        let ndl_alarm = match in_deco || state.depth <= Depth::zero() {
+            true => None,
+            false => {
+                let ndl = self.model.ndl();
+                (ndl < config.ndl_warning).then_some(Alarm::NdlLow(ndl))
+            }
+        };
+        self.update(&mut events, AlarmKind::NdlLow, ndl_alarm);
+
+        let density = state.gas.density(state.depth, surface_pressure);
+        let density_alarm =
+            (density > config.max_gas_density).then_some(Alarm::GasDensity(density));
+        self.update(&mut events, AlarmKind::GasDensity, density_alarm);
+
+        events
+    }
+
+    // emit event on alarm state change
+    fn update(&mut self, events: &mut AlarmEvents, kind: AlarmKind, alarm: Option<Alarm>) {
+        let active = &mut self.active[kind.index()];
+        match (alarm, *active) {
+            (Some(alarm), false) => events.push(AlarmEvent::Raised(alarm)),
+            (None, true) => events.push(AlarmEvent::Cleared(kind)),
+            _ => (),
+        }
+        *active = alarm.is_some();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuehlmannConfig, BuehlmannModel};
+
+    fn monitor() -> AlarmMonitor<BuehlmannModel> {
+        AlarmMonitor::new(BuehlmannModel::default())
+    }
+
+    #[test]
+    fn test_no_alarms_on_shallow_dive() {
+        let air = Gas::air();
+        let mut monitor = monitor();
+        let events = monitor
+            .record_travel(Depth::from_meters(10.), Time::from_minutes(1.), &air)
+            .unwrap();
+        assert!(events.is_empty());
+        let events = monitor
+            .record(Depth::from_meters(10.), Time::from_minutes(20.), &air)
+            .unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_ascent_rate() {
+        let air = Gas::air();
+        let mut monitor = monitor();
+        monitor
+            .record(Depth::from_meters(20.), Time::from_minutes(5.), &air)
+            .unwrap();
+
+        let mut events = monitor
+            .record_travel(Depth::from_meters(5.), Time::from_minutes(1.), &air)
+            .unwrap();
+        assert_eq!(
+            events.next(),
+            Some(AlarmEvent::Raised(Alarm::AscentRate(15.)))
+        );
+        assert!(monitor.is_active(AlarmKind::AscentRate));
+
+        // rate unknown on zero time record, alarm state kept
+        let events = monitor
+            .record(Depth::from_meters(5.), Time::zero(), &air)
+            .unwrap();
+        assert!(events.is_empty());
+
+        let events = monitor
+            .record_travel_with_rate(Depth::from_meters(3.), 9., &air)
+            .unwrap();
+        assert!(events.is_cleared(AlarmKind::AscentRate));
+        assert!(!monitor.is_active(AlarmKind::AscentRate));
+    }
+
+    #[test]
+    fn test_deco_and_ceiling_breach() {
+        let air = Gas::air();
+        let mut monitor =
+            monitor().with_config(AlarmConfig::new().with_ndl_warning(Time::from_minutes(3.)));
+        monitor
+            .record_travel(Depth::from_meters(40.), Time::from_minutes(2.), &air)
+            .unwrap();
+
+        let mut ndl_warning_raised = false;
+        let mut deco_raised = false;
+        for _ in 0..30 {
+            let events = monitor
+                .record(Depth::from_meters(40.), Time::from_minutes(1.), &air)
+                .unwrap();
+            ndl_warning_raised |= events.is_raised(AlarmKind::NdlLow);
+            if events.is_raised(AlarmKind::Deco) {
+                deco_raised = true;
+                assert!(events.is_cleared(AlarmKind::NdlLow));
+                break;
+            }
+        }
+        assert!(ndl_warning_raised);
+        assert!(deco_raised);
+
+        monitor
+            .record(Depth::from_meters(40.), Time::from_minutes(10.), &air)
+            .unwrap();
+        let ceiling = monitor.model().ceiling();
+        let events = monitor.record(Depth::zero(), Time::zero(), &air).unwrap();
+        assert!(events.into_iter().any(|event| event
+            == AlarmEvent::Raised(Alarm::CeilingBreach {
+                depth: Depth::zero(),
+                ceiling
+            })));
+    }
+
+    #[test]
+    fn test_pp_o2_and_gas_density() {
+        let mut monitor = monitor();
+        let ean_50 = Gas::new(0.5, 0.);
+        let events = monitor
+            .record(Depth::from_meters(25.), Time::zero(), &ean_50)
+            .unwrap();
+        assert!(events.is_raised(AlarmKind::PpO2High));
+        assert!(monitor.is_active(AlarmKind::PpO2High));
+
+        let hypoxic = Gas::new(0.1, 0.5);
+        let events = monitor
+            .record(Depth::from_meters(2.), Time::zero(), &hypoxic)
+            .unwrap();
+        assert!(events.is_cleared(AlarmKind::PpO2High));
+        assert!(events.is_raised(AlarmKind::PpO2Low));
+
+        let events = monitor
+            .record(Depth::from_meters(50.), Time::zero(), &Gas::air())
+            .unwrap();
+        assert!(events.is_cleared(AlarmKind::PpO2Low));
+        assert!(events.is_raised(AlarmKind::GasDensity));
+    }
+
+    #[test]
+    fn test_configurable_thresholds() {
+        let config = AlarmConfig::new()
+            .with_pp_o2_limits(0.18, 1.2)
+            .with_max_cns(5.)
+            .with_max_otu(10.)
+            .with_max_gas_density(3.);
+        let model = BuehlmannModel::new(BuehlmannConfig::default());
+        let mut monitor = AlarmMonitor::new(model).with_config(config);
+        assert_eq!(monitor.config(), config);
+
+        let ean_32 = Gas::new(0.32, 0.);
+        let events = monitor
+            .record(Depth::from_meters(30.), Time::from_minutes(30.), &ean_32)
+            .unwrap();
+        assert!(events.is_raised(AlarmKind::PpO2High));
+        assert!(events.is_raised(AlarmKind::Cns));
+        assert!(events.is_raised(AlarmKind::Otu));
+        assert!(events.is_raised(AlarmKind::GasDensity));
+    }
+}
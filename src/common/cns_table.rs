@@ -1,7 +1,9 @@
 use core::ops::RangeInclusive;
 
+use super::Float;
+
 // (PO2 Range, slope, intercept)
-pub type CNSCoeffRow = (RangeInclusive<f32>, i32, i32);
+pub type CNSCoeffRow = (RangeInclusive<Float>, i32, i32);
 
 pub const CNS_COEFFICIENTS: [CNSCoeffRow; 7] = [
     (0.5..=0.6, -1800, 1800),
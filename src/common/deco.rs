@@ -1,12 +1,26 @@
 use core::{cmp::Ordering, fmt};
 
-use crate::{common::MAX_DECO_STAGE, DecoModel, Depth, DepthType, Gas, Time};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
-use super::{DecoModelConfig, DiveState, MbarPressure, Sim, MAX_GASSES};
+use crate::{
+    common::{MAX_DECO_STAGE, MAX_GASSES},
+    DecoModel, Depth, DepthType, Gas, GasDensity, Pressure, Time,
+};
+
+use super::{math, Cns, DecoModelConfig, DiveState, Float, GradientFactor, MbarPressure, Otu, Sim};
 
 // @todo move to model config
 const DEFAULT_CEILING_WINDOW: DepthType = 3.;
 const DEFAULT_MAX_END_DEPTH: DepthType = 30.;
+// hard bound of deco planner iterations (guard against non-converging input). Each iteration
+// registers a single deco action (ascent, gas switch, whole stop solved by deco_stop_duration search)
+// or moves a missed stop back to stop depth, that is a handful of iterations per 3m stop window.
+// Even at the max 30 m/min ascent rate, 24h runtime limit is reached within 43.2 km of ascent
+// (14 400 stop windows), so converging calculations stay well below the bound
+const MAX_CALC_ITERATIONS: u32 = 100_000;
+// max simulated deco runtime (TTS)
+const MAX_DECO_RUNTIME_MINS: Float = 24. * 60.;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum DecoAction {
@@ -21,6 +35,7 @@ pub enum DecoStageType {
     Ascent,
     DecoStop,
     GasSwitch,
+    SafetyStop,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -30,7 +45,17 @@ pub struct DecoStage {
     pub end_depth: Depth,
     pub duration: Time,
     pub gas: Gas,
-    pub valid: bool
+    // dive runtime (model time) at stage end
+    pub runtime: Time,
+    // ppO2 and END at stage (end) depth
+    pub pp_o2: Pressure,
+    pub end: Depth,
+    // gradient factor in effect at stage (end) depth
+    pub gf: GradientFactor,
+    // oxygen toxicity at stage end
+    pub cns: Cns,
+    pub otu: Otu,
+    pub valid: bool,
 }
 
 impl Default for DecoStage {
@@ -41,37 +66,166 @@ impl Default for DecoStage {
             end_depth: Depth::zero(),
             duration: Time::zero(),
             gas: Gas::air(),
-            valid: false
+            runtime: Time::zero(),
+            pp_o2: 0.,
+            end: Depth::zero(),
+            gf: 0,
+            cns: 0.,
+            otu: 0.,
+            valid: false,
+        }
+    }
+}
+
+/// deco stages storage (fixed capacity array or growable Vec with `alloc` feature)
+pub trait DecoStageBuffer: Clone {
+    /// empty buffer
+    fn empty() -> Self;
+    /// registered deco stages
+    fn stages(&self) -> &[DecoStage];
+    /// registered deco stages, mutable
+    fn stages_mut(&mut self) -> &mut [DecoStage];
+    /// register new deco stage, Err if buffer capacity exceeded
+    fn push(&mut self, stage: DecoStage) -> Result<(), DecoCalculationError>;
+}
+
+// fixed capacity buffer, registered stages flagged as valid
+impl<const N: usize> DecoStageBuffer for [DecoStage; N] {
+    fn empty() -> Self {
+        [DecoStage::default(); N]
+    }
+
+    fn stages(&self) -> &[DecoStage] {
+        let stages_count = self.iter().filter(|stage| stage.valid).count();
+        &self[..stages_count]
+    }
+
+    fn stages_mut(&mut self) -> &mut [DecoStage] {
+        let stages_count = self.iter().filter(|stage| stage.valid).count();
+        &mut self[..stages_count]
+    }
+
+    fn push(&mut self, stage: DecoStage) -> Result<(), DecoCalculationError> {
+        let stages_count = self.stages().len();
+        if stages_count >= N {
+            return Err(DecoCalculationError::StageBufferOverflow);
         }
+        self[stages_count] = DecoStage {
+            valid: true,
+            ..stage
+        };
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl DecoStageBuffer for Vec<DecoStage> {
+    fn empty() -> Self {
+        Vec::new()
+    }
+
+    fn stages(&self) -> &[DecoStage] {
+        self
+    }
+
+    fn stages_mut(&mut self) -> &mut [DecoStage] {
+        self
+    }
+
+    fn push(&mut self, stage: DecoStage) -> Result<(), DecoCalculationError> {
+        Vec::push(self, stage);
+
+        Ok(())
     }
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct Deco {
-    deco_stages: [DecoStage; super::MAX_DECO_STAGE],
+#[derive(Clone, Debug)]
+pub struct Deco<S: DecoStageBuffer = [DecoStage; MAX_DECO_STAGE]> {
+    deco_stages: S,
     tts: Time,
+    // deco actions taken, bounded by MAX_CALC_ITERATIONS
+    iterations: u32,
     sim: bool,
 }
 
-#[derive(Debug, PartialEq, Default, Clone)]
-pub struct DecoRuntime {
+impl<S: DecoStageBuffer> Default for Deco<S> {
+    fn default() -> Self {
+        Self {
+            deco_stages: S::empty(),
+            tts: Time::zero(),
+            iterations: 0,
+            sim: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DecoRuntime<S: DecoStageBuffer = [DecoStage; MAX_DECO_STAGE]> {
     // runtime
-    pub deco_stages: [DecoStage; super::MAX_DECO_STAGE],
+    pub deco_stages: S,
     // current TTS in minutes
     pub tts: Time,
     // TTS @+5 (TTS in 5 min given current depth and gas mix)
     pub tts_at_5: Time,
     // TTS Δ+5 (absolute change in TTS after 5 mins given current depth and gas mix)
     pub tts_delta_at_5: Time,
+    // any deco stage breathed above configured max gas density
+    pub gas_density_exceeded: bool,
+    // oxygen toxicity at surfacing
+    pub cns: Cns,
+    pub otu: Otu,
+}
+
+impl<S: DecoStageBuffer> Default for DecoRuntime<S> {
+    fn default() -> Self {
+        Self {
+            deco_stages: S::empty(),
+            tts: Time::zero(),
+            tts_at_5: Time::zero(),
+            tts_delta_at_5: Time::zero(),
+            gas_density_exceeded: false,
+            cns: 0.,
+            otu: 0.,
+        }
+    }
+}
+
+impl<S: DecoStageBuffer> DecoRuntime<S> {
+    /// registered deco stages (valid part of the stages buffer)
+    pub fn stages(&self) -> &[DecoStage] {
+        self.deco_stages.stages()
+    }
+
+    /// decompression obligation (more than a direct ascent, planned safety stop excluded)
+    pub fn is_deco(&self) -> bool {
+        let stages = self.stages();
+        let safety_stop_planned = stages
+            .iter()
+            .any(|stage| stage.stage_type == DecoStageType::SafetyStop);
+        !safety_stop_planned && stages.len() > 1
+    }
 }
 
 #[derive(Debug)]
-struct MissedDecoStopViolation;
+enum DecoActionErr {
+    MissedDecoStopViolation,
+    Calculation(DecoCalculationError),
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum DecoCalculationError {
     EmptyGasList,
     CurrentGasNotInList,
+    GasListOverflow,
+    InvalidDepth,
+    InvalidTime,
+    InvalidGas,
+    InvalidTravelRate,
+    StageBufferOverflow,
+    RuntimeLimitExceeded,
+    NoConvergence,
+    InvalidState,
 }
 
 impl fmt::Display for DecoCalculationError {
@@ -84,11 +238,38 @@ impl fmt::Display for DecoCalculationError {
                 f,
                 "Avaibalbe gas mixes must include current gas mix used by deco model"
             ),
+            DecoCalculationError::GasListOverflow => {
+                write!(f, "Gas mixes exceed max gas mixes count")
+            }
+            DecoCalculationError::InvalidDepth => {
+                write!(f, "Depth must be a non-negative number")
+            }
+            DecoCalculationError::InvalidTime => {
+                write!(f, "Time must be a non-negative number")
+            }
+            DecoCalculationError::InvalidGas => {
+                write!(f, "Gas mix must have valid o2 and he fractions")
+            }
+            DecoCalculationError::InvalidTravelRate => {
+                write!(f, "Travel rate must be a positive number")
+            }
+            DecoCalculationError::StageBufferOverflow => {
+                write!(f, "Deco stages exceed max deco stages count")
+            }
+            DecoCalculationError::RuntimeLimitExceeded => {
+                write!(f, "Deco runtime exceeds max deco runtime")
+            }
+            DecoCalculationError::NoConvergence => {
+                write!(f, "Deco calculation didn't converge within max iterations")
+            }
+            DecoCalculationError::InvalidState => {
+                write!(f, "Deco model state is not a valid number")
+            }
         }
     }
 }
 
-impl Sim for Deco {
+impl<S: DecoStageBuffer> Sim for Deco<S> {
     fn fork(&self) -> Self {
         Self {
             sim: true,
@@ -100,7 +281,7 @@ impl Sim for Deco {
     }
 }
 
-impl Deco {
+impl<S: DecoStageBuffer> Deco<S> {
     pub fn new_sim() -> Self {
         let deco = Self::default();
         deco.fork()
@@ -109,204 +290,316 @@ impl Deco {
     pub fn calc<T: DecoModel + Clone + Sim>(
         &mut self,
         deco_model: T,
-        gas_mixes: [Gas; MAX_GASSES],
-    ) -> Result<DecoRuntime, DecoCalculationError> {
-        // validate gas mixes
-        Self::validate_gas_mixes(&deco_model, &gas_mixes)?;
+        gas_mixes: &[Gas],
+    ) -> Result<DecoRuntime<S>, DecoCalculationError> {
+        // run model simulation until no deco stages, owned model used as simulation model
+        let mut sim_model = deco_model;
+        self.start_calc(&sim_model, gas_mixes)?;
+        let pre_deco_checkpoint = sim_model.checkpoint();
+        while !self.calc_step(&mut sim_model, gas_mixes)? {}
+
+        let mut runtime = self.deco_runtime(&sim_model);
+        if !self.is_sim() {
+            // rolled back to pre-deco state for TTS @+5
+            sim_model.restore(&pre_deco_checkpoint);
+            runtime.tts_at_5 = Self::tts_at_5(sim_model, gas_mixes)?;
+            runtime.tts_delta_at_5 = runtime.tts_at_5 - runtime.tts;
+        }
+
+        Ok(runtime)
+    }
+
+    /// validate gas mixes and initialize deco state for stepped calculation on given simulation model
+    pub(crate) fn start_calc(
+        &mut self,
+        sim_model: &impl DecoModel,
+        gas_mixes: &[Gas],
+    ) -> Result<(), DecoCalculationError> {
+        Self::validate_gas_mixes(sim_model, gas_mixes)?;
+        Ok(())
+    }
 
-        // run model simulation until no deco stages
-        let mut sim_model: T = deco_model.clone();
+    /// single deco action on simulation model, true once deco obligation cleared
+    pub(crate) fn calc_step<T: DecoModel + Clone + Sim>(
+        &mut self,
+        sim_model: &mut T,
+        gas_mixes: &[Gas],
+    ) -> Result<bool, DecoCalculationError> {
         let ascent_rate = sim_model.config().deco_ascent_rate();
-        loop {
-            let DiveState {
-                depth: pre_stage_depth,
-                time: pre_stage_time,
-                gas: pre_stage_gas,
-                ..
-            } = sim_model.dive_state();
-            let ceiling = sim_model.ceiling();
-
-            // handle missed deco stop
-            // if missed deco stop, override sim model to depth at the expected stop and rerun the calculation
-            let next_deco_action = self.next_deco_action(&sim_model, gas_mixes.clone());
-            if let Err(e) = next_deco_action {
-                match e {
-                    MissedDecoStopViolation => {
-                        sim_model.record(
-                            self.deco_stop_depth(ceiling),
-                            Time::zero(),
-                            &pre_stage_gas,
-                        );
-                        return self.calc(sim_model, gas_mixes);
-                    }
-                }
-            }
+        // guaranteed termination on non-converging input
+        self.iterations += 1;
+        if self.iterations > MAX_CALC_ITERATIONS {
+            return Err(DecoCalculationError::NoConvergence);
+        }
+        if self.tts > Time::from_minutes(MAX_DECO_RUNTIME_MINS) {
+            return Err(DecoCalculationError::RuntimeLimitExceeded);
+        }
 
-            // handle deco actions
-            let mut deco_stages: [DecoStage; MAX_DECO_STAGE] = [ DecoStage::default(); MAX_DECO_STAGE ];
-            let (deco_action, next_switch_gas) = next_deco_action.unwrap();
-            match deco_action {
-                // deco obligation cleared
-                None => {
-                    break;
-                }
+        let DiveState {
+            depth: pre_stage_depth,
+            time: pre_stage_time,
+            gas: pre_stage_gas,
+            ..
+        } = sim_model.dive_state();
+        let ceiling = sim_model.ceiling();
+        if !(pre_stage_depth.as_meters().is_finite() && ceiling.as_meters().is_finite()) {
+            return Err(DecoCalculationError::InvalidState);
+        }
 
-                // handle mandatory deco stage
-                Some(deco_action) => {
-                    match deco_action {
-                        // ascent to min depth (deco stop or surface)
-                        DecoAction::AscentToCeil => {
-                            sim_model.record_travel_with_rate(
-                                self.deco_stop_depth(ceiling),
-                                ascent_rate,
-                                &pre_stage_gas,
-                            );
-                            let current_sim_state = sim_model.dive_state();
-                            let current_sim_time = current_sim_state.time;
-                            deco_stages.iter_mut().for_each(|stage| {
-                                if !stage.valid {
-                                    stage.valid = true;
-                                    stage.stage_type = DecoStageType::Ascent;
-                                    stage.start_depth = pre_stage_depth;
-                                    stage.end_depth = current_sim_state.depth;
-                                    stage.duration = current_sim_time - pre_stage_time;
-                                    stage.gas = pre_stage_gas;
-                                    return;
-                                }
-                            });
-                        }
+        // handle missed deco stop
+        // if missed deco stop, override sim model to depth at the expected stop and continue the calculation
+        let (deco_action, next_switch_gas) = match self.next_deco_action(&*sim_model, gas_mixes) {
+            Ok(next_deco_action) => next_deco_action,
+            Err(DecoActionErr::MissedDecoStopViolation) => {
+                sim_model.try_record(
+                    self.deco_stop_depth(ceiling),
+                    Time::zero(),
+                    &pre_stage_gas,
+                )?;
+                return Ok(false);
+            }
+            Err(DecoActionErr::Calculation(e)) => return Err(e),
+        };
+
+        // handle deco actions
+        match deco_action {
+            // deco obligation cleared
+            None => {
+                return Ok(true);
+            }
 
-                        // ascent to min depth with gas switch on next deco gas maximum operating depth
-                        DecoAction::AscentToGasSwitchDepth => {
-                            // @todo unwrap and handler err
-                            if let Some(next_switch_gas) = next_switch_gas {
-                                // travel to MOD
-                                let switch_gas_mod = next_switch_gas.max_operating_depth(1.6);
-                                sim_model.record_travel_with_rate(
-                                    switch_gas_mod,
+            // handle mandatory deco stage
+            Some(deco_action) => {
+                match deco_action {
+                    // ascent to min depth (deco stop or surface)
+                    DecoAction::AscentToCeil => {
+                        // no-deco ascent with safety stop
+                        if let Some(stop_depth) = self.planned_safety_stop(&*sim_model, ceiling) {
+                            let stage_gf = sim_model.gradient_factor_at(stop_depth);
+                            if pre_stage_depth > stop_depth {
+                                sim_model.try_record_travel_with_rate(
+                                    stop_depth,
                                     ascent_rate,
                                     &pre_stage_gas,
-                                );
-                                let DiveState {
-                                    depth: post_ascent_depth,
-                                    time: post_ascent_time,
-                                    ..
-                                } = sim_model.dive_state();
-                                deco_stages.iter_mut().for_each(|stage| {
-                                    if !stage.valid {
-                                        stage.valid = true;
-                                        stage.stage_type = DecoStageType::Ascent;
-                                        stage.start_depth = pre_stage_depth;
-                                        stage.end_depth = post_ascent_depth;
-                                        stage.duration = post_ascent_time - pre_stage_time;
-                                        stage.gas = pre_stage_gas;
-                                        return;
-                                    }
-                                });
-
-                                // switch gas @todo configurable gas change duration
-                                sim_model.record(
-                                    sim_model.dive_state().depth,
-                                    Time::zero(),
-                                    &next_switch_gas,
-                                );
-                                // @todo configurable oxygen window stop
-                                let post_switch_state = sim_model.dive_state();
-
-                                deco_stages.iter_mut().for_each(|stage| {
-                                    if !stage.valid {
-                                        stage.valid = true;
-                                        stage.stage_type = DecoStageType::GasSwitch;
-                                        stage.start_depth = post_ascent_depth;
-                                        stage.end_depth = post_switch_state.depth;
-                                        stage.duration = post_switch_state.time - post_ascent_time;
-                                        stage.gas = next_switch_gas;
-                                        return;
-                                    }
-                                });
+                                )?;
+                                let post_ascent_state = sim_model.dive_state();
+                                self.register_deco_stage(
+                                    DecoStage {
+                                        stage_type: DecoStageType::Ascent,
+                                        start_depth: pre_stage_depth,
+                                        end_depth: post_ascent_state.depth,
+                                        duration: post_ascent_state.time - pre_stage_time,
+                                        gas: pre_stage_gas,
+                                        gf: stage_gf,
+                                        valid: true,
+                                        ..DecoStage::default()
+                                    },
+                                    &*sim_model,
+                                )?;
                             }
+                            // remaining countdown after ascent through the stop band
+                            let Some(stop_duration) = sim_model.safety_stop().remaining() else {
+                                return Ok(false);
+                            };
+                            let pre_stop_time = sim_model.dive_state().time;
+                            sim_model.try_record(stop_depth, stop_duration, &pre_stage_gas)?;
+                            let sim_state = sim_model.dive_state();
+                            self.register_deco_stage(
+                                DecoStage {
+                                    stage_type: DecoStageType::SafetyStop,
+                                    start_depth: stop_depth,
+                                    end_depth: stop_depth,
+                                    duration: sim_state.time - pre_stop_time,
+                                    gas: pre_stage_gas,
+                                    gf: stage_gf,
+                                    valid: true,
+                                    ..DecoStage::default()
+                                },
+                                &*sim_model,
+                            )?;
+                            return Ok(false);
                         }
+                        let stop_depth = self.deco_stop_depth(ceiling);
+                        let stage_gf = sim_model.gradient_factor_at(stop_depth);
+                        sim_model.try_record_travel_with_rate(
+                            stop_depth,
+                            ascent_rate,
+                            &pre_stage_gas,
+                        )?;
+                        let current_sim_state = sim_model.dive_state();
+                        let current_sim_time = current_sim_state.time;
+                        self.register_deco_stage(
+                            DecoStage {
+                                stage_type: DecoStageType::Ascent,
+                                start_depth: pre_stage_depth,
+                                end_depth: current_sim_state.depth,
+                                duration: current_sim_time - pre_stage_time,
+                                gas: pre_stage_gas,
+                                gf: stage_gf,
+                                valid: true,
+                                ..DecoStage::default()
+                            },
+                            &*sim_model,
+                        )?;
+                    }
 
-                        // switch gas without ascent
-                        DecoAction::SwitchGas => {
-                            let switch_gas = next_switch_gas.unwrap();
-                            // @todo configurable gas switch duration
-                            sim_model.record(pre_stage_depth, Time::zero(), &switch_gas);
-                            deco_stages.iter_mut().for_each(|stage| {
-                                if !stage.valid {
-                                    stage.valid = true;
-                                    stage.stage_type = DecoStageType::GasSwitch;
-                                    stage.start_depth = pre_stage_depth;
-                                    stage.end_depth = pre_stage_depth;
-                                    stage.duration = Time::zero();
-                                    stage.gas = switch_gas;
-                                    return;
-                                }
-                            });
+                    // ascent to min depth with gas switch on next deco gas maximum operating depth
+                    DecoAction::AscentToGasSwitchDepth => {
+                        // @todo unwrap and handler err
+                        if let Some(next_switch_gas) = next_switch_gas {
+                            // travel to MOD (or max depth within gas density limit)
+                            let switch_gas_depth =
+                                self.gas_switch_depth(&*sim_model, &next_switch_gas);
+                            let stage_gf = sim_model.gradient_factor_at(switch_gas_depth);
+                            sim_model.try_record_travel_with_rate(
+                                switch_gas_depth,
+                                ascent_rate,
+                                &pre_stage_gas,
+                            )?;
+                            let DiveState {
+                                depth: post_ascent_depth,
+                                time: post_ascent_time,
+                                ..
+                            } = sim_model.dive_state();
+                            self.register_deco_stage(
+                                DecoStage {
+                                    stage_type: DecoStageType::Ascent,
+                                    start_depth: pre_stage_depth,
+                                    end_depth: post_ascent_depth,
+                                    duration: post_ascent_time - pre_stage_time,
+                                    gas: pre_stage_gas,
+                                    gf: stage_gf,
+                                    valid: true,
+                                    ..DecoStage::default()
+                                },
+                                &*sim_model,
+                            )?;
+
+                            // switch gas @todo configurable gas change duration
+                            sim_model.try_record(
+                                sim_model.dive_state().depth,
+                                Time::zero(),
+                                &next_switch_gas,
+                            )?;
+                            // @todo configurable oxygen window stop
+                            let post_switch_state = sim_model.dive_state();
+                            self.register_deco_stage(
+                                DecoStage {
+                                    stage_type: DecoStageType::GasSwitch,
+                                    start_depth: post_ascent_depth,
+                                    end_depth: post_switch_state.depth,
+                                    duration: post_switch_state.time - post_ascent_time,
+                                    gas: next_switch_gas,
+                                    gf: stage_gf,
+                                    valid: true,
+                                    ..DecoStage::default()
+                                },
+                                &*sim_model,
+                            )?;
                         }
+                    }
 
-                        // decompression stop (a series of 1s segments, merged into one on cleared stop)
-                        DecoAction::Stop => {
-                            sim_model.record(
-                                pre_stage_depth,
-                                Time::from_seconds(1.),
-                                &pre_stage_gas,
-                            );
-                            let sim_state = sim_model.dive_state();
-                            // @todo dedupe here on deco instead of of add deco
-                            deco_stages.iter_mut().for_each(|stage| {
-                                if !stage.valid {
-                                    stage.valid = true;
-                                    stage.stage_type = DecoStageType::DecoStop;
-                                    stage.start_depth = pre_stage_depth;
-                                    stage.end_depth = sim_state.depth;
-                                    stage.duration = sim_state.time - pre_stage_time;
-                                    stage.gas = sim_state.gas;
-                                    return;
-                                }
-                            })
+                    // switch gas without ascent
+                    DecoAction::SwitchGas => {
+                        if let Some(switch_gas) = next_switch_gas {
+                            // @todo configurable gas switch duration
+                            let stage_gf = sim_model.gradient_factor_at(pre_stage_depth);
+                            sim_model.try_record(pre_stage_depth, Time::zero(), &switch_gas)?;
+                            self.register_deco_stage(
+                                DecoStage {
+                                    stage_type: DecoStageType::GasSwitch,
+                                    start_depth: pre_stage_depth,
+                                    end_depth: pre_stage_depth,
+                                    duration: Time::zero(),
+                                    gas: switch_gas,
+                                    gf: stage_gf,
+                                    valid: true,
+                                    ..DecoStage::default()
+                                },
+                                &*sim_model,
+                            )?;
                         }
                     }
+
+                    // decompression stop (duration solved up to 1s resolution)
+                    DecoAction::Stop => {
+                        let stop_duration = self.deco_stop_duration(sim_model, gas_mixes)?;
+                        let stage_gf = sim_model.gradient_factor_at(pre_stage_depth);
+                        sim_model.try_record(pre_stage_depth, stop_duration, &pre_stage_gas)?;
+                        let sim_state = sim_model.dive_state();
+                        self.register_deco_stage(
+                            DecoStage {
+                                stage_type: DecoStageType::DecoStop,
+                                start_depth: pre_stage_depth,
+                                end_depth: sim_state.depth,
+                                duration: sim_state.time - pre_stage_time,
+                                gas: sim_state.gas,
+                                gf: stage_gf,
+                                valid: true,
+                                ..DecoStage::default()
+                            },
+                            &*sim_model,
+                        )?;
+                    }
                 }
             }
-            // register deco stages
-            deco_stages
-                .into_iter()
-                .for_each(|deco_stage| self.register_deco_stage(deco_stage));
         }
 
-        let tts = self.tts;
-        let mut tts_at_5 = Time::zero();
-        let mut tts_delta_at_5 = Time::zero();
-        if !self.is_sim() {
-            let mut nested_sim_deco = Deco::new_sim();
-            let mut nested_sim_model = deco_model.clone();
-            let DiveState {
-                depth: sim_depth,
-                gas: sim_gas,
-                ..
-            } = nested_sim_model.dive_state();
-            nested_sim_model.record(sim_depth, Time::from_minutes(5.), &sim_gas);
-            let nested_deco = nested_sim_deco
-                .calc(nested_sim_model, gas_mixes.clone())
-                .unwrap();
-            tts_at_5 = nested_deco.tts;
-            tts_delta_at_5 = tts_at_5 as Time - tts as Time;
-        }
+        Ok(false)
+    }
 
-        Ok(DecoRuntime {
+    /// deco runtime registered so far (TTS @+5 not calculated)
+    pub(crate) fn deco_runtime(&self, sim_model: &impl DecoModel) -> DecoRuntime<S> {
+        DecoRuntime {
             deco_stages: self.deco_stages.clone(),
-            tts,
-            tts_at_5,
-            tts_delta_at_5,
-        })
+            tts: self.tts,
+            gas_density_exceeded: self.gas_density_exceeded(sim_model),
+            cns: sim_model.cns(),
+            otu: sim_model.otu(),
+            ..DecoRuntime::default()
+        }
+    }
+
+    /// TTS @+5 (TTS in 5 min given current depth and gas mix)
+    pub(crate) fn tts_at_5<T: DecoModel + Clone + Sim>(
+        mut nested_sim_model: T,
+        gas_mixes: &[Gas],
+    ) -> Result<Time, DecoCalculationError> {
+        let mut nested_sim_deco = Deco::<S>::new_sim();
+        let DiveState {
+            depth: sim_depth,
+            gas: sim_gas,
+            ..
+        } = nested_sim_model.dive_state();
+        nested_sim_model.try_record(sim_depth, Time::from_minutes(5.), &sim_gas)?;
+        let nested_deco = nested_sim_deco.calc(nested_sim_model, gas_mixes)?;
+
+        Ok(nested_deco.tts)
+    }
+
+    // safety stop depth on direct no-deco ascent if required and planned
+    fn planned_safety_stop(&self, sim_model: &impl DecoModel, ceiling: Depth) -> Option<Depth> {
+        let config = sim_model.config().safety_stop();
+        if !config.planned
+            || !self.deco_stages.stages().is_empty()
+            || self.deco_stop_depth(ceiling) > Depth::zero()
+        {
+            return None;
+        }
+        sim_model.safety_stop().remaining()?;
+        let current_depth = sim_model.dive_state().depth;
+        match current_depth > config.stop_depth {
+            true => Some(config.stop_depth),
+            false if config.in_band(current_depth) => Some(current_depth),
+            false => None,
+        }
     }
 
     fn next_deco_action(
         &self,
         sim_model: &impl DecoModel,
-        gas_mixes: [Gas; MAX_GASSES],
-    ) -> Result<(Option<DecoAction>, Option<Gas>), MissedDecoStopViolation> {
+        gas_mixes: &[Gas],
+    ) -> Result<(Option<DecoAction>, Option<Gas>), DecoActionErr> {
         let DiveState {
             depth: current_depth,
             gas: current_gas,
@@ -326,18 +619,18 @@ impl Deco {
             Some(Ordering::Greater) => {
                 // check if deco violation
                 if current_depth < self.deco_stop_depth(ceiling) {
-                    return Err(MissedDecoStopViolation);
+                    return Err(DecoActionErr::MissedDecoStopViolation);
                 }
 
                 let next_switch_gas =
                     self.next_switch_gas(current_depth, &current_gas, gas_mixes, surface_pressure);
                 // check if within mod @todo min operational depth
                 if let Some(switch_gas) = next_switch_gas {
-                    //switch gas without ascent if within mod of next deco gas
-                    let gas_mod = switch_gas.max_operating_depth(1.6);
+                    //switch gas without ascent if within mod (and density limit) of next deco gas
+                    let gas_switch_depth = self.gas_switch_depth(sim_model, &switch_gas);
                     let gas_end = switch_gas.equivalent_narcotic_depth(current_depth);
                     if (switch_gas != current_gas)
-                        && (current_depth <= gas_mod)
+                        && (current_depth <= gas_switch_depth)
                         && (gas_end <= Depth::from_meters(DEFAULT_MAX_END_DEPTH))
                     {
                         return Ok((Some(DecoAction::SwitchGas), Some(switch_gas)));
@@ -351,7 +644,7 @@ impl Deco {
                 } else {
                     // ascent to next gas switch depth if next gas' MOD below ceiling
                     if let Some(next_switch_gas) = next_switch_gas {
-                        if next_switch_gas.max_operating_depth(1.6) >= ceiling {
+                        if self.gas_switch_depth(sim_model, &next_switch_gas) >= ceiling {
                             return Ok((
                                 Some(DecoAction::AscentToGasSwitchDepth),
                                 Some(next_switch_gas),
@@ -361,8 +654,64 @@ impl Deco {
                     Ok((Some(DecoAction::AscentToCeil), None))
                 }
             }
-            None => panic!("Ceiling and depth uncomparable"),
+            // ceiling and depth uncomparable
+            None => Err(DecoActionErr::Calculation(
+                DecoCalculationError::InvalidState,
+            )),
+        }
+    }
+
+    // min stop duration (full seconds) after which deco stop at current depth is cleared,
+    // exponential search for upper bound and bisection
+    fn deco_stop_duration<T: DecoModel>(
+        &self,
+        sim_model: &mut T,
+        gas_mixes: &[Gas],
+    ) -> Result<Time, DecoCalculationError> {
+        let DiveState { depth, gas, .. } = sim_model.dive_state();
+        // simulation model rolled back to pre-stop checkpoint for each probe and once solved
+        let checkpoint = sim_model.checkpoint();
+        let stop_cleared =
+            |sim_model: &mut T, seconds: u32| -> Result<bool, DecoCalculationError> {
+                sim_model.restore(&checkpoint);
+                sim_model.try_record(depth, Time::from_seconds(seconds as Float), &gas)?;
+                match self.next_deco_action(&*sim_model, gas_mixes) {
+                    Ok((Some(DecoAction::Stop), _)) => Ok(false),
+                    Ok(_) => Ok(true),
+                    Err(DecoActionErr::MissedDecoStopViolation) => Ok(true),
+                    Err(DecoActionErr::Calculation(e)) => Err(e),
+                }
+            };
+        let stop_seconds = Self::solve_stop_seconds(|seconds| stop_cleared(sim_model, seconds));
+        sim_model.restore(&checkpoint);
+
+        Ok(Time::from_seconds(stop_seconds? as Float))
+    }
+
+    // min seconds clearing the stop (exponential search, then bisection)
+    fn solve_stop_seconds(
+        mut stop_cleared: impl FnMut(u32) -> Result<bool, DecoCalculationError>,
+    ) -> Result<u32, DecoCalculationError> {
+        let max_stop_seconds = (MAX_DECO_RUNTIME_MINS * 60.) as u32;
+        let mut not_cleared: u32 = 0;
+        let mut cleared: u32 = 1;
+        while !stop_cleared(cleared)? {
+            if cleared >= max_stop_seconds {
+                return Err(DecoCalculationError::RuntimeLimitExceeded);
+            }
+            not_cleared = cleared;
+            cleared = (cleared * 2).min(max_stop_seconds);
         }
+        while cleared - not_cleared > 1 {
+            let mid = not_cleared + (cleared - not_cleared) / 2;
+            if stop_cleared(mid)? {
+                cleared = mid;
+            } else {
+                not_cleared = mid;
+            }
+        }
+
+        Ok(cleared)
     }
 
     /// check next deco gas in deco (the one with lowest MOD while more oxygen-rich than current)
@@ -370,84 +719,113 @@ impl Deco {
         &self,
         current_depth: Depth,
         current_gas: &Gas,
-        gas_mixes: [Gas; MAX_GASSES],
+        gas_mixes: &[Gas],
         surface_pressure: MbarPressure,
     ) -> Option<Gas> {
         let current_gas_partial_pressures =
             current_gas.partial_pressures(current_depth, surface_pressure);
-        // all potential deco gases that are more oxygen-rich than current (inc. trimix / heliox)
-        // let mut switch_gasses: [Gas; MAX_GASSES] = gas_mixes
-        //     .into_iter()
-        //     .filter(|gas| {
-        //         let partial_pressures = gas.partial_pressures(current_depth, surface_pressure);
-        //         partial_pressures.o2 > current_gas_partial_pressures.o2
-        //     })
-        //     .collect();
-        let mut switch_gasses: [Gas; MAX_GASSES] = gas_mixes.clone();
-        let mut i = 0;
-        for gas in gas_mixes.iter() {
+        // all potential deco gases that are more oxygen-rich than current (inc. trimix / heliox),
+        // mix with lowest MOD (by absolute o2 content) selected
+        let mut switch_gas: Option<Gas> = None;
+        for gas in gas_mixes.iter().filter(|gas| gas.is_valid()) {
             let partial_pressures = gas.partial_pressures(current_depth, surface_pressure);
-            if partial_pressures.o2 > current_gas_partial_pressures.o2 {
-                switch_gasses[i] = *gas;
-                i += 1;
+            if partial_pressures.o2 <= current_gas_partial_pressures.o2 {
+                continue;
+            }
+            let is_leaner = match switch_gas {
+                Some(selected) => {
+                    gas.gas_pressures_compound(1.).o2 < selected.gas_pressures_compound(1.).o2
+                }
+                None => true,
+            };
+            if is_leaner {
+                switch_gas = Some(*gas);
             }
         }
-        // sort deco gasses by o2 content
-        // switch_gasses[..i].sort_by(|a, b| {
-        //     let x = a.gas_pressures_compound(1.);
-        //     let y = b.gas_pressures_compound(1.);
-        //     x.o2.partial_cmp(&y.o2).unwrap()
-        // });
-        let mut j = 0;
-        panic!("switch_gasses: {:?}", switch_gasses);
-        for gas in switch_gasses.iter_mut() {
-            let x = gas.gas_pressures_compound(1.);
-            let y = gas.gas_pressures_compound(1.);
-            if x.o2 > y.o2 {
-                let temp = switch_gasses[j];
-                switch_gasses[j] = *gas;
-                *gas = temp;
-                j += 1;
+
+        switch_gas
+    }
+
+    // max depth a deco gas can be switched to at (MOD, capped by configured max gas density)
+    fn gas_switch_depth(&self, sim_model: &impl DecoModel, gas: &Gas) -> Depth {
+        let config = sim_model.config();
+        let gas_mod = gas.max_operating_depth(config.deco_max_ppo2());
+        match config.max_gas_density() {
+            Some(max_gas_density) => {
+                // rounded down to full meters
+                let density_limit_depth = Depth::from_meters(math::floor(
+                    gas.density_limit_depth(max_gas_density, config.surface_pressure())
+                        .as_meters(),
+                ));
+                if density_limit_depth < gas_mod {
+                    return density_limit_depth;
+                }
+                gas_mod
             }
+            None => gas_mod,
         }
+    }
 
-        // mix with lowest MOD (by absolute o2 content)
-        switch_gasses.first().copied()
+    // flag deco stages where breathed gas exceeds max gas density at the deepest point of the stage
+    fn gas_density_exceeded(&self, deco_model: &impl DecoModel) -> bool {
+        let config = deco_model.config();
+        let Some(max_gas_density) = config.max_gas_density() else {
+            return false;
+        };
+        self.deco_stages.stages().iter().any(|stage| {
+            let max_depth = match stage.end_depth > stage.start_depth {
+                true => stage.end_depth,
+                false => stage.start_depth,
+            };
+            let density: GasDensity = stage.gas.density(max_depth, config.surface_pressure());
+            density > max_gas_density
+        })
     }
 
-    fn register_deco_stage(&mut self, stage: DecoStage) {
+    fn register_deco_stage(
+        &mut self,
+        stage: DecoStage,
+        sim_model: &impl DecoModel,
+    ) -> Result<(), DecoCalculationError> {
         // dedupe iterative deco stops and merge into one
         let mut push_new = true;
-        let last_stage = self.deco_stages.last_mut();
-        if let Some(last_stage) = last_stage {
+        if let Some(last_stage) = self.deco_stages.stages_mut().last_mut() {
             if last_stage.stage_type == stage.stage_type {
                 last_stage.duration += stage.duration;
                 last_stage.end_depth = stage.end_depth;
+                last_stage.gf = stage.gf;
                 push_new = false;
             }
         }
         if push_new {
-            self.deco_stages.iter_mut().for_each(|stage| {
-                if !stage.valid {
-                    stage.valid = true;
-                    stage.stage_type = stage.stage_type;
-                    stage.start_depth = stage.start_depth;
-                    stage.end_depth = stage.end_depth;
-                    stage.duration = stage.duration;
-                    stage.gas = stage.gas;
-                    return;
-                }
-            });
+            self.deco_stages.push(stage)?;
         }
 
         // increment TTS by deco stage duration
         self.tts += stage.duration;
+
+        // runtime, gas and oxygen toxicity at stage end
+        let surface_pressure = sim_model.config().surface_pressure();
+        if let Some(last_stage) = self.deco_stages.stages_mut().last_mut() {
+            last_stage.runtime = sim_model.dive_state().time;
+            last_stage.pp_o2 = last_stage
+                .gas
+                .partial_pressures(last_stage.end_depth, surface_pressure)
+                .o2;
+            last_stage.end = last_stage
+                .gas
+                .equivalent_narcotic_depth(last_stage.end_depth);
+            last_stage.cns = sim_model.cns();
+            last_stage.otu = sim_model.otu();
+        }
+
+        Ok(())
     }
 
     // round ceiling up to the bottom of deco window
     fn deco_stop_depth(&self, ceiling: Depth) -> Depth {
         Depth::from_meters(
-            DEFAULT_CEILING_WINDOW * libm::ceil(ceiling.as_meters() as f64 / DEFAULT_CEILING_WINDOW as f64) as f32,
+            DEFAULT_CEILING_WINDOW * math::ceil(ceiling.as_meters() / DEFAULT_CEILING_WINDOW),
         )
     }
 
@@ -461,6 +839,9 @@ impl Deco {
         if gas_mixes.iter().all(|gas| !gas.is_valid()) {
             return Err(DecoCalculationError::EmptyGasList);
         }
+        if gas_mixes.len() > MAX_GASSES {
+            return Err(DecoCalculationError::GasListOverflow);
+        }
         let current_gas = deco_model.dive_state().gas;
         let current_gas_in_available = gas_mixes.iter().find(|gas_mix| **gas_mix == current_gas);
         if current_gas_in_available.is_none() {
@@ -475,6 +856,33 @@ mod tests {
     use super::*;
     use crate::BuehlmannModel;
 
+    #[test]
+    fn test_gas_mixes_overflow() {
+        let air = Gas::air();
+        let model = BuehlmannModel::default();
+        let mut deco: Deco = Deco::default();
+        let res = deco.calc(model.fork(), &[air; MAX_GASSES + 1]);
+        assert_eq!(res, Err(DecoCalculationError::GasListOverflow));
+        let mut deco: Deco = Deco::default();
+        assert!(deco.calc(model.fork(), &[air; MAX_GASSES]).is_ok());
+    }
+
+    #[test]
+    fn test_no_convergence() {
+        let air = Gas::air();
+        let mut model = BuehlmannModel::default();
+        model.record(Depth::from_meters(40.), Time::from_minutes(30.), &air);
+        let mut sim_model = model.fork();
+        let mut deco: Deco = Deco::new_sim();
+        deco.start_calc(&sim_model, &[air]).unwrap();
+        // iteration bound reached before deco obligation cleared
+        deco.iterations = MAX_CALC_ITERATIONS;
+        assert_eq!(
+            deco.calc_step(&mut sim_model, &[air]),
+            Err(DecoCalculationError::NoConvergence)
+        );
+    }
+
     #[test]
     fn test_ceiling_rounding() {
         let test_cases: [(DepthType, DepthType); 6] = [
@@ -485,7 +893,7 @@ mod tests {
             (3.00001, 6.),
             (12., 12.),
         ];
-        let deco = Deco::default();
+        let deco: Deco = Deco::default();
         for case in test_cases.into_iter() {
             let (input_depth, expected_depth) = case;
             let res = deco.deco_stop_depth(Depth::from_meters(input_depth));
@@ -493,6 +901,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_stage_buffer_overflow() {
+        let model = BuehlmannModel::default();
+        let mut deco: Deco = Deco::default();
+        // alternating stage types, no merging
+        for i in 0..MAX_DECO_STAGE {
+            let stage_type = match i % 2 {
+                0 => DecoStageType::Ascent,
+                _ => DecoStageType::DecoStop,
+            };
+            let stage = DecoStage {
+                stage_type,
+                valid: true,
+                ..DecoStage::default()
+            };
+            assert_eq!(deco.register_deco_stage(stage, &model), Ok(()));
+        }
+        let overflow_stage = DecoStage {
+            stage_type: DecoStageType::GasSwitch,
+            valid: true,
+            ..DecoStage::default()
+        };
+        assert_eq!(
+            deco.register_deco_stage(overflow_stage, &model),
+            Err(DecoCalculationError::StageBufferOverflow)
+        );
+    }
+
+    #[test]
+    fn test_runtime_limit_exceeded() {
+        let mut model = BuehlmannModel::default();
+        let air = Gas::air();
+        model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+        let mut deco: Deco = Deco {
+            tts: Time::from_minutes(MAX_DECO_RUNTIME_MINS + 1.),
+            ..Deco::default()
+        };
+        assert_eq!(
+            deco.calc(model, &build_gasses(air)),
+            Err(DecoCalculationError::RuntimeLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_deco_stop_duration_matches_stepping() {
+        let air = Gas::air();
+        let gas_mixes = [air];
+        let mut model = BuehlmannModel::default();
+        model.record(Depth::from_meters(40.), Time::from_minutes(30.), &air);
+
+        let deco: Deco = Deco::default();
+        let mut sim_model = model.fork();
+        let stop_depth = deco.deco_stop_depth(sim_model.ceiling());
+        sim_model.record(stop_depth, Time::zero(), &air);
+        let pre_stop_state = sim_model.dive_state();
+        let solved_duration = deco.deco_stop_duration(&mut sim_model, &gas_mixes).unwrap();
+        // simulation model rolled back after solving
+        assert_eq!(sim_model.dive_state().time, pre_stop_state.time);
+
+        // 1s stepping reference
+        let mut stepped_seconds = 0;
+        loop {
+            let next_deco_action = deco.next_deco_action(&sim_model, &gas_mixes).unwrap();
+            if next_deco_action.0 != Some(DecoAction::Stop) {
+                break;
+            }
+            sim_model.record(stop_depth, Time::from_seconds(1.), &air);
+            stepped_seconds += 1;
+        }
+        assert!(stepped_seconds > 0);
+        assert_eq!(
+            solved_duration,
+            Time::from_seconds(stepped_seconds as Float)
+        );
+    }
+
     fn build_gasses(gas: Gas) -> [Gas; MAX_GASSES] {
         let mut gasses = [Gas::default(); MAX_GASSES];
         gasses[0] = gas;
@@ -534,18 +1018,23 @@ mod tests {
             // air + ean50 + oxygen, ean50 withing MOD, oxygen out
             (20., air, build_3gasses(air, ean_50, oxygen), Some(ean_50)),
             // air + ean50 + oxy, deco on ean50, oxygen within MOD
-            (5.5, ean_50, build_3gasses(air, ean_50, oxygen), Some(oxygen)),
+            (
+                5.5,
+                ean_50,
+                build_3gasses(air, ean_50, oxygen),
+                Some(oxygen),
+            ),
             // air + heliox within o2 MOD, not considered deco gas
             (30., air, build_2gasses(air, trimix), Some(trimix)),
         ];
 
-        let deco = Deco::default();
+        let deco: Deco = Deco::default();
         for case in test_cases.into_iter() {
             let (current_depth, current_gas, available_gas_mixes, expected_switch_gas) = case;
             let res = deco.next_switch_gas(
                 Depth::from_meters(current_depth),
                 &current_gas,
-                available_gas_mixes,
+                &available_gas_mixes,
                 1000,
             );
             assert_eq!(res, expected_switch_gas);
@@ -554,21 +1043,21 @@ mod tests {
 
     #[test]
     fn should_err_on_empty_gas_mixes() {
-        let mut deco = Deco::default();
+        let mut deco: Deco = Deco::default();
         let deco_model = BuehlmannModel::default();
-        let deco_res = deco.calc(deco_model, [Gas::default(); 16]);
+        let deco_res = deco.calc(deco_model, &[Gas::default(); 16]);
         assert_eq!(deco_res, Err(DecoCalculationError::EmptyGasList));
     }
 
     #[test]
     fn should_err_on_gas_mixes_without_current_mix() {
-        let mut deco = Deco::default();
+        let mut deco: Deco = Deco::default();
         let mut deco_model = BuehlmannModel::default();
         let air = Gas::air();
         let ean50 = Gas::new(0.50, 0.);
         let tmx2135 = Gas::new(0.21, 0.35);
         deco_model.record_travel_with_rate(Depth::from_meters(40.), 10., &air);
-        let deco_res = deco.calc(deco_model, build_2gasses(ean50, tmx2135));
+        let deco_res = deco.calc(deco_model, &build_2gasses(ean50, tmx2135));
         assert_eq!(deco_res, Err(DecoCalculationError::CurrentGasNotInList));
     }
 }
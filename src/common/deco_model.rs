@@ -1,9 +1,17 @@
-use crate::common::deco::{DecoCalculationError, DecoRuntime};
-use crate::common::global_types::{CeilingType, MbarPressure};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::common::deco::{DecoCalculationError, DecoRuntime, DecoStage, DecoStageBuffer};
+use crate::common::global_types::{CeilingType, MbarPressure, NDLType, Pressure};
 use crate::common::ox_tox::OxTox;
-use crate::common::{AscentRatePerMinute, Cns, Gas, Otu};
+use crate::common::{
+    AscentRatePerMinute, Cns, Float, Gas, GasDensity, GradientFactor, Otu, SafetyStopConfig,
+    SafetyStopState,
+};
 use crate::common::{Depth, Time};
 
+pub(crate) const DEFAULT_NDL_CUT_OFF_MINS: Float = 99.;
+
 #[derive(Debug, PartialEq)]
 pub enum ConfigValidationErrorField {
     SurfacePressure,
@@ -11,16 +19,28 @@ pub enum ConfigValidationErrorField {
     CeilingType,
     RoundCeiling,
     GradientFactors,
+    MaxGasDensity,
+    DecoMaxPpO2,
+    NdlCutOff,
+    SafetyStop,
+    O2Fraction,
+    HeFraction,
+    GasFractions,
 }
 
+#[allow(non_camel_case_types)]
 #[derive(Debug, PartialEq)]
 pub enum ConfigValidationErrorReason {
     InvalidValue,
     OutOfRange,
-    GF_RANGE_ERR_MSG, //= "GF values have to be in 1-100 range",
-    GF_ORDER_ERR_MSG, //= "GFLow can't be higher than GFHigh",
+    GF_RANGE_ERR_MSG,         //= "GF values have to be in 1-100 range",
+    GF_ORDER_ERR_MSG,         //= "GFLow can't be higher than GFHigh",
     SURFACE_PRESSURE_ERR_MSG, //= "Surface pressure must be in milibars in 500-1500 range",
     DECO_ASCENT_RATE_ERR_MSG, //= "Ascent rate must in 1-30 m/s range",
+    MAX_GAS_DENSITY_ERR_MSG,  //= "Max gas density must be in 1-6.2 g/L range",
+    DECO_MAX_PPO2_ERR_MSG,    //= "Deco gas max ppO2 must be in 1-1.6 range",
+    NDL_CUT_OFF_ERR_MSG,      //= "NDL cut-off must be in 1-999 minutes range",
+    SAFETY_STOP_ERR_MSG, //= "Safety stop band must include stop depth above trigger depth, duration in 1s-30min range",
 }
 
 #[derive(Debug, PartialEq)]
@@ -41,6 +61,29 @@ pub trait DecoModelConfig {
     fn deco_ascent_rate(&self) -> AscentRatePerMinute;
     fn ceiling_type(&self) -> CeilingType;
     fn round_ceiling(&self) -> bool;
+    /// max gas density deco gases are checked against (default: not checked)
+    fn max_gas_density(&self) -> Option<GasDensity> {
+        None
+    }
+    /// max ppO2 deco gases are switched to at (gas switch MOD)
+    fn deco_max_ppo2(&self) -> Pressure {
+        1.6
+    }
+    /// NDL type (default: `Actual` with `Adaptive` ceiling, `ByCeiling` otherwise)
+    fn ndl_type(&self) -> NDLType {
+        match self.ceiling_type() {
+            CeilingType::Adaptive => NDLType::Actual,
+            CeilingType::Actual => NDLType::ByCeiling,
+        }
+    }
+    /// max reported NDL (default: 99 min)
+    fn ndl_cut_off(&self) -> Time {
+        Time::from_minutes(DEFAULT_NDL_CUT_OFF_MINS)
+    }
+    /// safety stop tracking and planning (default: not planned)
+    fn safety_stop(&self) -> SafetyStopConfig {
+        SafetyStopConfig::default()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -53,12 +96,24 @@ pub struct DiveState {
 
 pub trait DecoModel {
     type ConfigType: DecoModelConfig;
+    type Checkpoint: Clone;
 
     // default
     fn default() -> Self;
 
-    /// model init
-    fn new(config: Self::ConfigType) -> Self;
+    /// model init (panics on invalid config)
+    fn new(config: Self::ConfigType) -> Self
+    where
+        Self: Sized,
+    {
+        Self::try_new(config)
+            .unwrap_or_else(|e| panic!("Config error [{:?}]: {:?}", e.field, e.reason))
+    }
+
+    /// model init, Err on invalid config
+    fn try_new(config: Self::ConfigType) -> Result<Self, ConfigValidationErr>
+    where
+        Self: Sized;
 
     /// get model config
     fn config(&self) -> Self::ConfigType;
@@ -66,19 +121,64 @@ pub trait DecoModel {
     /// get model dive state
     fn dive_state(&self) -> DiveState;
 
-    /// record (depth: meters, time: seconds)
-    fn record(&mut self, depth: Depth, time: Time, gas: &Gas);
+    /// record (depth: meters, time: seconds), panics on invalid record
+    fn record(&mut self, depth: Depth, time: Time, gas: &Gas) {
+        if let Err(e) = self.try_record(depth, time, gas) {
+            panic!("Record error: {}", e);
+        }
+    }
+
+    /// record (depth: meters, time: seconds), Err on invalid record
+    fn try_record(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError>;
 
-    /// record linear ascent / descent record given travel time
-    fn record_travel(&mut self, target_depth: Depth, time: Time, gas: &Gas);
+    /// record linear ascent / descent record given travel time, panics on invalid record
+    fn record_travel(&mut self, target_depth: Depth, time: Time, gas: &Gas) {
+        if let Err(e) = self.try_record_travel(target_depth, time, gas) {
+            panic!("Record error: {}", e);
+        }
+    }
 
-    /// register linear ascent / descent record given rate
+    /// record linear ascent / descent record given travel time, Err on invalid record
+    fn try_record_travel(
+        &mut self,
+        target_depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError>;
+
+    /// register linear ascent / descent record given rate, panics on invalid record
     fn record_travel_with_rate(
         &mut self,
         target_depth: Depth,
         rate: AscentRatePerMinute,
         gas: &Gas,
-    );
+    ) {
+        if let Err(e) = self.try_record_travel_with_rate(target_depth, rate, gas) {
+            panic!("Record error: {}", e);
+        }
+    }
+
+    /// register linear ascent / descent record given rate, Err on invalid record
+    fn try_record_travel_with_rate(
+        &mut self,
+        target_depth: Depth,
+        rate: AscentRatePerMinute,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        if !(rate.is_finite() && rate > 0.) {
+            return Err(DecoCalculationError::InvalidTravelRate);
+        }
+        let mut distance = (target_depth - self.dive_state().depth).as_meters();
+        if distance < 0. {
+            distance = -distance;
+        }
+        self.try_record_travel(target_depth, Time::from_seconds(distance / rate * 60.), gas)
+    }
 
     /// current non decompression limit (NDL)
     fn ndl(&self) -> Time;
@@ -86,11 +186,33 @@ pub trait DecoModel {
     /// current decompression ceiling in meters
     fn ceiling(&self) -> Depth;
 
+    /// gradient factor in effect at given depth (default: 100, models without gradient factors)
+    fn gradient_factor_at(&self, _depth: Depth) -> GradientFactor {
+        100
+    }
+
     /// deco stages, TTL
     fn deco(
         &self,
         gas_mixes: [Gas; super::MAX_GASSES],
-    ) -> Result<DecoRuntime, DecoCalculationError>;
+    ) -> Result<DecoRuntime, DecoCalculationError> {
+        self.deco_with_buffer(&gas_mixes)
+    }
+
+    /// deco stages, TTL using given stages buffer (eg. `[DecoStage; N]` for custom capacity)
+    fn deco_with_buffer<S: DecoStageBuffer>(
+        &self,
+        gas_mixes: &[Gas],
+    ) -> Result<DecoRuntime<S>, DecoCalculationError>;
+
+    /// deco stages, TTL with growable deco stages list (up to MAX_GASSES gas mixes)
+    #[cfg(feature = "alloc")]
+    fn deco_vec(
+        &self,
+        gas_mixes: &[Gas],
+    ) -> Result<DecoRuntime<Vec<DecoStage>>, DecoCalculationError> {
+        self.deco_with_buffer(gas_mixes)
+    }
 
     /// central nervous system oxygen toxicity
     fn cns(&self) -> Cns;
@@ -98,6 +220,17 @@ pub trait DecoModel {
     /// pulmonary oxygen toxicity
     fn otu(&self) -> Otu;
 
+    /// safety stop state (inactive for models without safety stop tracking)
+    fn safety_stop(&self) -> SafetyStopState {
+        SafetyStopState::Inactive
+    }
+
+    /// copy of model state (config excluded) to roll back to
+    fn checkpoint(&self) -> Self::Checkpoint;
+
+    /// roll back model state to checkpoint
+    fn restore(&mut self, checkpoint: &Self::Checkpoint);
+
     /// is in deco check
     fn in_deco(&self) -> bool {
         let ceiling_type = self.config().ceiling_type();
@@ -105,9 +238,13 @@ pub trait DecoModel {
             CeilingType::Actual => self.ceiling() > Depth::zero(),
             CeilingType::Adaptive => {
                 let current_gas = self.dive_state().gas;
-                let runtime = self.deco([current_gas; 16]).unwrap();
-                let deco_stages = runtime.deco_stages;
-                deco_stages.len() > 1
+                match self.deco_with_buffer::<[DecoStage; super::MAX_DECO_STAGE]>(&[current_gas]) {
+                    Ok(runtime) => runtime.is_deco(),
+                    // more stages than a no-deco ascent takes
+                    Err(DecoCalculationError::StageBufferOverflow) => true,
+                    // fall back to actual ceiling if deco runtime can't be calculated
+                    Err(_) => self.ceiling() > Depth::zero(),
+                }
             }
         }
     }
@@ -0,0 +1,480 @@
+use crate::common::deco::{Deco, DecoCalculationError, DecoRuntime};
+use crate::common::{AscentRatePerMinute, DecoModel, Depth, Gas, Sim, Time, MAX_GASSES};
+
+/// deco runtime recalculation thresholds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DecoTrackerConfig {
+    // depth change since last calculation
+    pub depth_threshold: Depth,
+    // dive time elapsed since last calculation
+    pub time_threshold: Time,
+    // ceiling change since last calculation
+    pub ceiling_threshold: Depth,
+}
+
+impl Default for DecoTrackerConfig {
+    fn default() -> Self {
+        Self {
+            depth_threshold: Depth::from_meters(1.),
+            time_threshold: Time::from_seconds(30.),
+            ceiling_threshold: Depth::from_meters(0.5),
+        }
+    }
+}
+
+impl DecoTrackerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_depth_threshold(mut self, depth_threshold: Depth) -> Self {
+        self.depth_threshold = depth_threshold;
+        self
+    }
+
+    pub fn with_time_threshold(mut self, time_threshold: Time) -> Self {
+        self.time_threshold = time_threshold;
+        self
+    }
+
+    pub fn with_ceiling_threshold(mut self, ceiling_threshold: Depth) -> Self {
+        self.ceiling_threshold = ceiling_threshold;
+        self
+    }
+}
+
+// model state deco runtime was calculated for
+#[derive(Copy, Clone, Debug)]
+struct CalcState {
+    depth: Depth,
+    time: Time,
+    gas: Gas,
+    ceiling: Depth,
+}
+
+// deco calculation resumed across updates
+#[derive(Clone, Debug)]
+struct PendingCalc<T> {
+    // calculation started for model state of given calc id
+    calc_id: u32,
+    deco: Deco,
+    sim_model: T,
+}
+
+impl<T: DecoModel + Clone + Sim> PendingCalc<T> {
+    fn new(calc_id: u32, sim_model: T, gas_mixes: &[Gas]) -> Result<Self, DecoCalculationError> {
+        let mut deco: Deco = Deco::new_sim();
+        deco.start_calc(&sim_model, gas_mixes)?;
+        Ok(Self {
+            calc_id,
+            deco,
+            sim_model,
+        })
+    }
+
+    // single deco action, deco runtime once deco obligation cleared
+    fn step(&mut self, gas_mixes: &[Gas]) -> Result<Option<DecoRuntime>, DecoCalculationError> {
+        if !self.deco.calc_step(&mut self.sim_model, gas_mixes)? {
+            return Ok(None);
+        }
+        Ok(Some(self.deco.deco_runtime(&self.sim_model)))
+    }
+}
+
+/// stateful deco runtime, recalculated only on model state changes beyond thresholds
+#[derive(Clone, Debug)]
+pub struct DecoTracker<T: DecoModel + Clone + Sim> {
+    model: T,
+    gas_mixes: [Gas; MAX_GASSES],
+    gas_mixes_count: usize,
+    config: DecoTrackerConfig,
+    runtime: Option<DecoRuntime>,
+    // calc id of model state runtime was calculated for
+    runtime_calc_id: u32,
+    calc_state: Option<CalcState>,
+    calc_id: u32,
+    deco_calc: Option<PendingCalc<T>>,
+    tts_at_5_calc: Option<PendingCalc<T>>,
+    // TTS @+5 calculation for latest model state, started once pending one finished
+    queued_tts_at_5_calc: Option<PendingCalc<T>>,
+}
+
+impl<T: DecoModel + Clone + Sim> DecoTracker<T> {
+    pub fn new(model: T, gas_mixes: &[Gas]) -> Self {
+        Self::try_new(model, gas_mixes).unwrap_or_else(|e| panic!("Gas mixes error: {}", e))
+    }
+
+    /// tracker with available gas mixes (up to MAX_GASSES)
+    pub fn try_new(model: T, gas_mixes: &[Gas]) -> Result<Self, DecoCalculationError> {
+        let mut tracker = Self {
+            model,
+            gas_mixes: [Gas::default(); MAX_GASSES],
+            gas_mixes_count: 0,
+            config: DecoTrackerConfig::default(),
+            runtime: None,
+            runtime_calc_id: 0,
+            calc_state: None,
+            calc_id: 0,
+            deco_calc: None,
+            tts_at_5_calc: None,
+            queued_tts_at_5_calc: None,
+        };
+        tracker.set_gas_mixes(gas_mixes)?;
+        Ok(tracker)
+    }
+
+    pub fn with_config(mut self, config: DecoTrackerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// tracked deco model
+    pub fn model(&self) -> &T {
+        &self.model
+    }
+
+    /// available gas mixes
+    pub fn gas_mixes(&self) -> &[Gas] {
+        &self.gas_mixes[..self.gas_mixes_count]
+    }
+
+    /// available gas mixes (up to MAX_GASSES), forces recalculation
+    pub fn set_gas_mixes(&mut self, gas_mixes: &[Gas]) -> Result<(), DecoCalculationError> {
+        if gas_mixes.len() > MAX_GASSES {
+            return Err(DecoCalculationError::GasListOverflow);
+        }
+        self.gas_mixes[..gas_mixes.len()].copy_from_slice(gas_mixes);
+        self.gas_mixes_count = gas_mixes.len();
+        self.invalidate();
+        Ok(())
+    }
+
+    /// force deco runtime recalculation on next update, pending calculations dropped
+    pub fn invalidate(&mut self) {
+        self.calc_state = None;
+        self.deco_calc = None;
+        self.tts_at_5_calc = None;
+        self.queued_tts_at_5_calc = None;
+    }
+
+    /// record (depth: meters, time: seconds)
+    pub fn record(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        self.model.try_record(depth, time, gas)
+    }
+
+    /// record linear ascent / descent record given travel time
+    pub fn record_travel(
+        &mut self,
+        target_depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        self.model.try_record_travel(target_depth, time, gas)
+    }
+
+    /// record linear ascent / descent record given rate
+    pub fn record_travel_with_rate(
+        &mut self,
+        target_depth: Depth,
+        rate: AscentRatePerMinute,
+        gas: &Gas,
+    ) -> Result<(), DecoCalculationError> {
+        self.model
+            .try_record_travel_with_rate(target_depth, rate, gas)
+    }
+
+    /// last calculated deco runtime
+    pub fn runtime(&self) -> Option<&DecoRuntime> {
+        self.runtime.as_ref()
+    }
+
+    /// deco stages and TTS calculation in progress (deferred by time budget)
+    pub fn is_deco_pending(&self) -> bool {
+        self.deco_calc.is_some()
+    }
+
+    /// TTS @+5 not yet calculated for current deco runtime (deferred by time budget)
+    pub fn is_tts_at_5_pending(&self) -> bool {
+        self.tts_at_5_calc.is_some() || self.queued_tts_at_5_calc.is_some()
+    }
+
+    /// model state changed beyond thresholds since last calculation
+    pub fn is_stale(&self) -> bool {
+        let Some(calc_state) = self.calc_state else {
+            return true;
+        };
+        let state = self.model.dive_state();
+        if state.gas != calc_state.gas {
+            return true;
+        }
+        if abs_depth(state.depth - calc_state.depth) > self.config.depth_threshold {
+            return true;
+        }
+        if (state.time - calc_state.time) >= self.config.time_threshold {
+            return true;
+        }
+        abs_depth(self.model.ceiling() - calc_state.ceiling) > self.config.ceiling_threshold
+    }
+
+    /// up to date deco runtime, recalculated if stale
+    pub fn update(&mut self) -> Result<&DecoRuntime, DecoCalculationError> {
+        self.update_within_budget(u64::MAX, || 0)?
+            .ok_or(DecoCalculationError::InvalidState)
+    }
+
+    /// deco runtime calculated within time budget (in clock units), None until first calculated.
+    /// Calculation started when stale is resumed on next updates once budget is used, deco stages
+    /// and TTS first, then TTS @+5. At least one deco action is calculated per update.
+    /// TTS Δ+5 is zero until TTS @+5 is calculated for the same model state as TTS
+    pub fn update_within_budget<F: FnMut() -> u64>(
+        &mut self,
+        budget: u64,
+        mut clock: F,
+    ) -> Result<Option<&DecoRuntime>, DecoCalculationError> {
+        let start = clock();
+
+        // pending deco stages calculation finished before stale state recalculated
+        if self.deco_calc.is_none() && self.is_stale() {
+            if let Err(e) = self.start_calc() {
+                self.invalidate();
+                return Err(e);
+            }
+        }
+
+        loop {
+            match self.calc_step() {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(e) => {
+                    self.invalidate();
+                    return Err(e);
+                }
+            }
+            if clock().saturating_sub(start) >= budget {
+                break;
+            }
+        }
+
+        Ok(self.runtime.as_ref())
+    }
+
+    // deco stages and TTS @+5 calculations for current model state,
+    // pending TTS @+5 calculation not replaced (queued instead) so it completes on continuous changes
+    fn start_calc(&mut self) -> Result<(), DecoCalculationError> {
+        let gas_mixes = &self.gas_mixes[..self.gas_mixes_count];
+        let state = self.model.dive_state();
+        let calc_id = self.calc_id.wrapping_add(1);
+        let deco_calc = PendingCalc::new(calc_id, self.model.fork(), gas_mixes)?;
+        // current depth and gas held for 5 minutes
+        let mut at_5_model = self.model.fork();
+        at_5_model.try_record(state.depth, Time::from_minutes(5.), &state.gas)?;
+        let tts_at_5_calc = PendingCalc::new(calc_id, at_5_model, gas_mixes)?;
+
+        self.calc_id = calc_id;
+        self.calc_state = Some(CalcState {
+            depth: state.depth,
+            time: state.time,
+            gas: state.gas,
+            ceiling: self.model.ceiling(),
+        });
+        self.deco_calc = Some(deco_calc);
+        match self.tts_at_5_calc {
+            Some(_) => self.queued_tts_at_5_calc = Some(tts_at_5_calc),
+            None => self.tts_at_5_calc = Some(tts_at_5_calc),
+        }
+        Ok(())
+    }
+
+    // single deco action of pending calculations, true if nothing pending
+    fn calc_step(&mut self) -> Result<bool, DecoCalculationError> {
+        let gas_mixes = &self.gas_mixes[..self.gas_mixes_count];
+
+        if let Some(deco_calc) = self.deco_calc.as_mut() {
+            if let Some(runtime) = deco_calc.step(gas_mixes)? {
+                // last TTS @+5 kept, Δ+5 unknown until TTS @+5 recalculated for this state
+                let tts_at_5 = match &self.runtime {
+                    Some(prev_runtime) => prev_runtime.tts_at_5,
+                    None => Time::zero(),
+                };
+                self.runtime = Some(DecoRuntime {
+                    tts_at_5,
+                    tts_delta_at_5: Time::zero(),
+                    ..runtime
+                });
+                self.runtime_calc_id = deco_calc.calc_id;
+                self.deco_calc = None;
+            }
+            return Ok(false);
+        }
+
+        if let Some(tts_at_5_calc) = self.tts_at_5_calc.as_mut() {
+            if let Some(runtime_at_5) = tts_at_5_calc.step(gas_mixes)? {
+                if let Some(runtime) = self.runtime.as_mut() {
+                    runtime.tts_at_5 = runtime_at_5.tts;
+                    // Δ+5 only against TTS of the same model state
+                    if tts_at_5_calc.calc_id == self.runtime_calc_id {
+                        runtime.tts_delta_at_5 = runtime_at_5.tts - runtime.tts;
+                    }
+                }
+                self.tts_at_5_calc = self.queued_tts_at_5_calc.take();
+            }
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+fn abs_depth(depth: Depth) -> Depth {
+    if depth < Depth::zero() {
+        return Depth::zero() - depth;
+    }
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BuehlmannModel;
+
+    fn tracker_in_deco() -> DecoTracker<BuehlmannModel> {
+        let air = Gas::air();
+        let mut model = BuehlmannModel::default();
+        model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+        DecoTracker::new(model, &[air, Gas::new(0.5, 0.)])
+    }
+
+    #[test]
+    fn test_update_matches_deco() {
+        let mut tracker = tracker_in_deco();
+        let expected_runtime = tracker
+            .model()
+            .deco_with_buffer(&[Gas::air(), Gas::new(0.5, 0.)])
+            .unwrap();
+        let runtime = tracker.update().unwrap();
+        assert_eq!(runtime, &expected_runtime);
+        assert!(!tracker.is_deco_pending());
+        assert!(!tracker.is_tts_at_5_pending());
+    }
+
+    #[test]
+    fn test_gas_mixes_overflow() {
+        let gas_mixes = [Gas::air(); MAX_GASSES + 1];
+        let res = DecoTracker::try_new(BuehlmannModel::default(), &gas_mixes);
+        assert_eq!(res.err(), Some(DecoCalculationError::GasListOverflow));
+
+        let mut tracker = tracker_in_deco();
+        let res = tracker.set_gas_mixes(&gas_mixes);
+        assert_eq!(res, Err(DecoCalculationError::GasListOverflow));
+        assert_eq!(tracker.gas_mixes(), &[Gas::air(), Gas::new(0.5, 0.)]);
+    }
+
+    #[test]
+    fn test_recalculation_thresholds() {
+        let air = Gas::air();
+        let mut tracker = tracker_in_deco();
+        let initial_tts = tracker.update().unwrap().tts;
+        assert!(!tracker.is_stale());
+
+        // below thresholds, cached runtime kept
+        tracker
+            .record(Depth::from_meters(40.), Time::from_seconds(10.), &air)
+            .unwrap();
+        assert!(!tracker.is_stale());
+        assert_eq!(tracker.update().unwrap().tts, initial_tts);
+
+        // time threshold exceeded
+        tracker
+            .record(Depth::from_meters(40.), Time::from_seconds(20.), &air)
+            .unwrap();
+        assert!(tracker.is_stale());
+        assert!(tracker.update().unwrap().tts > initial_tts);
+
+        // depth threshold exceeded
+        tracker
+            .record(Depth::from_meters(38.), Time::zero(), &air)
+            .unwrap();
+        assert!(tracker.is_stale());
+    }
+
+    #[test]
+    fn test_calculation_resumed_within_budget() {
+        let mut expected_tracker = tracker_in_deco();
+        let expected_runtime = expected_tracker.update().unwrap().clone();
+
+        // single deco action per update
+        let mut tracker = tracker_in_deco();
+        let mut clock_ticks = 0;
+        let mut updates = 0;
+        while tracker.runtime().is_none() {
+            let runtime = tracker
+                .update_within_budget(5, || {
+                    clock_ticks += 10;
+                    clock_ticks
+                })
+                .unwrap();
+            assert_eq!(runtime.is_none(), tracker.is_deco_pending());
+            updates += 1;
+        }
+        assert!(updates > 1);
+        let runtime = tracker.runtime().unwrap();
+        assert_eq!(runtime.tts, expected_runtime.tts);
+        assert_eq!(runtime.tts_at_5, Time::zero());
+        assert!(tracker.is_tts_at_5_pending());
+
+        // TTS @+5 resumed on next updates
+        while tracker.is_tts_at_5_pending() {
+            tracker
+                .update_within_budget(5, || {
+                    clock_ticks += 10;
+                    clock_ticks
+                })
+                .unwrap();
+        }
+        assert_eq!(tracker.runtime(), Some(&expected_runtime));
+    }
+
+    #[test]
+    fn test_tts_at_5_completed_on_continuous_changes() {
+        let air = Gas::air();
+        let mut tracker = tracker_in_deco();
+        let mut clock_ticks = 0;
+        let mut clock = || {
+            clock_ticks += 10;
+            clock_ticks
+        };
+
+        // stale on every update, pending TTS @+5 not replaced
+        let mut depth = 40.;
+        let mut updates = 0;
+        while tracker
+            .runtime()
+            .is_none_or(|runtime| runtime.tts_at_5 == Time::zero())
+        {
+            depth = if depth == 40. { 38. } else { 40. };
+            tracker
+                .record(Depth::from_meters(depth), Time::zero(), &air)
+                .unwrap();
+            tracker.update_within_budget(25, &mut clock).unwrap();
+            updates += 1;
+            assert!(updates < 10_000);
+        }
+        // TTS @+5 of earlier model state, Δ+5 not paired with current TTS
+        assert!(tracker.is_tts_at_5_pending());
+        assert_eq!(tracker.runtime().unwrap().tts_delta_at_5, Time::zero());
+
+        // TTS @+5 recalculated for settled model state
+        while tracker.is_deco_pending() || tracker.is_tts_at_5_pending() {
+            tracker.update_within_budget(25, &mut clock).unwrap();
+        }
+        let mut expected_tracker = tracker.clone();
+        expected_tracker.invalidate();
+        let expected_runtime = expected_tracker.update().unwrap();
+        assert_eq!(tracker.runtime(), Some(expected_runtime));
+    }
+}
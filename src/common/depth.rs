@@ -1,7 +1,10 @@
 use core::fmt;
 use core::ops::{Add, AddAssign, Div, Mul, Sub};
 
-use super::DepthType;
+use super::{
+    real::{self, Real},
+    DepthType, Float,
+};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Units {
@@ -9,7 +12,7 @@ pub enum Units {
     Imperial,
 }
 
-pub trait Unit<T = f32>: Sized {
+pub trait Unit<T = Float>: Sized {
     fn from_units(val: T, units: Units) -> Self;
     fn to_units(&self, units: Units) -> T;
     fn base_unit(&self) -> T;
@@ -17,12 +20,12 @@ pub trait Unit<T = f32>: Sized {
 
 #[derive(Clone, Copy, Debug)]
 pub struct Depth {
-    m: DepthType,
+    m: Real,
 }
 
 impl Default for Depth {
     fn default() -> Self {
-        Self { m: 0. }
+        Self { m: real::zero() }
     }
 }
 
@@ -68,11 +71,13 @@ impl Mul<Self> for Depth {
     }
 }
 
-impl Mul<f32> for Depth {
+impl Mul<Float> for Depth {
     type Output = Self;
 
-    fn mul(self, rhs: f32) -> Self::Output {
-        Self { m: self.m * rhs }
+    fn mul(self, rhs: Float) -> Self::Output {
+        Self {
+            m: self.m * real::from_float(rhs),
+        }
     }
 }
 
@@ -84,11 +89,13 @@ impl Div<Self> for Depth {
     }
 }
 
-impl Div<f32> for Depth {
+impl Div<Float> for Depth {
     type Output = Self;
 
-    fn div(self, rhs: f32) -> Self::Output {
-        Self { m: self.m / rhs }
+    fn div(self, rhs: Float) -> Self::Output {
+        Self {
+            m: self.m / real::from_float(rhs),
+        }
     }
 }
 
@@ -111,28 +118,34 @@ impl Unit for Depth {
             Units::Imperial => self.as_feet(),
         }
     }
-    fn base_unit(&self) -> f32 {
-        self.m
+    fn base_unit(&self) -> Float {
+        self.as_meters()
     }
 }
 
 impl Depth {
     pub fn zero() -> Self {
-        Self { m: 0. }
+        Self { m: real::zero() }
     }
     pub fn from_meters(val: DepthType) -> Self {
-        Self { m: val }
-    }
-    pub fn from_feet(val: DepthType) -> Self {
         Self {
-            m: Self::ft_to_m(val),
+            m: real::from_float(val),
         }
     }
+    pub fn from_feet(val: DepthType) -> Self {
+        Self::from_meters(Self::ft_to_m(val))
+    }
     pub fn as_meters(&self) -> DepthType {
-        self.m
+        real::to_float(self.m)
     }
     pub fn as_feet(&self) -> DepthType {
-        Self::m_to_ft(self.m)
+        Self::m_to_ft(self.as_meters())
+    }
+    pub(crate) fn from_real_meters(val: Real) -> Self {
+        Self { m: val }
+    }
+    pub(crate) fn as_real_meters(&self) -> Real {
+        self.m
     }
     fn m_to_ft(m: DepthType) -> DepthType {
         m * 3.28084
@@ -150,14 +163,14 @@ mod tests {
     fn m_to_ft() {
         let depth = Depth::from_meters(1.);
         let ft = depth.as_feet();
-        assert_eq!(ft, 3.28084);
+        assert_close_to_abs!(ft, 3.28084, 1e-6);
     }
 
     #[test]
     fn ft_to_m() {
         let depth = Depth::from_feet(100.);
         let m = depth.as_meters();
-        assert_eq!(m, 30.48);
+        assert_close_to_abs!(m, 30.48, 1e-6);
     }
 
     #[test]
@@ -173,15 +186,15 @@ mod tests {
     fn from_units_constructor() {
         let depth_m = Depth::from_units(1., Units::Metric);
         assert_eq!(depth_m.as_meters(), 1.);
-        assert_eq!(depth_m.as_feet(), 3.28084);
+        assert_close_to_abs!(depth_m.as_feet(), 3.28084, 1e-6);
 
         let depth_ft = Depth::from_units(1., Units::Imperial);
         assert_eq!(with_precision(depth_ft.as_feet(), 5), 1.);
-        assert_eq!(depth_ft.as_meters(), 0.3048);
+        assert_close_to_abs!(depth_ft.as_meters(), 0.3048, 1e-6);
     }
 
-    fn with_precision(x: f32, precision: u32) -> f32 {
-        let d = 10_u32.pow(precision) as f32;
+    fn with_precision(x: Float, precision: u32) -> Float {
+        let d = 10_u32.pow(precision) as Float;
         (x * d).round() / d
     }
 }
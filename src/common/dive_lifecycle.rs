@@ -0,0 +1,409 @@
+use crate::common::deco::DecoCalculationError;
+use crate::common::{DecoModel, Depth, Float, Gas, Time};
+
+/// dive start / end detection thresholds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DiveLifecycleConfig {
+    // dive started below
+    pub start_depth: Depth,
+    // surfaced above
+    pub end_depth: Depth,
+    // dive ended after given time at the surface
+    pub end_delay: Time,
+    // post-dive phase duration
+    pub post_dive_time: Time,
+}
+
+impl Default for DiveLifecycleConfig {
+    fn default() -> Self {
+        Self {
+            start_depth: Depth::from_meters(1.2),
+            end_depth: Depth::from_meters(1.),
+            end_delay: Time::from_seconds(60.),
+            post_dive_time: Time::from_minutes(10.),
+        }
+    }
+}
+
+impl DiveLifecycleConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_start_depth(mut self, start_depth: Depth) -> Self {
+        self.start_depth = start_depth;
+        self
+    }
+
+    pub fn with_end_depth(mut self, end_depth: Depth) -> Self {
+        self.end_depth = end_depth;
+        self
+    }
+
+    pub fn with_end_delay(mut self, end_delay: Time) -> Self {
+        self.end_delay = end_delay;
+        self
+    }
+
+    pub fn with_post_dive_time(mut self, post_dive_time: Time) -> Self {
+        self.post_dive_time = post_dive_time;
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DivePhase {
+    Surface,
+    Dive,
+    PostDive,
+}
+
+/// per-dive counters
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DiveCounters {
+    pub number: u32,
+    // model time at dive start
+    pub start_time: Time,
+    pub max_depth: Depth,
+    pub avg_depth: Depth,
+    pub dive_time: Time,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LifecycleEvent {
+    DiveStarted { number: u32 },
+    DiveEnded(DiveCounters),
+    PostDiveEnded,
+}
+
+// dive counters accumulator
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct DiveAcc {
+    counters: DiveCounters,
+    // depth (m) x time (s) integral
+    depth_time: Float,
+}
+
+impl DiveAcc {
+    fn new(number: u32, start_time: Time) -> Self {
+        Self {
+            counters: DiveCounters {
+                number,
+                start_time,
+                max_depth: Depth::zero(),
+                avg_depth: Depth::zero(),
+                dive_time: Time::zero(),
+            },
+            depth_time: 0.,
+        }
+    }
+
+    fn add(&mut self, depth: Depth, time: Time) {
+        let counters = &mut self.counters;
+        if depth > counters.max_depth {
+            counters.max_depth = depth;
+        }
+        counters.dive_time += time;
+        self.depth_time += depth.as_meters() * time.as_seconds();
+        if counters.dive_time > Time::zero() {
+            counters.avg_depth =
+                Depth::from_meters(self.depth_time / counters.dive_time.as_seconds());
+        }
+    }
+}
+
+/// dive lifecycle (surface, dive, post-dive) on top of a deco model, driven by sensor samples.
+/// Records outside of a dive are registered as surface records breathing air
+#[derive(Clone, Debug)]
+pub struct DiveLifecycle<T: DecoModel> {
+    model: T,
+    config: DiveLifecycleConfig,
+    phase: DivePhase,
+    dives_count: u32,
+    dive: Option<DiveAcc>,
+    // time above end depth and counters when surfaced
+    surfaced: Option<(Time, DiveAcc)>,
+    last_dive: Option<DiveCounters>,
+    post_dive_elapsed: Time,
+}
+
+impl<T: DecoModel> DiveLifecycle<T> {
+    pub fn new(model: T) -> Self {
+        Self {
+            model,
+            config: DiveLifecycleConfig::default(),
+            phase: DivePhase::Surface,
+            dives_count: 0,
+            dive: None,
+            surfaced: None,
+            last_dive: None,
+            post_dive_elapsed: Time::zero(),
+        }
+    }
+
+    pub fn with_config(mut self, config: DiveLifecycleConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// number of previously logged dives (next dive number continues from it)
+    pub fn with_dives_count(mut self, dives_count: u32) -> Self {
+        self.dives_count = dives_count;
+        self
+    }
+
+    /// underlying deco model
+    pub fn model(&self) -> &T {
+        &self.model
+    }
+
+    pub fn phase(&self) -> DivePhase {
+        self.phase
+    }
+
+    pub fn dives_count(&self) -> u32 {
+        self.dives_count
+    }
+
+    /// current dive counters
+    pub fn dive(&self) -> Option<DiveCounters> {
+        self.dive.map(|acc| acc.counters)
+    }
+
+    /// last ended dive counters
+    pub fn last_dive(&self) -> Option<DiveCounters> {
+        self.last_dive
+    }
+
+    /// sensor sample (depth, time since previous sample, breathing gas)
+    pub fn record(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<Option<LifecycleEvent>, DecoCalculationError> {
+        if self.phase != DivePhase::Dive && depth > self.config.start_depth {
+            return self.start_dive(depth, time, gas).map(Some);
+        }
+        match self.phase {
+            DivePhase::Dive => self.record_dive(depth, time, gas),
+            _ => self.record_surface(depth, time, gas),
+        }
+    }
+
+    /// surface interval without samples (eg. device in sleep mode), breathing air
+    pub fn record_surface_interval(
+        &mut self,
+        time: Time,
+    ) -> Result<Option<LifecycleEvent>, DecoCalculationError> {
+        match self.phase {
+            DivePhase::Dive => self.record_dive(Depth::zero(), time, &Gas::air()),
+            _ => self.record_surface(Depth::zero(), time, &Gas::air()),
+        }
+    }
+
+    // sample outside of a dive (above start depth), post-dive phase countdown
+    fn record_surface(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<Option<LifecycleEvent>, DecoCalculationError> {
+        self.model.try_record(depth, time, gas)?;
+        if self.phase == DivePhase::PostDive {
+            self.post_dive_elapsed += time;
+            if self.post_dive_elapsed >= self.config.post_dive_time {
+                self.phase = DivePhase::Surface;
+                return Ok(Some(LifecycleEvent::PostDiveEnded));
+            }
+        }
+        Ok(None)
+    }
+
+    fn start_dive(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<LifecycleEvent, DecoCalculationError> {
+        let start_time = self.model.dive_state().time;
+        self.model.try_record(depth, time, gas)?;
+        self.dives_count += 1;
+        let mut acc = DiveAcc::new(self.dives_count, start_time);
+        acc.add(depth, time);
+        self.dive = Some(acc);
+        self.surfaced = None;
+        self.phase = DivePhase::Dive;
+
+        Ok(LifecycleEvent::DiveStarted {
+            number: self.dives_count,
+        })
+    }
+
+    fn record_dive(
+        &mut self,
+        depth: Depth,
+        time: Time,
+        gas: &Gas,
+    ) -> Result<Option<LifecycleEvent>, DecoCalculationError> {
+        self.model.try_record(depth, time, gas)?;
+        let Some(acc) = self.dive.as_mut() else {
+            return Err(DecoCalculationError::InvalidState);
+        };
+
+        if depth > self.config.end_depth {
+            // time spent at the surface included in resumed dive
+            if let Some((surface_time, _)) = self.surfaced.take() {
+                acc.add(Depth::zero(), surface_time);
+            }
+            acc.add(depth, time);
+            return Ok(None);
+        }
+
+        // dive counters frozen at surfacing, dive resumed if descended before end delay
+        let (surface_time, surfaced_acc) = match self.surfaced {
+            Some((surface_time, surfaced_acc)) => (surface_time + time, surfaced_acc),
+            None => {
+                acc.add(depth, time);
+                (Time::zero(), *acc)
+            }
+        };
+        if surface_time < self.config.end_delay {
+            self.surfaced = Some((surface_time, surfaced_acc));
+            return Ok(None);
+        }
+
+        let counters = surfaced_acc.counters;
+        self.last_dive = Some(counters);
+        self.dive = None;
+        self.surfaced = None;
+        self.phase = DivePhase::PostDive;
+        self.post_dive_elapsed = surface_time;
+
+        Ok(Some(LifecycleEvent::DiveEnded(counters)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BuehlmannModel;
+
+    fn lifecycle() -> DiveLifecycle<BuehlmannModel> {
+        DiveLifecycle::new(BuehlmannModel::default())
+    }
+
+    fn sample(
+        lifecycle: &mut DiveLifecycle<BuehlmannModel>,
+        meters: Float,
+        seconds: Float,
+    ) -> Option<LifecycleEvent> {
+        lifecycle
+            .record(
+                Depth::from_meters(meters),
+                Time::from_seconds(seconds),
+                &Gas::air(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_surface_samples() {
+        let mut lifecycle = lifecycle();
+        assert_eq!(sample(&mut lifecycle, 0.5, 60.), None);
+        assert_eq!(lifecycle.phase(), DivePhase::Surface);
+        assert_eq!(lifecycle.dive(), None);
+
+        // surface samples recorded at sampled depth and gas
+        let ean50 = Gas::ean(50);
+        lifecycle
+            .record(Depth::from_meters(1.), Time::from_seconds(60.), &ean50)
+            .unwrap();
+        let state = lifecycle.model().dive_state();
+        assert_eq!(state.depth, Depth::from_meters(1.));
+        assert_eq!(state.gas, ean50);
+        assert_eq!(state.time, Time::from_seconds(120.));
+        assert_eq!(lifecycle.dive(), None);
+
+        // sample-less surface interval at zero depth on air
+        lifecycle
+            .record_surface_interval(Time::from_minutes(10.))
+            .unwrap();
+        let state = lifecycle.model().dive_state();
+        assert_eq!(state.depth, Depth::zero());
+        assert_eq!(state.gas, Gas::air());
+    }
+
+    #[test]
+    fn test_dive_start_and_end() {
+        let mut lifecycle = lifecycle();
+        assert_eq!(
+            sample(&mut lifecycle, 2., 10.),
+            Some(LifecycleEvent::DiveStarted { number: 1 })
+        );
+        assert_eq!(lifecycle.phase(), DivePhase::Dive);
+        sample(&mut lifecycle, 20., 590.);
+        sample(&mut lifecycle, 10., 600.);
+
+        // surfaced, dive ends after end delay
+        assert_eq!(sample(&mut lifecycle, 0.5, 0.), None);
+        assert_eq!(sample(&mut lifecycle, 0., 30.), None);
+        let Some(LifecycleEvent::DiveEnded(counters)) = sample(&mut lifecycle, 0., 30.) else {
+            panic!("dive end expected");
+        };
+        assert_eq!(lifecycle.phase(), DivePhase::PostDive);
+        assert_eq!(counters.number, 1);
+        assert_eq!(counters.start_time, Time::zero());
+        assert_eq!(counters.max_depth, Depth::from_meters(20.));
+        assert_eq!(counters.dive_time, Time::from_seconds(1200.));
+        assert_close_to_abs!(
+            counters.avg_depth.as_meters(),
+            (2. * 10. + 20. * 590. + 10. * 600.) / 1200.,
+            0.001
+        );
+        assert_eq!(lifecycle.last_dive(), Some(counters));
+
+        // post-dive phase
+        assert_eq!(
+            lifecycle.record_surface_interval(Time::from_minutes(10.)),
+            Ok(Some(LifecycleEvent::PostDiveEnded))
+        );
+        assert_eq!(lifecycle.phase(), DivePhase::Surface);
+    }
+
+    #[test]
+    fn test_dive_resumed_within_end_delay() {
+        let mut lifecycle = lifecycle();
+        sample(&mut lifecycle, 10., 600.);
+        sample(&mut lifecycle, 0.5, 30.);
+        sample(&mut lifecycle, 0., 20.);
+        assert_eq!(lifecycle.phase(), DivePhase::Dive);
+        assert_eq!(sample(&mut lifecycle, 5., 30.), None);
+        assert_eq!(lifecycle.phase(), DivePhase::Dive);
+        assert_eq!(
+            lifecycle.dive().unwrap().dive_time,
+            Time::from_seconds(680.)
+        );
+    }
+
+    #[test]
+    fn test_repetitive_dives() {
+        let mut lifecycle = lifecycle().with_dives_count(41);
+        sample(&mut lifecycle, 15., 600.);
+        sample(&mut lifecycle, 0., 60.);
+        sample(&mut lifecycle, 0., 60.);
+        assert_eq!(lifecycle.phase(), DivePhase::PostDive);
+
+        // new dive started from post-dive phase
+        assert_eq!(
+            sample(&mut lifecycle, 3., 60.),
+            Some(LifecycleEvent::DiveStarted { number: 43 })
+        );
+        assert_eq!(lifecycle.dives_count(), 43);
+        assert_eq!(lifecycle.last_dive().unwrap().number, 42);
+        assert_eq!(
+            lifecycle.dive().unwrap().start_time,
+            Time::from_seconds(720.)
+        );
+    }
+}
@@ -0,0 +1,281 @@
+// Q32.32 fixed-point number, numeric core of the "fixed-point" feature (FPU-less targets).
+// Non-finite floats map to a NaN sentinel propagated through arithmetic, so validation by
+// `is_finite()` on converted values keeps working.
+use core::{
+    cmp::Ordering,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, Neg, Sub},
+};
+
+use super::Float;
+
+const FRAC_BITS: u32 = 32;
+const ONE: i64 = 1 << FRAC_BITS;
+const FRAC_MASK: i64 = ONE - 1;
+const NAN: i64 = i64::MIN;
+const INFINITY: i64 = i64::MAX;
+const NEG_INFINITY: i64 = i64::MIN + 1;
+
+// 2^(2^-k) for k in 1..=32 (Q32.32)
+const EXP2_FRAC_FACTORS: [i64; 32] = [
+    6074001000, 5107605667, 4683695048, 4485121744, 4389014833, 4341736423, 4318288544, 4306612134,
+    4300785774, 4297875550, 4296421177, 4295694175, 4295330720, 4295149004, 4295058149, 4295012722,
+    4294990009, 4294978653, 4294972974, 4294970135, 4294968716, 4294968006, 4294967651, 4294967473,
+    4294967385, 4294967340, 4294967318, 4294967307, 4294967302, 4294967299, 4294967297, 4294967297,
+];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub(crate) const fn from_raw(raw: i64) -> Self {
+        Self(raw)
+    }
+
+    pub(crate) const fn from_int(val: i32) -> Self {
+        Self((val as i64) << FRAC_BITS)
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    pub(crate) const fn from_float(val: Float) -> Self {
+        if val.is_nan() {
+            return Self(NAN);
+        }
+        // saturating cast (infinities at the bounds), lowest value reserved for NaN
+        match (val as f64 * ONE as f64) as i64 {
+            NAN => Self(NEG_INFINITY),
+            raw => Self(raw),
+        }
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    pub(crate) fn to_float(self) -> Float {
+        match self.0 {
+            NAN => Float::NAN,
+            INFINITY => Float::INFINITY,
+            NEG_INFINITY => Float::NEG_INFINITY,
+            raw => (raw as f64 / ONE as f64) as Float,
+        }
+    }
+
+    pub(crate) const fn is_nan(self) -> bool {
+        self.0 == NAN
+    }
+
+    // integer part (rounded towards negative infinity) and fraction in Q0.32
+    pub(crate) const fn split(self) -> (i64, u64) {
+        (self.0 >> FRAC_BITS, (self.0 & FRAC_MASK) as u64)
+    }
+
+    // 2^x, bit by bit from precomputed 2^(2^-k) factors
+    pub(crate) fn exp2(self) -> Self {
+        if self.is_nan() {
+            return self;
+        }
+        let (int, frac) = self.split();
+        let mut res = ONE;
+        for (i, factor) in EXP2_FRAC_FACTORS.iter().enumerate() {
+            if frac & (1 << (FRAC_BITS - 1 - i as u32)) != 0 {
+                res = ((res as i128 * *factor as i128) >> FRAC_BITS) as i64;
+            }
+        }
+        match int {
+            i if i >= 31 => Self(INFINITY),
+            i if i >= 0 => Self(res << i),
+            i if i > -64 => Self(res >> -i),
+            _ => Self(0),
+        }
+    }
+
+    // log2(x) for x > 0, fractional bits by repeated squaring
+    pub(crate) fn log2(self) -> Self {
+        if self.is_nan() || self.0 <= 0 {
+            return Self(NAN);
+        }
+        let msb = 63 - self.0.leading_zeros() as i64;
+        let int = msb - FRAC_BITS as i64;
+        // normalized to [1, 2)
+        let mut y: u128 = if int >= 0 {
+            (self.0 >> int) as u128
+        } else {
+            (self.0 << -int) as u128
+        };
+        let mut frac: i64 = 0;
+        for bit in (0..FRAC_BITS).rev() {
+            y = (y * y) >> FRAC_BITS;
+            if y >= 2 << FRAC_BITS {
+                y >>= 1;
+                frac |= 1 << bit;
+            }
+        }
+        Self((int << FRAC_BITS) + frac)
+    }
+
+    // x^exp for x > 0
+    pub(crate) fn powf(self, exp: Self) -> Self {
+        (exp * self.log2()).exp2()
+    }
+
+    fn saturate(val: i128) -> Self {
+        Self(val.clamp(NEG_INFINITY as i128, INFINITY as i128) as i64)
+    }
+}
+
+impl PartialEq for Fixed {
+    fn eq(&self, other: &Self) -> bool {
+        !self.is_nan() && self.0 == other.0
+    }
+}
+
+impl PartialOrd for Fixed {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.is_nan() || other.is_nan() {
+            return None;
+        }
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.is_nan() || rhs.is_nan() {
+            return Self(NAN);
+        }
+        Self::saturate(self.0 as i128 + rhs.0 as i128)
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        if self.is_nan() || rhs.is_nan() {
+            return Self(NAN);
+        }
+        Self::saturate(self.0 as i128 - rhs.0 as i128)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        if self.is_nan() || rhs.is_nan() {
+            return Self(NAN);
+        }
+        Self::saturate((self.0 as i128 * rhs.0 as i128) >> FRAC_BITS)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self::Output {
+        if self.is_nan() || rhs.is_nan() {
+            return Self(NAN);
+        }
+        if rhs.0 == 0 {
+            return match self.0.cmp(&0) {
+                Ordering::Greater => Self(INFINITY),
+                Ordering::Less => Self(NEG_INFINITY),
+                Ordering::Equal => Self(NAN),
+            };
+        }
+        Self::saturate(((self.0 as i128) << FRAC_BITS) / rhs.0 as i128)
+    }
+}
+
+impl DivAssign for Fixed {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        if self.is_nan() {
+            return self;
+        }
+        Self(-self.0)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_cast)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Fixed, expected: f64, tolerance: f64) {
+        let actual = actual.0 as f64 / ONE as f64;
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_float_conversion() {
+        assert_eq!(Fixed::from_float(1.5), Fixed::from_raw(ONE + ONE / 2));
+        assert_eq!(Fixed::from_float(-2.).to_float(), -2.);
+        assert_eq!(Fixed::from_int(3), Fixed::from_float(3.));
+        assert!(Fixed::from_float(Float::NAN).to_float().is_nan());
+        assert_eq!(
+            Fixed::from_float(Float::INFINITY).to_float(),
+            Float::INFINITY
+        );
+        assert_eq!(Fixed::from_float(-1e30).to_float(), Float::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_nan_propagation() {
+        let nan = Fixed::from_float(Float::NAN);
+        let one = Fixed::from_int(1);
+        assert!((nan + one).is_nan());
+        assert!((one * nan).is_nan());
+        assert!((Fixed::from_int(0) / Fixed::from_int(0)).is_nan());
+        assert_eq!((one / Fixed::from_int(0)).to_float(), Float::INFINITY);
+        assert_ne!(nan, nan);
+        assert_eq!(nan.partial_cmp(&one), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Fixed::from_float(2.5);
+        let b = Fixed::from_float(-0.5);
+        assert_eq!((a + b).to_float(), 2.);
+        assert_eq!((a - b).to_float(), 3.);
+        assert_eq!((a * b).to_float(), -1.25);
+        assert_eq!((a / b).to_float(), -5.);
+        assert_eq!((-a).to_float(), -2.5);
+        assert_eq!(Fixed::from_float(-1.25).split(), (-2, 3 << 30));
+        assert!(b < a);
+    }
+
+    #[test]
+    fn test_exp2() {
+        for x in [-30., -5.5, -1., -0.013, 0., 0.3, 1., 7.77, 20.] {
+            let expected = libm::pow(2., (x as Float) as f64);
+            assert_close(
+                Fixed::from_float(x as Float).exp2(),
+                expected,
+                expected * 1e-8 + 1e-9,
+            );
+        }
+    }
+
+    #[test]
+    fn test_log2_powf() {
+        for x in [0.001, 0.25, 0.9, 1., 1.5, 3.3, 1000.] {
+            let x = Fixed::from_float(x as Float);
+            assert_close(x.log2(), libm::log2(x.0 as f64 / ONE as f64), 1e-8);
+        }
+        assert!(Fixed::from_int(0).log2().is_nan());
+        let powf = Fixed::from_float(1.8).powf(Fixed::from_float(0.8333));
+        let expected = libm::pow((1.8 as Float) as f64, (0.8333 as Float) as f64);
+        assert_close(powf, expected, 1e-8);
+    }
+}
@@ -1,9 +1,31 @@
-use crate::common::global_types::{MbarPressure, Pressure};
+use core::ops::RangeInclusive;
 
-use super::Depth;
+use crate::common::global_types::{Float, GasDensity, MbarPressure, Pressure};
+use crate::common::math;
+
+use super::real::{self, Real};
+use super::{ConfigValidationErr, ConfigValidationErrorField, ConfigValidationErrorReason, Depth};
 
 // alveolar water vapor pressure assuming 47 mm Hg at 37C (Buehlmann's value)
-const ALVEOLI_WATER_VAPOR_PRESSURE: f32 = 0.0627;
+const ALVEOLI_WATER_VAPOR_PRESSURE: Float = 0.0627;
+
+// gas densities at 1 ATA and 0C (g/L)
+const O2_DENSITY: GasDensity = 1.429;
+const N2_DENSITY: GasDensity = 1.251;
+const HE_DENSITY: GasDensity = 0.1786;
+
+// nitrogen fraction in air, EAD reference
+const AIR_N2_FRACTION: Float = 0.79;
+
+// absolute pressure (bar) at depth given surface pressure
+fn ambient_pressure(depth: Depth, surface_pressure: MbarPressure) -> Pressure {
+    (surface_pressure as Pressure / 1000.) + (depth.as_meters() / 10.)
+}
+
+// depth at absolute pressure (bar) given surface pressure
+fn ambient_pressure_depth(pressure: Pressure, surface_pressure: MbarPressure) -> Depth {
+    Depth::from_meters((pressure - (surface_pressure as Pressure / 1000.)) * 10.)
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Gas {
@@ -37,9 +59,36 @@ pub struct PartialPressures {
     pub he: Pressure,
 }
 
-pub enum InertGas {
-    Helium,
-    Nitrogen,
+/// limits used to determine safe depth ranges of a gas for each use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasUseLimits {
+    pub bottom_max_ppo2: Pressure,
+    pub travel_max_ppo2: Pressure,
+    pub deco_max_ppo2: Pressure,
+    pub min_ppo2: Pressure,
+    pub max_end: Depth,
+    pub max_density: GasDensity,
+}
+
+impl Default for GasUseLimits {
+    fn default() -> Self {
+        Self {
+            bottom_max_ppo2: 1.4,
+            travel_max_ppo2: 1.5,
+            deco_max_ppo2: 1.6,
+            min_ppo2: 0.16,
+            max_end: Depth::from_meters(30.),
+            max_density: 5.2,
+        }
+    }
+}
+
+/// depth ranges a gas can be safely used in given gas use limits (None if not suitable)
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasSuitability {
+    pub bottom: Option<RangeInclusive<Depth>>,
+    pub travel: Option<RangeInclusive<Depth>>,
+    pub deco: Option<RangeInclusive<Depth>>,
 }
 
 impl core::fmt::Display for Gas {
@@ -51,25 +100,41 @@ impl core::fmt::Display for Gas {
 impl Gas {
     /// init new gas with partial pressures (eg. 0.21, 0. for air)
     pub fn new(o2_pp: Pressure, he_pp: Pressure) -> Self {
+        Self::try_new(o2_pp, he_pp)
+            .unwrap_or_else(|e| panic!("Invalid gas [{:?}]: {:?}", e.field, e.reason))
+    }
+
+    /// init new gas with partial pressures, Err on invalid fractions
+    pub fn try_new(o2_pp: Pressure, he_pp: Pressure) -> Result<Self, ConfigValidationErr> {
         if !(0. ..=1.).contains(&o2_pp) {
-            panic!("Invalid O2 partial pressure");
+            return Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::O2Fraction,
+                ConfigValidationErrorReason::OutOfRange,
+            ));
         }
         if !(0. ..=1.).contains(&he_pp) {
-            panic!("Invalid He partial pressure [{he_pp}]");
+            return Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::HeFraction,
+                ConfigValidationErrorReason::OutOfRange,
+            ));
         }
+        // can't exceed 1ATA in total
         if (o2_pp + he_pp) > 1. {
-            panic!("Invalid partial pressures, can't exceed 1ATA in total");
+            return Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::GasFractions,
+                ConfigValidationErrorReason::InvalidValue,
+            ));
         }
 
-        Self {
+        Ok(Self {
             o2_pp,
             he_pp,
             n2_pp: ((1. - (o2_pp + he_pp)) * 100.0) / 100.0,
             valid: true,
-        }
+        })
     }
 
-    pub fn id(&self) -> (f32, f32) {
+    pub fn id(&self) -> (Float, Float) {
         (self.o2_pp, self.he_pp)
     }
 
@@ -79,7 +144,7 @@ impl Gas {
         depth: Depth,
         surface_pressure: MbarPressure,
     ) -> PartialPressures {
-        let gas_pressure = (surface_pressure as f32 / 1000.) + (depth.as_meters() / 10.);
+        let gas_pressure = ambient_pressure(depth, surface_pressure);
         self.gas_pressures_compound(gas_pressure)
     }
 
@@ -89,12 +154,31 @@ impl Gas {
         depth: Depth,
         surface_pressure: MbarPressure,
     ) -> PartialPressures {
-        let gas_pressure = ((surface_pressure as f32 / 1000.) + (depth.as_meters() / 10.))
-            - ALVEOLI_WATER_VAPOR_PRESSURE;
-        self.gas_pressures_compound(gas_pressure)
+        let (o2, n2, he) = self.inspired_partial_pressures_real(depth, surface_pressure);
+        PartialPressures {
+            o2: real::to_float(o2),
+            n2: real::to_float(n2),
+            he: real::to_float(he),
+        }
+    }
+
+    // inspired partial pressures (o2, n2, he) in numeric core type
+    pub(crate) fn inspired_partial_pressures_real(
+        &self,
+        depth: Depth,
+        surface_pressure: MbarPressure,
+    ) -> (Real, Real, Real) {
+        let gas_pressure = real::from_int(surface_pressure as i32) / real::from_int(1000)
+            + depth.as_real_meters() / real::from_int(10)
+            - real::from_float(ALVEOLI_WATER_VAPOR_PRESSURE);
+        (
+            real::from_float(self.o2_pp) * gas_pressure,
+            real::from_float(self.n2_pp) * gas_pressure,
+            real::from_float(self.he_pp) * gas_pressure,
+        )
     }
 
-    pub fn gas_pressures_compound(&self, gas_pressure: f32) -> PartialPressures {
+    pub fn gas_pressures_compound(&self, gas_pressure: Float) -> PartialPressures {
         PartialPressures {
             o2: self.o2_pp * gas_pressure,
             n2: self.n2_pp * gas_pressure,
@@ -118,10 +202,177 @@ impl Gas {
         end
     }
 
-    // TODO standard nitrox (bottom and deco) and trimix gasses
+    /// minimum operating depth for hypoxic mixes, with minimum o2 partial pressure as parameter
+    pub fn min_operating_depth(
+        &self,
+        pp_o2_min: Pressure,
+        surface_pressure: MbarPressure,
+    ) -> Depth {
+        let min_od = self.pp_o2_depth(pp_o2_min, surface_pressure);
+        if min_od < Depth::zero() {
+            return Depth::zero();
+        }
+        min_od
+    }
+
+    /// EAD
+    pub fn equivalent_air_depth(&self, depth: Depth) -> Depth {
+        let ead = (depth + Depth::from_meters(10.)) * (self.n2_pp / AIR_N2_FRACTION)
+            - Depth::from_meters(10.);
+        if ead < Depth::zero() {
+            return Depth::zero();
+        }
+        ead
+    }
+
+    /// gas density at depth (g/L)
+    pub fn density(&self, depth: Depth, surface_pressure: MbarPressure) -> GasDensity {
+        self.density_compound(ambient_pressure(depth, surface_pressure))
+    }
+
+    /// gas density at given absolute pressure (g/L)
+    pub fn density_compound(&self, gas_pressure: Pressure) -> GasDensity {
+        let surface_density =
+            (self.o2_pp * O2_DENSITY) + (self.n2_pp * N2_DENSITY) + (self.he_pp * HE_DENSITY);
+        surface_density * gas_pressure
+    }
+
+    /// maximum depth at which gas density doesn't exceed given limit
+    pub fn density_limit_depth(
+        &self,
+        max_density: GasDensity,
+        surface_pressure: MbarPressure,
+    ) -> Depth {
+        let max_gas_pressure = max_density / self.density_compound(1.);
+        ambient_pressure_depth(max_gas_pressure, surface_pressure)
+    }
+
+    /// best mix for given depth (max o2 within ppO2 limit, min helium within END and density limits)
+    pub fn best_mix(
+        depth: Depth,
+        max_ppo2: Pressure,
+        max_end: Depth,
+        max_density: GasDensity,
+        surface_pressure: MbarPressure,
+    ) -> Option<Self> {
+        let gas_pressure = ambient_pressure(depth, surface_pressure);
+
+        // round o2 down and helium up to a full percent
+        let mut o2 = math::floor((max_ppo2 / gas_pressure) * 100. + 0.001) / 100.;
+        if o2 > 1. {
+            o2 = 1.;
+        }
+        let he_by_end = 1. - (ambient_pressure(max_end, surface_pressure) / gas_pressure);
+        let he_by_density = ((o2 * O2_DENSITY) + ((1. - o2) * N2_DENSITY)
+            - (max_density / gas_pressure))
+            / (N2_DENSITY - HE_DENSITY);
+        let mut he = math::ceil(he_by_end.max(he_by_density) * 100. - 0.001) / 100.;
+        if he < 0. {
+            he = 0.;
+        }
+
+        if o2 <= 0. || (o2 + he) > 1. {
+            return None;
+        }
+        let mix = Self::new(o2, he);
+        if mix.density_compound(gas_pressure) > max_density {
+            return None;
+        }
+
+        Some(mix)
+    }
+
+    /// safe depth ranges for bottom, travel and deco use
+    pub fn suitability(
+        &self,
+        limits: GasUseLimits,
+        surface_pressure: MbarPressure,
+    ) -> GasSuitability {
+        let GasUseLimits {
+            bottom_max_ppo2,
+            travel_max_ppo2,
+            deco_max_ppo2,
+            min_ppo2,
+            max_end,
+            max_density,
+        } = limits;
+        let min_depth = self.min_operating_depth(min_ppo2, surface_pressure);
+        let end_max_depth = ambient_pressure_depth(
+            ambient_pressure(max_end, surface_pressure) / (1. - self.he_pp),
+            surface_pressure,
+        );
+        let density_max_depth = self.density_limit_depth(max_density, surface_pressure);
+
+        let depth_range = |max_ppo2: Pressure| -> Option<RangeInclusive<Depth>> {
+            let mut max_depth = self.pp_o2_depth(max_ppo2, surface_pressure);
+            for limit in [end_max_depth, density_max_depth] {
+                if limit < max_depth {
+                    max_depth = limit;
+                }
+            }
+            if min_depth > max_depth {
+                return None;
+            }
+            Some(min_depth..=max_depth)
+        };
+
+        GasSuitability {
+            bottom: depth_range(bottom_max_ppo2),
+            travel: depth_range(travel_max_ppo2),
+            deco: depth_range(deco_max_ppo2),
+        }
+    }
+
+    // depth at which o2 reaches given partial pressure
+    fn pp_o2_depth(&self, pp_o2: Pressure, surface_pressure: MbarPressure) -> Depth {
+        ambient_pressure_depth(pp_o2 / self.o2_pp, surface_pressure)
+    }
+
     pub fn air() -> Self {
         Self::new(0.21, 0.)
     }
+
+    /// nitrox with o2 percentage (eg. 32 for EAN32)
+    pub fn ean(o2_percent: u8) -> Self {
+        Self::trimix(o2_percent, 0)
+    }
+
+    /// nitrox with o2 percentage, Err on invalid percentage
+    pub fn try_ean(o2_percent: u8) -> Result<Self, ConfigValidationErr> {
+        Self::try_trimix(o2_percent, 0)
+    }
+
+    pub fn oxygen() -> Self {
+        Self::new(1., 0.)
+    }
+
+    /// trimix with o2 and he percentages (eg. 18, 45 for TMX 18/45)
+    pub fn trimix(o2_percent: u8, he_percent: u8) -> Self {
+        Self::try_trimix(o2_percent, he_percent)
+            .unwrap_or_else(|e| panic!("Invalid gas [{:?}]: {:?}", e.field, e.reason))
+    }
+
+    /// trimix with o2 and he percentages, Err on invalid percentages
+    pub fn try_trimix(o2_percent: u8, he_percent: u8) -> Result<Self, ConfigValidationErr> {
+        Self::try_new(o2_percent as Float / 100., he_percent as Float / 100.)
+    }
+
+    /// heliox with o2 percentage, helium as the remainder
+    pub fn heliox(o2_percent: u8) -> Self {
+        Self::try_heliox(o2_percent)
+            .unwrap_or_else(|e| panic!("Invalid gas [{:?}]: {:?}", e.field, e.reason))
+    }
+
+    /// heliox with o2 percentage, Err on o2 percentage above 100
+    pub fn try_heliox(o2_percent: u8) -> Result<Self, ConfigValidationErr> {
+        let he_percent = 100_u8
+            .checked_sub(o2_percent)
+            .ok_or(ConfigValidationErr::new(
+                ConfigValidationErrorField::O2Fraction,
+                ConfigValidationErrorReason::OutOfRange,
+            ))?;
+        Self::try_trimix(o2_percent, he_percent)
+    }
 }
 
 #[cfg(test)]
@@ -141,7 +392,7 @@ mod tests {
         let tmx = Gas::new(0.18, 0.35);
         assert_eq!(tmx.o2_pp, 0.18);
         assert_eq!(tmx.he_pp, 0.35);
-        assert_eq!(tmx.n2_pp, 0.47);
+        assert_close_to_abs!(tmx.n2_pp, 0.47, 1e-6);
     }
 
     #[test]
@@ -162,6 +413,20 @@ mod tests {
         Gas::new(0.5, 0.51);
     }
 
+    #[test]
+    fn test_try_new_invalid_fractions() {
+        let test_cases = [
+            (1.1, 0., ConfigValidationErrorField::O2Fraction),
+            (0.21, -0.1, ConfigValidationErrorField::HeFraction),
+            (0.5, 0.51, ConfigValidationErrorField::GasFractions),
+        ];
+        for (o2, he, expected_field) in test_cases {
+            let err = Gas::try_new(o2, he).unwrap_err();
+            assert_eq!(err.field, expected_field);
+        }
+        assert_eq!(Gas::try_new(0.21, 0.), Ok(Gas::air()));
+    }
+
     #[test]
     fn test_partial_pressures_air() {
         let air = Gas::new(0.21, 0.);
@@ -179,46 +444,39 @@ mod tests {
     #[test]
     fn partial_pressures_tmx() {
         let tmx = Gas::new(0.21, 0.35);
-        let partial_pressures = tmx.partial_pressures(Depth::from_meters(10.), 1000);
-        assert_eq!(
-            partial_pressures,
-            PartialPressures {
-                o2: 0.42,
-                he: 0.70,
-                n2: 0.88
-            }
-        )
+        let PartialPressures { o2, n2, he } = tmx.partial_pressures(Depth::from_meters(10.), 1000);
+        assert_close_to_abs!(o2, 0.42, 1e-6);
+        assert_close_to_abs!(he, 0.70, 1e-6);
+        assert_close_to_abs!(n2, 0.88, 1e-6);
     }
 
     #[test]
     fn test_inspired_partial_pressures() {
         let air = Gas::new(0.21, 0.);
-        let inspired_partial_pressures =
+        let PartialPressures { o2, n2, he } =
             air.inspired_partial_pressures(Depth::from_meters(10.), 1000);
-        assert_eq!(
-            inspired_partial_pressures,
-            PartialPressures {
-                o2: 0.406833,
-                n2: 1.530467,
-                he: 0.0
-            }
-        );
+        assert_close_to_abs!(o2, 0.406833, 1e-6);
+        assert_close_to_abs!(n2, 1.530467, 1e-6);
+        assert_eq!(he, 0.);
     }
 
     #[test]
     fn test_mod() {
         // o2, he, max_ppo2, MOD
         let test_cases = [
-            (0.21, 0., 1.4, 56.66666666666666),
+            (0.21, 0., 1.4, 56.666667),
             (0.50, 0., 1.6, 22.),
-            (0.21, 0.35, 1.4, 56.66666666666666),
-            (0., 0., 1.4, f32::INFINITY),
+            (0.21, 0.35, 1.4, 56.666667),
         ];
         for (pp_o2, pe_he, max_pp_o2, expected_mod) in test_cases {
             let gas = Gas::new(pp_o2, pe_he);
             let calculated_mod = gas.max_operating_depth(max_pp_o2);
-            assert_eq!(calculated_mod, Depth::from_meters(expected_mod));
+            assert_close_to_abs!(calculated_mod.as_meters(), expected_mod, 1e-5);
         }
+        assert_eq!(
+            Gas::new(0., 0.).max_operating_depth(1.4),
+            Depth::from_meters(Float::INFINITY)
+        );
     }
 
     #[test]
@@ -232,7 +490,7 @@ mod tests {
         for (depth, o2_pp, he_pp, expected_end) in test_cases {
             let tmx = Gas::new(o2_pp, he_pp);
             let calculated_end = tmx.equivalent_narcotic_depth(Depth::from_meters(depth));
-            assert_eq!(calculated_end, Depth::from_meters(expected_end));
+            assert_close_to_abs!(calculated_end.as_meters(), expected_end, 1e-6);
         }
     }
 
@@ -243,4 +501,190 @@ mod tests {
         let tmx2135 = Gas::new(0.21, 0.35);
         assert_eq!(tmx2135.id(), (0.21, 0.35));
     }
+
+    #[test]
+    fn test_min_operating_depth() {
+        // o2, he, min_ppo2, MinOD
+        let test_cases = [
+            (0.21, 0., 0.16, 0.),
+            (0.10, 0.70, 0.16, 6.),
+            (0.08, 0.80, 0.18, 12.5),
+        ];
+        for (pp_o2, pp_he, min_pp_o2, expected_min_od) in test_cases {
+            let gas = Gas::new(pp_o2, pp_he);
+            let calculated_min_od = gas.min_operating_depth(min_pp_o2, 1000);
+            assert_close_to_abs!(calculated_min_od.as_meters(), expected_min_od, 1e-5);
+        }
+
+        // deeper MinOD at altitude, same as suitability min depth
+        let tmx1070 = Gas::new(0.10, 0.70);
+        let min_od_altitude = tmx1070.min_operating_depth(0.16, 700);
+        assert_close_to_abs!(min_od_altitude.as_meters(), 9., 1e-4);
+        let GasSuitability { bottom, .. } = tmx1070.suitability(GasUseLimits::default(), 700);
+        assert_eq!(bottom.map(|range| *range.start()), Some(min_od_altitude));
+    }
+
+    #[test]
+    fn test_ead() {
+        // depth, o2, he, EAD
+        let test_cases = [
+            (30., 0.32, 0., 24.43038),
+            (30., 0.21, 0., 30.),
+            (0., 0.36, 0., 0.),
+        ];
+        for (depth, o2_pp, he_pp, expected_ead) in test_cases {
+            let gas = Gas::new(o2_pp, he_pp);
+            let calculated_ead = gas.equivalent_air_depth(Depth::from_meters(depth));
+            assert_close_to_abs!(calculated_ead.as_meters(), expected_ead, 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_density() {
+        // depth, o2, he, density (g/L)
+        let test_cases = [
+            (0., 0.21, 0., 1.28838),
+            (30., 0.21, 0., 5.15352),
+            (60., 0.18, 0.45, 5.60322),
+        ];
+        for (depth, o2_pp, he_pp, expected_density) in test_cases {
+            let gas = Gas::new(o2_pp, he_pp);
+            let calculated_density = gas.density(Depth::from_meters(depth), 1000);
+            assert_close_to_abs!(calculated_density, expected_density, 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_density_limit_depth() {
+        let air = Gas::air();
+        let density_limit_depth = air.density_limit_depth(5.2, 1000);
+        assert!(density_limit_depth > Depth::from_meters(30.));
+        assert!(density_limit_depth < Depth::from_meters(31.));
+        assert_close_density(air.density(density_limit_depth, 1000), 5.2);
+
+        let tmx1845 = Gas::new(0.18, 0.45);
+        let density_limit_depth = tmx1845.density_limit_depth(5.2, 1000);
+        assert_close_density(tmx1845.density(density_limit_depth, 1000), 5.2);
+    }
+
+    fn assert_close_density(density: GasDensity, expected: GasDensity) {
+        assert!(
+            (density - expected).abs() < 0.0001,
+            "{density} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_presets_percentages() {
+        assert_eq!(Gas::try_ean(32), Ok(Gas::ean(32)));
+        assert_eq!(Gas::try_trimix(18, 45), Ok(Gas::trimix(18, 45)));
+        assert_eq!(Gas::try_heliox(10), Ok(Gas::heliox(10)));
+
+        let o2_out_of_range = Err(ConfigValidationErr::new(
+            ConfigValidationErrorField::O2Fraction,
+            ConfigValidationErrorReason::OutOfRange,
+        ));
+        assert_eq!(Gas::try_ean(101), o2_out_of_range);
+        assert_eq!(Gas::try_heliox(150), o2_out_of_range);
+        assert_eq!(
+            Gas::try_trimix(50, 60),
+            Err(ConfigValidationErr::new(
+                ConfigValidationErrorField::GasFractions,
+                ConfigValidationErrorReason::InvalidValue,
+            ))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_heliox_invalid_percentage() {
+        Gas::heliox(101);
+    }
+
+    #[test]
+    fn test_best_mix() {
+        // depth, max ppO2, max END, max density, best mix
+        let test_cases = [
+            (20., 1.4, 30., 5.2, Some((0.46, 0.))),
+            (30., 1.4, 30., 5.2, Some((0.35, 0.02))),
+            (60., 1.4, 30., 5.2, Some((0.2, 0.51))),
+            (6., 1.6, 30., 5.2, Some((1., 0.))),
+            (200., 1.3, 30., 5.2, None),
+        ];
+        for (depth, max_ppo2, max_end, max_density, expected_mix) in test_cases {
+            let best_mix = Gas::best_mix(
+                Depth::from_meters(depth),
+                max_ppo2,
+                Depth::from_meters(max_end),
+                max_density,
+                1000,
+            );
+            assert_eq!(best_mix.map(|mix| mix.id()), expected_mix);
+        }
+
+        // less o2 at sea level than at altitude for the same depth
+        let best_mix_sea_level = Gas::best_mix(
+            Depth::from_meters(30.),
+            1.4,
+            Depth::from_meters(30.),
+            5.2,
+            1013,
+        );
+        assert_eq!(best_mix_sea_level.map(|mix| mix.id()), Some((0.34, 0.02)));
+        let best_mix_altitude = Gas::best_mix(
+            Depth::from_meters(30.),
+            1.4,
+            Depth::from_meters(30.),
+            5.2,
+            700,
+        );
+        assert_eq!(best_mix_altitude.map(|mix| mix.id()), Some((0.37, 0.)));
+
+        // more helium for the same END at altitude
+        let best_mix_end_altitude = Gas::best_mix(
+            Depth::from_meters(60.),
+            1.4,
+            Depth::from_meters(30.),
+            10.,
+            700,
+        );
+        assert_eq!(best_mix_end_altitude.map(|mix| mix.id()), Some((0.2, 0.45)));
+    }
+
+    #[test]
+    fn test_suitability() {
+        let ean50 = Gas::new(0.5, 0.);
+        let suitability = ean50.suitability(GasUseLimits::default(), 1000);
+        assert_eq!(
+            suitability,
+            GasSuitability {
+                bottom: Some(Depth::zero()..=Depth::from_meters(18.)),
+                travel: Some(Depth::zero()..=Depth::from_meters(20.)),
+                deco: Some(Depth::zero()..=Depth::from_meters(22.)),
+            }
+        );
+
+        // hypoxic trimix, not breathable at the surface
+        let tmx1070 = Gas::new(0.10, 0.70);
+        let GasSuitability { bottom, .. } = tmx1070.suitability(GasUseLimits::default(), 1000);
+        let bottom_min_depth = bottom.map(|range| range.start().as_meters()).unwrap();
+        assert_close_to_abs!(bottom_min_depth, 6., 1e-5);
+
+        // no depth satisfies both min ppO2 and density limit
+        let limits = GasUseLimits {
+            max_density: 0.7,
+            ..GasUseLimits::default()
+        };
+        let GasSuitability {
+            bottom,
+            travel,
+            deco,
+        } = tmx1070.suitability(limits, 1000);
+        assert_eq!((bottom, travel, deco), (None, None, None));
+
+        // deeper MOD at altitude
+        let GasSuitability { deco, .. } = ean50.suitability(GasUseLimits::default(), 700);
+        let deco_max_depth = deco.map(|range| range.end().as_meters()).unwrap();
+        assert_close_to_abs!(deco_max_depth, 25., 1e-4);
+    }
 }
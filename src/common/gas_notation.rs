@@ -0,0 +1,232 @@
+use core::{fmt, num::IntErrorKind, str::FromStr};
+
+use super::{math, Gas};
+
+/// gas naming convention used for display
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GasNotation {
+    // o2/he percentages, eg. "21/0", "18/45"
+    Fractions,
+    // EAN style, eg. "Air", "EAN32", "O2", "TMX 18/45", "Heliox 10/90"
+    Ean,
+    // Nx style, eg. "Air", "Nx32", "O2", "Tx18/45", "Hx10/90"
+    Nx,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum GasParseError {
+    InvalidNotation,
+    InvalidFractions,
+}
+
+impl fmt::Display for GasParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            GasParseError::InvalidNotation => write!(f, "Unrecognized gas notation"),
+            GasParseError::InvalidFractions => write!(
+                f,
+                "Gas percentages must be in 0-100 range (O2 at least 1) and can't exceed 100 in total"
+            ),
+        }
+    }
+}
+
+/// gas formatted using given notation
+#[derive(Clone, Copy, Debug)]
+pub struct GasDisplay {
+    gas: Gas,
+    notation: GasNotation,
+}
+
+impl fmt::Display for GasDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (o2, he) = percentages(&self.gas);
+        if self.notation == GasNotation::Fractions {
+            return write!(f, "{}/{}", o2, he);
+        }
+
+        // air and oxygen share a name across notations
+        if (o2, he) == (21, 0) {
+            return write!(f, "Air");
+        }
+        if o2 == 100 {
+            return write!(f, "O2");
+        }
+
+        let is_heliox = (o2 + he) == 100;
+        match (self.notation, he, is_heliox) {
+            (GasNotation::Ean, 0, _) => write!(f, "EAN{}", o2),
+            (GasNotation::Ean, _, true) => write!(f, "Heliox {}/{}", o2, he),
+            (GasNotation::Ean, ..) => write!(f, "TMX {}/{}", o2, he),
+            (_, 0, _) => write!(f, "Nx{}", o2),
+            (_, _, true) => write!(f, "Hx{}/{}", o2, he),
+            _ => write!(f, "Tx{}/{}", o2, he),
+        }
+    }
+}
+
+impl Gas {
+    /// gas formatter using given naming convention
+    pub fn display(&self, notation: GasNotation) -> GasDisplay {
+        GasDisplay {
+            gas: *self,
+            notation,
+        }
+    }
+}
+
+impl FromStr for Gas {
+    type Err = GasParseError;
+
+    /// parse standard gas notation (eg. "air", "EAN32", "Nx50", "O2", "TMX 18/45", "Tx21/35", "Heliox 10/90", "18/45")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let notation = s.trim();
+        if notation.eq_ignore_ascii_case("air") {
+            return Ok(Self::air());
+        }
+        if notation.eq_ignore_ascii_case("o2") || notation.eq_ignore_ascii_case("oxygen") {
+            return Ok(Self::oxygen());
+        }
+
+        // split gas name prefix from percentages
+        let prefix_len = notation
+            .find(|c: char| c.is_ascii_digit())
+            .ok_or(GasParseError::InvalidNotation)?;
+        let (prefix, percentages) = notation.split_at(prefix_len);
+        let prefix = prefix.trim();
+        let is_prefix = |names: &[&str]| names.iter().any(|name| prefix.eq_ignore_ascii_case(name));
+
+        let (o2, he) = if is_prefix(&["ean", "eanx", "nx", "nitrox"]) {
+            (parse_percentage(percentages)?, 0)
+        } else if is_prefix(&["tmx", "tx", "trimix", ""]) {
+            parse_percentages_pair(percentages)?
+        } else if is_prefix(&["heliox", "hx"]) {
+            let (o2, he) = parse_percentages_pair(percentages)?;
+            if (o2 as u16 + he as u16) != 100 {
+                return Err(GasParseError::InvalidFractions);
+            }
+            (o2, he)
+        } else {
+            return Err(GasParseError::InvalidNotation);
+        };
+
+        if o2 == 0 || (o2 as u16 + he as u16) > 100 {
+            return Err(GasParseError::InvalidFractions);
+        }
+
+        Ok(Self::trimix(o2, he))
+    }
+}
+
+fn percentages(gas: &Gas) -> (u8, u8) {
+    let (o2, he) = gas.id();
+    (math::round(o2 * 100.) as u8, math::round(he * 100.) as u8)
+}
+
+// any percentage above 100 (incl. integer overflow) rejected as invalid fractions
+fn parse_percentage(val: &str) -> Result<u8, GasParseError> {
+    let percentage = val.trim().parse::<u32>().map_err(|e| match e.kind() {
+        IntErrorKind::PosOverflow => GasParseError::InvalidFractions,
+        _ => GasParseError::InvalidNotation,
+    })?;
+    match percentage {
+        0..=100 => Ok(percentage as u8),
+        _ => Err(GasParseError::InvalidFractions),
+    }
+}
+
+fn parse_percentages_pair(val: &str) -> Result<(u8, u8), GasParseError> {
+    let (o2, he) = val.split_once('/').ok_or(GasParseError::InvalidNotation)?;
+    Ok((parse_percentage(o2)?, parse_percentage(he)?))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn test_parse_standard_notations() {
+        let test_cases = [
+            ("air", Gas::air()),
+            ("Air", Gas::air()),
+            ("EAN32", Gas::new(0.32, 0.)),
+            ("EANx36", Gas::new(0.36, 0.)),
+            ("Nx50", Gas::new(0.5, 0.)),
+            ("nitrox 28", Gas::new(0.28, 0.)),
+            ("O2", Gas::new(1., 0.)),
+            ("oxygen", Gas::new(1., 0.)),
+            ("TMX 18/45", Gas::new(0.18, 0.45)),
+            ("Tx21/35", Gas::new(0.21, 0.35)),
+            ("trimix 10/70", Gas::new(0.1, 0.7)),
+            ("Heliox 10/90", Gas::new(0.1, 0.9)),
+            ("Hx 21/79", Gas::new(0.21, 0.79)),
+            ("18/45", Gas::new(0.18, 0.45)),
+            (" ean32 ", Gas::new(0.32, 0.)),
+        ];
+        for (notation, expected_gas) in test_cases {
+            let gas: Gas = notation.parse().unwrap();
+            assert_eq!(gas, expected_gas, "{}", notation);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_notations() {
+        let test_cases = [
+            ("", GasParseError::InvalidNotation),
+            ("nitrox", GasParseError::InvalidNotation),
+            ("EAN32.5", GasParseError::InvalidNotation),
+            ("Tx18", GasParseError::InvalidNotation),
+            ("argon 30", GasParseError::InvalidNotation),
+            ("EAN0", GasParseError::InvalidFractions),
+            ("EAN101", GasParseError::InvalidFractions),
+            ("EAN300", GasParseError::InvalidFractions),
+            ("EAN99999999999", GasParseError::InvalidFractions),
+            ("Tx10/300", GasParseError::InvalidFractions),
+            ("Tx60/50", GasParseError::InvalidFractions),
+            ("Heliox 10/80", GasParseError::InvalidFractions),
+        ];
+        for (notation, expected_err) in test_cases {
+            assert_eq!(notation.parse::<Gas>(), Err(expected_err), "{}", notation);
+        }
+    }
+
+    #[test]
+    fn test_display_notations() {
+        // gas, fractions, EAN style, Nx style
+        let test_cases = [
+            (Gas::air(), "21/0", "Air", "Air"),
+            (Gas::ean(32), "32/0", "EAN32", "Nx32"),
+            (Gas::oxygen(), "100/0", "O2", "O2"),
+            (Gas::trimix(18, 45), "18/45", "TMX 18/45", "Tx18/45"),
+            (Gas::heliox(10), "10/90", "Heliox 10/90", "Hx10/90"),
+        ];
+        for (gas, fractions, ean, nx) in test_cases {
+            assert_eq!(
+                format!("{}", gas.display(GasNotation::Fractions)),
+                fractions
+            );
+            assert_eq!(format!("{}", gas.display(GasNotation::Ean)), ean);
+            assert_eq!(format!("{}", gas.display(GasNotation::Nx)), nx);
+        }
+    }
+
+    #[test]
+    fn test_display_parse_round_trip() {
+        let gasses = [
+            Gas::air(),
+            Gas::ean(50),
+            Gas::oxygen(),
+            Gas::trimix(15, 55),
+            Gas::heliox(21),
+        ];
+        for gas in gasses {
+            for notation in [GasNotation::Fractions, GasNotation::Ean, GasNotation::Nx] {
+                let parsed: Gas = format!("{}", gas.display(notation)).parse().unwrap();
+                assert_eq!(parsed, gas);
+            }
+        }
+    }
+}
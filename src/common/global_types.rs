@@ -1,11 +1,18 @@
-pub type Pressure = f32;
-pub type DepthType = f32;
+// floating point precision (f64 with "f64" feature)
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+pub type Pressure = Float;
+pub type DepthType = Float;
 pub type GradientFactor = u8;
 pub type GradientFactors = (u8, u8);
 pub type MbarPressure = u16;
-pub type AscentRatePerMinute = f32;
-pub type Cns = f32;
-pub type Otu = f32;
+pub type AscentRatePerMinute = Float;
+pub type Cns = Float;
+pub type Otu = Float;
+pub type GasDensity = Float;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum NDLType {
@@ -0,0 +1,39 @@
+// libm functions for configured float precision
+use super::Float;
+
+// fixed-point numeric core doesn't use pow
+#[cfg(not(any(feature = "f64", feature = "fixed-point")))]
+pub(crate) fn pow(x: Float, y: Float) -> Float {
+    libm::powf(x, y)
+}
+#[cfg(all(feature = "f64", not(feature = "fixed-point")))]
+pub(crate) fn pow(x: Float, y: Float) -> Float {
+    libm::pow(x, y)
+}
+
+#[cfg(not(feature = "f64"))]
+pub(crate) fn floor(x: Float) -> Float {
+    libm::floorf(x)
+}
+#[cfg(feature = "f64")]
+pub(crate) fn floor(x: Float) -> Float {
+    libm::floor(x)
+}
+
+#[cfg(not(feature = "f64"))]
+pub(crate) fn ceil(x: Float) -> Float {
+    libm::ceilf(x)
+}
+#[cfg(feature = "f64")]
+pub(crate) fn ceil(x: Float) -> Float {
+    libm::ceil(x)
+}
+
+#[cfg(not(feature = "f64"))]
+pub(crate) fn round(x: Float) -> Float {
+    libm::roundf(x)
+}
+#[cfg(feature = "f64")]
+pub(crate) fn round(x: Float) -> Float {
+    libm::round(x)
+}
@@ -1,28 +1,72 @@
+mod alarms;
 mod cns_table;
 mod deco;
 mod deco_model;
+mod deco_tracker;
 mod depth;
+mod dive_lifecycle;
+#[cfg(feature = "fixed-point")]
+mod fixed_point;
 mod gas;
+mod gas_notation;
 mod global_types;
+pub(crate) mod math;
 mod ox_tox;
+mod profile;
+pub(crate) mod real;
 mod record;
+mod replay;
+mod safety_stop;
 mod sim;
+mod subsurface;
+mod surface_deco;
+mod tables;
 mod time;
+mod uddf;
+mod xml;
 
-pub const MAX_GASSES : usize = 16;
+pub const MAX_GASSES: usize = 16;
 pub const MAX_DECO_STAGE: usize = 16;
 
+pub use alarms::{Alarm, AlarmConfig, AlarmEvent, AlarmEvents, AlarmKind, AlarmMonitor};
 pub use cns_table::{CNSCoeffRow, CNS_COEFFICIENTS};
-pub use deco::{Deco, DecoCalculationError, DecoRuntime, DecoStage, DecoStageType};
-pub use deco_model::{ConfigValidationErr, DecoModel, DecoModelConfig, DiveState, ConfigValidationErrorField, ConfigValidationErrorReason};
+pub use deco::{
+    Deco, DecoCalculationError, DecoRuntime, DecoStage, DecoStageBuffer, DecoStageType,
+};
+pub(crate) use deco_model::DEFAULT_NDL_CUT_OFF_MINS;
+pub use deco_model::{
+    ConfigValidationErr, ConfigValidationErrorField, ConfigValidationErrorReason, DecoModel,
+    DecoModelConfig, DiveState,
+};
+pub use deco_tracker::{DecoTracker, DecoTrackerConfig};
 pub use depth::{Depth, Unit, Units};
+pub use dive_lifecycle::{
+    DiveCounters, DiveLifecycle, DiveLifecycleConfig, DivePhase, LifecycleEvent,
+};
 pub use time::Time;
+pub use uddf::{
+    write_uddf_plan, write_uddf_profile, UddfDive, UddfDives, UddfDocument, UddfError, UddfMix,
+    UddfMixes, UddfSamples,
+};
 
-pub use gas::{Gas, InertGas, PartialPressures};
+pub use gas::{Gas, GasSuitability, GasUseLimits};
+pub use gas_notation::{GasDisplay, GasNotation, GasParseError};
 pub use global_types::{
-    AscentRatePerMinute, CeilingType, Cns, DepthType, GradientFactor, GradientFactors,
-    MbarPressure, NDLType, Otu, Pressure,
+    AscentRatePerMinute, CeilingType, Cns, DepthType, Float, GasDensity, GradientFactor,
+    GradientFactors, MbarPressure, NDLType, Otu, Pressure,
 };
 pub use ox_tox::OxTox;
+pub use profile::{ProfileSample, ReplayPoint, SampleDepth};
 pub use record::RecordData;
+pub use replay::{ProfileReplay, ReplayError};
+pub use safety_stop::{SafetyStop, SafetyStopConfig, SafetyStopExit, SafetyStopState};
 pub use sim::Sim;
+pub use subsurface::{
+    RepetitiveSamples, SubsurfaceDive, SubsurfaceDives, SubsurfaceError, SubsurfaceLog,
+    SubsurfaceSamples, SubsurfaceTrip, SubsurfaceTrips,
+};
+pub use surface_deco::{
+    ChamberSchedule, ChamberStage, ChamberStageType, SurfaceDeco, SurfaceDecoConfig,
+    SurfaceDecoRuntime,
+};
+pub use tables::{DecoTableRow, DepthRange, DiveTables, NdlTableRow, TableError, TableFormat};
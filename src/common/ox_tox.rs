@@ -1,34 +1,54 @@
-use core::cmp::Ordering;
-
 use crate::common::CNS_COEFFICIENTS;
-use crate::{Pressure, RecordData};
+use crate::RecordData;
 
 use super::global_types::Otu;
+use super::real::{self, Real};
 use super::{CNSCoeffRow, Cns, Depth, MbarPressure};
 
-const CNS_ELIMINATION_HALF_TIME_MINUTES: f32 = 90.;
-const CNS_LIMIT_OVER_MAX_PP02_SECONDS: f32 = 400.;
-const OTU_EQUATION_EXPONENT: f32 = -0.8333;
+const CNS_ELIMINATION_HALF_TIME_MINUTES: Real = real::from_int(90);
+const CNS_LIMIT_OVER_MAX_PP02_SECONDS: Real = real::from_int(400);
+const CNS_MIN_PP02: Real = real::from_float(0.5);
+const CNS_MAX_PP02: Real = real::from_float(1.6);
+const OTU_MIN_PP02: Real = real::from_float(0.5);
+const OTU_EQUATION_EXPONENT: Real = real::from_float(-0.8333);
+
+// CNS coefficients table ppO2 ranges in numeric core type
+const CNS_PP02_RANGES: [(Real, Real); CNS_COEFFICIENTS.len()] = {
+    let mut ranges = [(real::zero(), real::zero()); CNS_COEFFICIENTS.len()];
+    let mut i = 0;
+    while i < CNS_COEFFICIENTS.len() {
+        let range = &CNS_COEFFICIENTS[i].0;
+        ranges[i] = (
+            real::from_float(*range.start()),
+            real::from_float(*range.end()),
+        );
+        i += 1;
+    }
+    ranges
+};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct OxTox {
-    cns: Cns,
-    otu: Otu,
+    cns: Real,
+    otu: Real,
 }
 
 impl Default for OxTox {
     fn default() -> Self {
-        Self { cns: 0., otu: 0. }
+        Self {
+            cns: real::zero(),
+            otu: real::zero(),
+        }
     }
 }
 
 impl OxTox {
     pub fn cns(&self) -> Cns {
-        self.cns
+        real::to_float(self.cns)
     }
 
     pub fn otu(&self) -> Otu {
-        self.otu
+        real::to_float(self.otu)
     }
 
     pub fn recalculate(&mut self, record: &RecordData, surface_pressure: MbarPressure) {
@@ -39,58 +59,52 @@ impl OxTox {
     fn recalculate_cns(&mut self, record: &RecordData, surface_pressure: MbarPressure) {
         let RecordData { depth, time, gas } = *record;
 
-        let pp_o2 = gas.inspired_partial_pressures(depth, surface_pressure).o2;
+        let (pp_o2, ..) = gas.inspired_partial_pressures_real(depth, surface_pressure);
+        let hundred = real::from_int(100);
 
         // attempt to assign CNS coefficients by o2 partial pressure
         let coeffs_for_range = self.assign_cns_coeffs(pp_o2);
         // only calculate CNS change if o2 partial pressure higher than 0.5
         if let Some((.., slope, intercept)) = coeffs_for_range {
             // time limit for given P02
-            let t_lim = ((slope as f32) * pp_o2) + (intercept as f32);
-            self.cns += (time.as_seconds() / (t_lim * 60.)) * 100.;
+            let t_lim = (real::from_int(slope) * pp_o2) + real::from_int(intercept);
+            self.cns += (time.as_real_seconds() / (t_lim * real::from_int(60))) * hundred;
         } else {
             // PO2 out of cns table range
-            if (depth == Depth::zero()) && (pp_o2 <= 0.5) {
+            if (depth == Depth::zero()) && (pp_o2 <= CNS_MIN_PP02) {
                 // eliminate CNS with half time
-                self.cns /= libm::powf(2.0, time.as_minutes() / (CNS_ELIMINATION_HALF_TIME_MINUTES));
-            } else if pp_o2 > 1.6 {
+                self.cns /= real::exp2(time.as_real_minutes() / CNS_ELIMINATION_HALF_TIME_MINUTES);
+            } else if pp_o2 > CNS_MAX_PP02 {
                 // increase CNS by a constant when ppO2 higher than 1.6
-                self.cns += (time.as_seconds() / CNS_LIMIT_OVER_MAX_PP02_SECONDS) * 100.;
+                self.cns += (time.as_real_seconds() / CNS_LIMIT_OVER_MAX_PP02_SECONDS) * hundred;
             }
         }
     }
 
     fn recalculate_otu(&mut self, record: &RecordData, surface_pressure: MbarPressure) {
         let RecordData { depth, time, gas } = *record;
-        let pp_o2 = gas.inspired_partial_pressures(depth, surface_pressure).o2;
+        let (pp_o2, ..) = gas.inspired_partial_pressures_real(depth, surface_pressure);
 
-        let otu_delta = match pp_o2.total_cmp(&0.5) {
-            Ordering::Less => 0.,
-            Ordering::Equal | Ordering::Greater => {
-                time.as_minutes() * libm::powf((0.5 / (pp_o2 - 0.5)), OTU_EQUATION_EXPONENT)
-            }
-        };
+        if pp_o2 < OTU_MIN_PP02 {
+            return;
+        }
+        let otu_delta = time.as_real_minutes()
+            * real::pow(OTU_MIN_PP02 / (pp_o2 - OTU_MIN_PP02), OTU_EQUATION_EXPONENT);
         self.otu += otu_delta;
     }
 
     // find CNS coefficients by o2 partial pressure
-    fn assign_cns_coeffs(&self, pp_o2: Pressure) -> Option<CNSCoeffRow> {
-        let mut coeffs_for_range: Option<CNSCoeffRow> = None;
-        for row in CNS_COEFFICIENTS.into_iter() {
-            let row_range = row.0.clone();
-            let in_range_start_exclusive =
-                (&pp_o2 != row_range.start()) && row_range.contains(&pp_o2);
-            if in_range_start_exclusive {
-                coeffs_for_range = Some(row);
-                break;
-            }
-        }
-
-        coeffs_for_range
+    fn assign_cns_coeffs(&self, pp_o2: Real) -> Option<CNSCoeffRow> {
+        // range start exclusive
+        CNS_PP02_RANGES
+            .iter()
+            .position(|(start, end)| pp_o2 > *start && pp_o2 <= *end)
+            .map(|i| CNS_COEFFICIENTS[i].clone())
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::excessive_precision)]
 mod tests {
     use super::*;
     use crate::{Gas, Time};
@@ -98,9 +112,8 @@ mod tests {
     #[test]
     fn test_default() {
         let ox_tox = OxTox::default();
-        let OxTox { cns, otu } = ox_tox;
-        assert_eq!(cns, 0.);
-        assert_eq!(otu, 0.);
+        assert_eq!(ox_tox.cns(), 0.);
+        assert_eq!(ox_tox.otu(), 0.);
     }
 
     #[test]
@@ -116,7 +129,7 @@ mod tests {
         ];
 
         for (pp_o2, is_assignable) in assignable_cases.into_iter() {
-            let row = ox_tox.assign_cns_coeffs(pp_o2);
+            let row = ox_tox.assign_cns_coeffs(real::from_float(pp_o2));
             if is_assignable {
                 assert!(row
                     .unwrap_or_else(|| panic!("row for ppO2 {} not found", pp_o2))
@@ -143,7 +156,7 @@ mod tests {
         };
 
         ox_tox.recalculate_cns(&record, 1013);
-        assert_eq!(ox_tox.cns(), 15.018265);
+        assert_close_to_abs!(ox_tox.cns(), 15.018262, 1e-5);
     }
 
     #[test]
@@ -156,7 +169,7 @@ mod tests {
             gas: &Gas::new(0.35, 0.),
         };
         ox_tox.recalculate_cns(&record, 1013);
-        assert_eq!(ox_tox.cns, 48.31898259550245);
+        assert_close_to_abs!(ox_tox.cns(), 48.31898259550245, 1e-5);
         // 2x 90 mins half time
         let mut i = 0;
         while i < 2 {
@@ -170,7 +183,7 @@ mod tests {
             );
             i += 1;
         }
-        assert_eq!(ox_tox.cns, 12.079745648875612);
+        assert_close_to_abs!(ox_tox.cns(), 12.079745648875612, 1e-5);
     }
 
     #[test]
@@ -208,6 +221,6 @@ mod tests {
             gas: &ean32,
         };
         ox_tox.recalculate_otu(&record, 1013);
-        assert_eq!(ox_tox.otu(), 37.75920807052313);
+        assert_close_to_abs!(ox_tox.otu(), 37.75920807052313, 1e-5);
     }
 }
@@ -0,0 +1,69 @@
+use super::{Cns, Depth, Gas, MbarPressure, Otu, Pressure, Time};
+
+/// sample depth, either depth or absolute ambient pressure (bar) e.g. from a pressure sensor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleDepth {
+    Depth(Depth),
+    Pressure(Pressure),
+}
+
+/// logged profile sample, time since dive start
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProfileSample {
+    pub time: Time,
+    pub depth: SampleDepth,
+    pub gas: Gas,
+}
+
+impl ProfileSample {
+    pub fn new(time: Time, depth: Depth, gas: Gas) -> Self {
+        Self {
+            time,
+            depth: SampleDepth::Depth(depth),
+            gas,
+        }
+    }
+
+    pub fn from_pressure(time: Time, pressure: Pressure, gas: Gas) -> Self {
+        Self {
+            time,
+            depth: SampleDepth::Pressure(pressure),
+            gas,
+        }
+    }
+
+    /// sample depth, pressure converted given surface pressure (mbar), clamped to surface
+    pub fn depth(&self, surface_pressure: MbarPressure) -> Depth {
+        match self.depth {
+            SampleDepth::Depth(depth) => depth,
+            SampleDepth::Pressure(pressure) => {
+                let meters = (pressure - surface_pressure as Pressure / 1000.) * 10.;
+                if meters > 0. {
+                    Depth::from_meters(meters)
+                } else {
+                    Depth::zero()
+                }
+            }
+        }
+    }
+}
+
+/// model outputs after replayed sample, details type given by replay (eg. `[Compartment; 16]`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayPoint<D = ()> {
+    pub time: Time,
+    pub depth: Depth,
+    pub gas: Gas,
+    pub ceiling: Depth,
+    // NDL, if enabled
+    pub ndl: Option<Time>,
+    // TTS, if enabled
+    pub tts: Option<Time>,
+    // TTS @+5, if enabled
+    pub tts_at_5: Option<Time>,
+    pub pp_o2: Pressure,
+    pub cns: Cns,
+    pub otu: Otu,
+    // model specific details, if enabled
+    pub details: D,
+}
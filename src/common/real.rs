@@ -0,0 +1,57 @@
+// numeric core type (Float, Q32.32 fixed-point with "fixed-point" feature), converted to Float
+// at public API boundaries
+use super::Float;
+
+#[cfg(not(feature = "fixed-point"))]
+pub(crate) type Real = Float;
+#[cfg(feature = "fixed-point")]
+pub(crate) type Real = super::fixed_point::Fixed;
+
+#[cfg(not(feature = "fixed-point"))]
+pub(crate) const fn from_float(x: Float) -> Real {
+    x
+}
+#[cfg(feature = "fixed-point")]
+pub(crate) const fn from_float(x: Float) -> Real {
+    Real::from_float(x)
+}
+
+#[cfg(not(feature = "fixed-point"))]
+pub(crate) fn to_float(x: Real) -> Float {
+    x
+}
+#[cfg(feature = "fixed-point")]
+pub(crate) fn to_float(x: Real) -> Float {
+    x.to_float()
+}
+
+#[cfg(not(feature = "fixed-point"))]
+pub(crate) const fn from_int(x: i32) -> Real {
+    x as Float
+}
+#[cfg(feature = "fixed-point")]
+pub(crate) const fn from_int(x: i32) -> Real {
+    Real::from_int(x)
+}
+
+pub(crate) const fn zero() -> Real {
+    from_int(0)
+}
+
+#[cfg(not(feature = "fixed-point"))]
+pub(crate) fn exp2(x: Real) -> Real {
+    super::math::pow(2., x)
+}
+#[cfg(feature = "fixed-point")]
+pub(crate) fn exp2(x: Real) -> Real {
+    x.exp2()
+}
+
+#[cfg(not(feature = "fixed-point"))]
+pub(crate) fn pow(x: Real, y: Real) -> Real {
+    super::math::pow(x, y)
+}
+#[cfg(feature = "fixed-point")]
+pub(crate) fn pow(x: Real, y: Real) -> Real {
+    x.powf(y)
+}
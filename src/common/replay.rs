@@ -0,0 +1,217 @@
+use core::{cmp::Ordering, fmt};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::common::{
+    math, Deco, DecoCalculationError, DecoModel, DecoModelConfig, Gas, ProfileSample, ReplayPoint,
+    Sim, Time, MAX_GASSES,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ReplayError {
+    NonMonotonicTime,
+    Calculation(DecoCalculationError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::NonMonotonicTime => {
+                write!(f, "Sample time can't be earlier than previous sample time")
+            }
+            ReplayError::Calculation(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<DecoCalculationError> for ReplayError {
+    fn from(err: DecoCalculationError) -> Self {
+        ReplayError::Calculation(err)
+    }
+}
+
+/// replays logged samples on a model, linear travel between irregular samples
+#[derive(Clone, Debug)]
+pub struct ProfileReplay<T: DecoModel + Clone + Sim, D = ()> {
+    model: T,
+    gas_mixes: [Gas; MAX_GASSES],
+    gas_mixes_count: usize,
+    last_time: Time,
+    last_gas: Option<Gas>,
+    details: fn(&T) -> D,
+    with_ndl: bool,
+    with_tts: bool,
+    with_tts_at_5: bool,
+}
+
+impl<T: DecoModel + Clone + Sim> ProfileReplay<T> {
+    pub fn new(model: T) -> Self {
+        let last_time = model.dive_state().time;
+        Self {
+            model,
+            gas_mixes: [Gas::air(); MAX_GASSES],
+            gas_mixes_count: 0,
+            last_time,
+            last_gas: None,
+            details: |_| (),
+            with_ndl: false,
+            with_tts: false,
+            with_tts_at_5: false,
+        }
+    }
+}
+
+impl<T: DecoModel + Clone + Sim, D> ProfileReplay<T, D> {
+    /// gas mixes available for TTS (in addition to sampled gases), up to MAX_GASSES in total
+    pub fn with_gas_mixes(mut self, gas_mixes: &[Gas]) -> Result<Self, ReplayError> {
+        self.gas_mixes_count = 0;
+        for gas in gas_mixes {
+            self.add_gas_mix(*gas)?;
+        }
+        Ok(self)
+    }
+
+    /// include model specific details in replay points (eg. `BuehlmannModel::tissues`)
+    pub fn with_details<E>(self, details: fn(&T) -> E) -> ProfileReplay<T, E> {
+        ProfileReplay {
+            model: self.model,
+            gas_mixes: self.gas_mixes,
+            gas_mixes_count: self.gas_mixes_count,
+            last_time: self.last_time,
+            last_gas: self.last_gas,
+            details,
+            with_ndl: self.with_ndl,
+            with_tts: self.with_tts,
+            with_tts_at_5: self.with_tts_at_5,
+        }
+    }
+
+    /// include NDL in replay points (NDL calculation per sample)
+    pub fn with_ndl(mut self, with_ndl: bool) -> Self {
+        self.with_ndl = with_ndl;
+        self
+    }
+
+    /// include TTS in replay points (deco calculation per sample)
+    pub fn with_tts(mut self, with_tts: bool) -> Self {
+        self.with_tts = with_tts;
+        self
+    }
+
+    /// include TTS @+5 in replay points (additional deco calculation per sample)
+    pub fn with_tts_at_5(mut self, with_tts_at_5: bool) -> Self {
+        self.with_tts_at_5 = with_tts_at_5;
+        self
+    }
+
+    /// replayed model
+    pub fn model(&self) -> &T {
+        &self.model
+    }
+
+    /// record travel from previous sample (previous sample's gas) and model outputs at sample
+    pub fn replay_sample(&mut self, sample: &ProfileSample) -> Result<ReplayPoint<D>, ReplayError> {
+        let interval = sample.time - self.last_time;
+        match interval.as_seconds().partial_cmp(&0.) {
+            Some(Ordering::Greater | Ordering::Equal) => (),
+            _ => return Err(ReplayError::NonMonotonicTime),
+        }
+
+        let surface_pressure = self.model.config().surface_pressure();
+        let depth = sample.depth(surface_pressure);
+        let travel_gas = self.last_gas.unwrap_or(sample.gas);
+        self.add_gas_mix(sample.gas)?;
+
+        // travel records whole seconds only, fraction recorded at sample depth
+        let travel_seconds = math::floor(interval.as_seconds());
+        let remainder = interval - Time::from_seconds(travel_seconds);
+        self.model
+            .try_record_travel(depth, Time::from_seconds(travel_seconds), &travel_gas)?;
+        if remainder > Time::zero() || sample.gas != travel_gas {
+            self.model.try_record(depth, remainder, &sample.gas)?;
+        }
+        self.last_time = sample.time;
+        self.last_gas = Some(sample.gas);
+
+        self.replay_point(sample.time)
+    }
+
+    /// lazy time series of replay points, stops on first error
+    pub fn replay<'a, I>(
+        &'a mut self,
+        samples: I,
+    ) -> impl Iterator<Item = Result<ReplayPoint<D>, ReplayError>> + 'a
+    where
+        I: IntoIterator<Item = &'a ProfileSample>,
+        I::IntoIter: 'a,
+    {
+        let mut failed = false;
+        samples.into_iter().map_while(move |sample| {
+            if failed {
+                return None;
+            }
+            let point = self.replay_sample(sample);
+            failed = point.is_err();
+            Some(point)
+        })
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn replay_vec(
+        &mut self,
+        samples: &[ProfileSample],
+    ) -> Result<Vec<ReplayPoint<D>>, ReplayError> {
+        self.replay(samples).collect()
+    }
+
+    fn replay_point(&self, time: Time) -> Result<ReplayPoint<D>, ReplayError> {
+        let state = self.model.dive_state();
+        let surface_pressure = self.model.config().surface_pressure();
+        // deco calculated only if TTS or TTS @+5 enabled
+        let runtime = match (self.with_tts, self.with_tts_at_5) {
+            (false, false) => None,
+            (_, with_tts_at_5) => {
+                let mut deco: Deco = match with_tts_at_5 {
+                    true => Deco::default(),
+                    false => Deco::new_sim(),
+                };
+                let gas_mixes = &self.gas_mixes[..self.gas_mixes_count];
+                Some(deco.calc(self.model.fork(), gas_mixes)?)
+            }
+        };
+
+        Ok(ReplayPoint {
+            time,
+            depth: state.depth,
+            gas: state.gas,
+            ceiling: self.model.ceiling(),
+            ndl: self.with_ndl.then(|| self.model.ndl()),
+            tts: runtime.as_ref().filter(|_| self.with_tts).map(|r| r.tts),
+            tts_at_5: runtime
+                .as_ref()
+                .filter(|_| self.with_tts_at_5)
+                .map(|r| r.tts_at_5),
+            pp_o2: state
+                .gas
+                .partial_pressures(state.depth, surface_pressure)
+                .o2,
+            cns: self.model.cns(),
+            otu: self.model.otu(),
+            details: (self.details)(&self.model),
+        })
+    }
+
+    fn add_gas_mix(&mut self, gas: Gas) -> Result<(), ReplayError> {
+        let gas_mixes = &self.gas_mixes[..self.gas_mixes_count];
+        if gas_mixes.contains(&gas) {
+            return Ok(());
+        }
+        if self.gas_mixes_count == MAX_GASSES {
+            return Err(DecoCalculationError::GasListOverflow.into());
+        }
+        self.gas_mixes[self.gas_mixes_count] = gas;
+        self.gas_mixes_count += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use crate::common::{Depth, Time};
+
+/// countdown behavior when leaving the stop band before completion
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SafetyStopExit {
+    // countdown paused, resumed on return to the band
+    Pause,
+    // countdown restarted on return to the band
+    Reset,
+}
+
+/// safety stop config
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SafetyStopConfig {
+    // safety stop required after descent below
+    pub trigger_depth: Depth,
+    // planned stop depth
+    pub stop_depth: Depth,
+    // stop band, countdown runs within
+    pub min_depth: Depth,
+    pub max_depth: Depth,
+    pub duration: Time,
+    pub exit: SafetyStopExit,
+    // add safety stop to no-deco ascents in deco runtime
+    pub planned: bool,
+}
+
+impl Default for SafetyStopConfig {
+    fn default() -> Self {
+        Self {
+            trigger_depth: Depth::from_meters(10.),
+            stop_depth: Depth::from_meters(5.),
+            min_depth: Depth::from_meters(3.),
+            max_depth: Depth::from_meters(6.),
+            duration: Time::from_minutes(3.),
+            exit: SafetyStopExit::Pause,
+            planned: false,
+        }
+    }
+}
+
+impl SafetyStopConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_trigger_depth(mut self, trigger_depth: Depth) -> Self {
+        self.trigger_depth = trigger_depth;
+        self
+    }
+
+    /// stop depth and band (min, max depth)
+    pub fn with_stop_depth(mut self, stop_depth: Depth, band: (Depth, Depth)) -> Self {
+        self.stop_depth = stop_depth;
+        (self.min_depth, self.max_depth) = band;
+        self
+    }
+
+    pub fn with_duration(mut self, duration: Time) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_exit(mut self, exit: SafetyStopExit) -> Self {
+        self.exit = exit;
+        self
+    }
+
+    pub fn with_planned(mut self, planned: bool) -> Self {
+        self.planned = planned;
+        self
+    }
+
+    /// depth within stop band
+    pub fn in_band(&self, depth: Depth) -> bool {
+        depth >= self.min_depth && depth <= self.max_depth
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SafetyStopState {
+    // not required (trigger depth not exceeded)
+    Inactive,
+    // required, outside of stop band
+    Pending { remaining: Time },
+    // countdown within stop band
+    InProgress { remaining: Time },
+    Completed,
+    // surfaced before completion
+    Skipped,
+}
+
+impl SafetyStopState {
+    /// remaining stop time if required and not completed
+    pub fn remaining(&self) -> Option<Time> {
+        match self {
+            SafetyStopState::Pending { remaining } | SafetyStopState::InProgress { remaining } => {
+                Some(*remaining)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// safety stop state machine driven by records (depth, time spent at depth)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SafetyStop {
+    state: SafetyStopState,
+}
+
+impl Default for SafetyStop {
+    fn default() -> Self {
+        Self {
+            state: SafetyStopState::Inactive,
+        }
+    }
+}
+
+impl SafetyStop {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self) -> SafetyStopState {
+        self.state
+    }
+
+    pub fn record(&mut self, config: &SafetyStopConfig, depth: Depth, time: Time) {
+        // (re)triggered below trigger depth
+        if depth > config.trigger_depth {
+            self.state = SafetyStopState::Pending {
+                remaining: config.duration,
+            };
+            return;
+        }
+
+        let (remaining, in_progress) = match self.state {
+            SafetyStopState::Pending { remaining } => (remaining, false),
+            SafetyStopState::InProgress { remaining } => (remaining, true),
+            _ => return,
+        };
+        self.state = if depth <= Depth::zero() {
+            SafetyStopState::Skipped
+        } else if config.in_band(depth) {
+            let remaining = remaining - time;
+            match remaining > Time::zero() {
+                true => SafetyStopState::InProgress { remaining },
+                false => SafetyStopState::Completed,
+            }
+        } else {
+            let remaining = match (in_progress, config.exit) {
+                (true, SafetyStopExit::Reset) => config.duration,
+                _ => remaining,
+            };
+            SafetyStopState::Pending { remaining }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_minutes(safety_stop: &mut SafetyStop, config: &SafetyStopConfig, depth: Depth) {
+        safety_stop.record(config, depth, Time::from_minutes(1.));
+    }
+
+    #[test]
+    fn test_not_triggered() {
+        let config = SafetyStopConfig::default();
+        let mut safety_stop = SafetyStop::new();
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(8.));
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(5.));
+        assert_eq!(safety_stop.state(), SafetyStopState::Inactive);
+    }
+
+    #[test]
+    fn test_countdown() {
+        let config = SafetyStopConfig::default();
+        let mut safety_stop = SafetyStop::new();
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(18.));
+        assert_eq!(
+            safety_stop.state(),
+            SafetyStopState::Pending {
+                remaining: Time::from_minutes(3.)
+            }
+        );
+
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(5.));
+        assert_eq!(
+            safety_stop.state(),
+            SafetyStopState::InProgress {
+                remaining: Time::from_minutes(2.)
+            }
+        );
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(4.));
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(5.));
+        assert_eq!(safety_stop.state(), SafetyStopState::Completed);
+        assert_eq!(safety_stop.state().remaining(), None);
+
+        // completed stop kept until next trigger
+        record_minutes(&mut safety_stop, &config, Depth::zero());
+        assert_eq!(safety_stop.state(), SafetyStopState::Completed);
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(12.));
+        assert_eq!(
+            safety_stop.state().remaining(),
+            Some(Time::from_minutes(3.))
+        );
+    }
+
+    #[test]
+    fn test_exit_pause_and_reset() {
+        let pause_config = SafetyStopConfig::default();
+        let reset_config = SafetyStopConfig::default().with_exit(SafetyStopExit::Reset);
+        for (config, expected_remaining) in [(pause_config, 2.), (reset_config, 3.)] {
+            let mut safety_stop = SafetyStop::new();
+            record_minutes(&mut safety_stop, &config, Depth::from_meters(20.));
+            record_minutes(&mut safety_stop, &config, Depth::from_meters(5.));
+            record_minutes(&mut safety_stop, &config, Depth::from_meters(8.));
+            assert_eq!(
+                safety_stop.state(),
+                SafetyStopState::Pending {
+                    remaining: Time::from_minutes(expected_remaining)
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_skipped() {
+        let config = SafetyStopConfig::default();
+        let mut safety_stop = SafetyStop::new();
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(20.));
+        record_minutes(&mut safety_stop, &config, Depth::from_meters(5.));
+        record_minutes(&mut safety_stop, &config, Depth::zero());
+        assert_eq!(safety_stop.state(), SafetyStopState::Skipped);
+    }
+}
@@ -0,0 +1,622 @@
+use core::fmt;
+
+use super::math;
+use super::xml::{attr, fuse, XmlEvent, XmlReader, XmlSyntaxError};
+use super::{Depth, Float, Gas, MbarPressure, Pressure, ProfileSample, Time, MAX_GASSES};
+
+const GRAVITY: Float = 9.80665;
+const DEFAULT_SURFACE_PRESSURE: MbarPressure = 1013;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SubsurfaceError {
+    InvalidXml,
+    NotSubsurface,
+    MissingValue,
+    InvalidValue,
+    InvalidGas,
+    UnknownCylinder,
+    TooManyCylinders,
+}
+
+impl fmt::Display for SubsurfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubsurfaceError::InvalidXml => write!(f, "Malformed XML"),
+            SubsurfaceError::NotSubsurface => write!(f, "Root element isn't <divelog>"),
+            SubsurfaceError::MissingValue => {
+                write!(f, "Required Subsurface element or attribute missing")
+            }
+            SubsurfaceError::InvalidValue => write!(f, "Subsurface value can't be parsed"),
+            SubsurfaceError::InvalidGas => write!(f, "Subsurface cylinder isn't a valid gas"),
+            SubsurfaceError::UnknownCylinder => {
+                write!(f, "Gas change references unknown cylinder")
+            }
+            SubsurfaceError::TooManyCylinders => {
+                write!(f, "Cylinders exceed max gas mixes count")
+            }
+        }
+    }
+}
+
+impl From<XmlSyntaxError> for SubsurfaceError {
+    fn from(_: XmlSyntaxError) -> Self {
+        SubsurfaceError::InvalidXml
+    }
+}
+
+/// Subsurface XML log (metric units), parsed lazily without allocations
+#[derive(Clone, Copy, Debug)]
+pub struct SubsurfaceLog<'a> {
+    xml: &'a str,
+}
+
+impl<'a> SubsurfaceLog<'a> {
+    pub fn parse(xml: &'a str) -> Result<Self, SubsurfaceError> {
+        let mut reader = XmlReader::new(xml);
+        match reader.next() {
+            Some(Ok(XmlEvent::Start {
+                name: "divelog", ..
+            })) => Ok(Self { xml }),
+            Some(Ok(_)) => Err(SubsurfaceError::NotSubsurface),
+            Some(Err(_)) | None => Err(SubsurfaceError::InvalidXml),
+        }
+    }
+
+    /// all dives, in trips or not
+    pub fn dives(&self) -> SubsurfaceDives<'a> {
+        SubsurfaceDives::new(self.xml)
+    }
+
+    pub fn trips(&self) -> SubsurfaceTrips<'a> {
+        SubsurfaceTrips {
+            xml: self.xml,
+            reader: XmlReader::new(self.xml),
+            done: false,
+        }
+    }
+
+    /// all dives as repetitive dives, see `SubsurfaceTrip::samples`
+    pub fn samples(&self) -> RepetitiveSamples<'a> {
+        RepetitiveSamples::new(self.dives())
+    }
+}
+
+/// dive trip (group of repetitive dives)
+#[derive(Clone, Copy, Debug)]
+pub struct SubsurfaceTrip<'a> {
+    pub location: Option<&'a str>,
+    xml: &'a str,
+}
+
+impl<'a> SubsurfaceTrip<'a> {
+    pub fn dives(&self) -> SubsurfaceDives<'a> {
+        SubsurfaceDives::new(self.xml)
+    }
+
+    /// samples of all trip dives as a single sequence, time since first dive start.
+    /// Each dive starts and ends at the surface, surface intervals breathing air
+    pub fn samples(&self) -> RepetitiveSamples<'a> {
+        RepetitiveSamples::new(self.dives())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SubsurfaceTrips<'a> {
+    xml: &'a str,
+    reader: XmlReader<'a>,
+    done: bool,
+}
+
+impl<'a> SubsurfaceTrips<'a> {
+    fn next_trip(&mut self) -> Result<Option<SubsurfaceTrip<'a>>, SubsurfaceError> {
+        while let Some(event) = self.reader.next() {
+            if let XmlEvent::Start {
+                name: "trip",
+                attrs,
+                empty: false,
+            } = event?
+            {
+                let start = self.reader.position();
+                self.reader.skip_element()?;
+                return Ok(Some(SubsurfaceTrip {
+                    location: attr(attrs, "location"),
+                    xml: &self.xml[start..self.reader.position()],
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Iterator for SubsurfaceTrips<'a> {
+    type Item = Result<SubsurfaceTrip<'a>, SubsurfaceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_trip();
+        fuse(&mut self.done, next)
+    }
+}
+
+/// logged dive, profile of the first dive computer
+#[derive(Clone, Copy, Debug)]
+pub struct SubsurfaceDive<'a> {
+    pub number: Option<u32>,
+    // dive start (local time) in seconds since 1970-01-01
+    pub start: Option<i64>,
+    pub duration: Option<Time>,
+    pub surface_pressure: Option<MbarPressure>,
+    // water salinity (g/l)
+    pub salinity: Option<u16>,
+    cylinders: [Gas; MAX_GASSES],
+    cylinders_count: usize,
+    profile_xml: &'a str,
+}
+
+impl<'a> SubsurfaceDive<'a> {
+    fn parse(attrs: &'a str, xml: &'a str) -> Result<Self, SubsurfaceError> {
+        let number = match attr(attrs, "number") {
+            Some(number) => Some(number.parse().map_err(|_| SubsurfaceError::InvalidValue)?),
+            None => None,
+        };
+        let start = match (attr(attrs, "date"), attr(attrs, "time")) {
+            (Some(date), Some(time)) => Some(parse_timestamp(date, time)?),
+            _ => None,
+        };
+        let duration = match attr(attrs, "duration") {
+            Some(duration) => Some(parse_duration(duration)?),
+            None => None,
+        };
+        let mut dive = Self {
+            number,
+            start,
+            duration,
+            surface_pressure: None,
+            salinity: None,
+            cylinders: [Gas::air(); MAX_GASSES],
+            cylinders_count: 0,
+            profile_xml: xml,
+        };
+
+        let mut reader = XmlReader::new(xml);
+        let mut profile_found = false;
+        while let Some(event) = reader.next() {
+            let XmlEvent::Start { name, attrs, empty } = event? else {
+                continue;
+            };
+            match name {
+                "cylinder" => dive.add_cylinder(parse_gas(attrs)?)?,
+                "surface" if dive.surface_pressure.is_none() => {
+                    if let Some(pressure) = attr(attrs, "pressure") {
+                        let bar = parse_value(pressure, "bar")?;
+                        dive.surface_pressure = Some(to_u16(math::round(bar * 1000.))?);
+                    }
+                }
+                "water" if dive.salinity.is_none() => {
+                    if let Some(salinity) = attr(attrs, "salinity") {
+                        dive.salinity = Some(to_u16(math::round(parse_value(salinity, "g/l")?))?);
+                    }
+                }
+                "divecomputer" if !profile_found && !empty => {
+                    // samples of first dive computer only, header elements still scanned
+                    let start = reader.position();
+                    let mut dc_reader = reader.clone();
+                    dc_reader.skip_element()?;
+                    dive.profile_xml = &xml[start..dc_reader.position()];
+                    profile_found = true;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(dive)
+    }
+
+    /// cylinders gas mixes (gas change events reference cylinder index)
+    pub fn gas_mixes(&self) -> &[Gas] {
+        &self.cylinders[..self.cylinders_count]
+    }
+
+    /// replay-ready samples, time since dive start. Depth is converted to absolute pressure
+    /// using logged salinity and surface pressure if salinity available
+    pub fn samples(&self) -> SubsurfaceSamples<'a> {
+        self.samples_with_offset(Time::zero())
+    }
+
+    /// replay-ready samples with given time offset (e.g. time since first repetitive dive start)
+    pub fn samples_with_offset(&self, offset: Time) -> SubsurfaceSamples<'a> {
+        SubsurfaceSamples {
+            dive: *self,
+            samples: XmlReader::new(self.profile_xml),
+            events: XmlReader::new(self.profile_xml),
+            next_gas_change: None,
+            events_done: false,
+            gas: self.start_gas(),
+            depth: None,
+            offset,
+            done: false,
+        }
+    }
+
+    fn start_gas(&self) -> Gas {
+        self.gas_mixes().first().copied().unwrap_or(Gas::air())
+    }
+
+    fn add_cylinder(&mut self, gas: Gas) -> Result<(), SubsurfaceError> {
+        if self.cylinders_count == MAX_GASSES {
+            return Err(SubsurfaceError::TooManyCylinders);
+        }
+        self.cylinders[self.cylinders_count] = gas;
+        self.cylinders_count += 1;
+        Ok(())
+    }
+
+    fn sample(&self, time: Time, depth: Depth, gas: Gas) -> ProfileSample {
+        match self.salinity {
+            Some(salinity) => {
+                let surface_pressure = self.surface_pressure.unwrap_or(DEFAULT_SURFACE_PRESSURE);
+                let pressure = surface_pressure as Pressure / 1000.
+                    + depth.as_meters() * salinity as Pressure * GRAVITY / 100_000.;
+                ProfileSample::from_pressure(time, pressure, gas)
+            }
+            None => ProfileSample::new(time, depth, gas),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SubsurfaceDives<'a> {
+    xml: &'a str,
+    reader: XmlReader<'a>,
+    done: bool,
+}
+
+impl<'a> SubsurfaceDives<'a> {
+    fn new(xml: &'a str) -> Self {
+        Self {
+            xml,
+            reader: XmlReader::new(xml),
+            done: false,
+        }
+    }
+
+    fn next_dive(&mut self) -> Result<Option<SubsurfaceDive<'a>>, SubsurfaceError> {
+        while let Some(event) = self.reader.next() {
+            if let XmlEvent::Start {
+                name: "dive",
+                attrs,
+                empty: false,
+            } = event?
+            {
+                let start = self.reader.position();
+                self.reader.skip_element()?;
+                let xml = &self.xml[start..self.reader.position()];
+                return SubsurfaceDive::parse(attrs, xml).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Iterator for SubsurfaceDives<'a> {
+    type Item = Result<SubsurfaceDive<'a>, SubsurfaceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_dive();
+        fuse(&mut self.done, next)
+    }
+}
+
+/// dive samples with gas changes applied at event time, missing sample depth carried over
+#[derive(Clone, Debug)]
+pub struct SubsurfaceSamples<'a> {
+    dive: SubsurfaceDive<'a>,
+    samples: XmlReader<'a>,
+    events: XmlReader<'a>,
+    next_gas_change: Option<(Time, Gas)>,
+    events_done: bool,
+    gas: Gas,
+    depth: Option<Depth>,
+    offset: Time,
+    done: bool,
+}
+
+impl SubsurfaceSamples<'_> {
+    fn next_sample(&mut self) -> Result<Option<ProfileSample>, SubsurfaceError> {
+        while let Some(event) = self.samples.next() {
+            let XmlEvent::Start {
+                name: "sample",
+                attrs,
+                ..
+            } = event?
+            else {
+                continue;
+            };
+            let time = parse_duration(attr(attrs, "time").ok_or(SubsurfaceError::MissingValue)?)?;
+            if let Some(depth) = attr(attrs, "depth") {
+                self.depth = Some(Depth::from_meters(parse_value(depth, "m")?));
+            }
+            let depth = self.depth.ok_or(SubsurfaceError::MissingValue)?;
+
+            while let Some((change_time, gas)) = self.peek_gas_change()? {
+                if change_time > time {
+                    break;
+                }
+                self.gas = gas;
+                self.next_gas_change = None;
+            }
+
+            return Ok(Some(self.dive.sample(self.offset + time, depth, self.gas)));
+        }
+        Ok(None)
+    }
+
+    fn peek_gas_change(&mut self) -> Result<Option<(Time, Gas)>, SubsurfaceError> {
+        if self.next_gas_change.is_some() || self.events_done {
+            return Ok(self.next_gas_change);
+        }
+        while let Some(event) = self.events.next() {
+            let XmlEvent::Start {
+                name: "event",
+                attrs,
+                ..
+            } = event?
+            else {
+                continue;
+            };
+            if attr(attrs, "name") != Some("gaschange") {
+                continue;
+            }
+            let time = parse_duration(attr(attrs, "time").ok_or(SubsurfaceError::MissingValue)?)?;
+            self.next_gas_change = Some((time, self.gas_change(attrs)?));
+            return Ok(self.next_gas_change);
+        }
+        self.events_done = true;
+        Ok(None)
+    }
+
+    // by cylinder index, event mix (o2 / he) or legacy value (o2 % + he % << 16)
+    fn gas_change(&self, attrs: &str) -> Result<Gas, SubsurfaceError> {
+        if let Some(cylinder) = attr(attrs, "cylinder") {
+            let index: usize = cylinder
+                .parse()
+                .map_err(|_| SubsurfaceError::InvalidValue)?;
+            return self
+                .dive
+                .gas_mixes()
+                .get(index)
+                .copied()
+                .ok_or(SubsurfaceError::UnknownCylinder);
+        }
+        if attr(attrs, "o2").is_some() {
+            return parse_gas(attrs);
+        }
+        let value: u32 = attr(attrs, "value")
+            .ok_or(SubsurfaceError::MissingValue)?
+            .parse()
+            .map_err(|_| SubsurfaceError::InvalidValue)?;
+        let (o2, he) = ((value & 0xffff) as Float, (value >> 16) as Float);
+        if o2 == 0. {
+            return Ok(Gas::air());
+        }
+        Gas::try_new(o2 / 100., he / 100.).map_err(|_| SubsurfaceError::InvalidGas)
+    }
+}
+
+impl Iterator for SubsurfaceSamples<'_> {
+    type Item = Result<ProfileSample, SubsurfaceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_sample();
+        fuse(&mut self.done, next)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RepetitivePhase {
+    Surface,
+    Samples,
+}
+
+/// repetitive dives samples as a single sequence, time since first dive start
+#[derive(Clone, Debug)]
+pub struct RepetitiveSamples<'a> {
+    dives: SubsurfaceDives<'a>,
+    first_start: Option<i64>,
+    current: Option<(SubsurfaceDive<'a>, SubsurfaceSamples<'a>, Time)>,
+    phase: RepetitivePhase,
+    last_time: Time,
+    done: bool,
+}
+
+impl<'a> RepetitiveSamples<'a> {
+    fn new(dives: SubsurfaceDives<'a>) -> Self {
+        Self {
+            dives,
+            first_start: None,
+            current: None,
+            phase: RepetitivePhase::Surface,
+            last_time: Time::zero(),
+            done: false,
+        }
+    }
+
+    fn next_sample(&mut self) -> Result<Option<ProfileSample>, SubsurfaceError> {
+        loop {
+            let Some((dive, samples, offset)) = &mut self.current else {
+                // next dive, starting at the surface
+                let Some(dive) = self.dives.next().transpose()? else {
+                    return Ok(None);
+                };
+                let start = dive.start.ok_or(SubsurfaceError::MissingValue)?;
+                let first_start = *self.first_start.get_or_insert(start);
+                let offset = Time::from_seconds((start - first_start) as Float);
+                self.current = Some((dive, dive.samples_with_offset(offset), offset));
+                self.phase = RepetitivePhase::Samples;
+                self.last_time = offset;
+                return Ok(Some(dive.sample(offset, Depth::zero(), dive.start_gas())));
+            };
+
+            if self.phase == RepetitivePhase::Samples {
+                if let Some(sample) = samples.next().transpose()? {
+                    self.last_time = sample.time;
+                    return Ok(Some(sample));
+                }
+                // back at the surface after dive duration, surface interval on air
+                self.phase = RepetitivePhase::Surface;
+                let mut end = self.last_time;
+                if let Some(duration) = dive.duration {
+                    if *offset + duration > end {
+                        end = *offset + duration;
+                    }
+                }
+                return Ok(Some(dive.sample(end, Depth::zero(), Gas::air())));
+            }
+            self.current = None;
+        }
+    }
+}
+
+impl Iterator for RepetitiveSamples<'_> {
+    type Item = Result<ProfileSample, SubsurfaceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_sample();
+        fuse(&mut self.done, next)
+    }
+}
+
+// number with unit suffix, e.g. "30.5 m", "1.013 bar", "32.0%"
+fn parse_value(text: &str, unit: &str) -> Result<Float, SubsurfaceError> {
+    let text = text.trim();
+    text.strip_suffix(unit)
+        .unwrap_or(text)
+        .trim()
+        .parse::<Float>()
+        .map_err(|_| SubsurfaceError::InvalidValue)
+}
+
+// positive whole number within u16 range (mbar pressure, g/l salinity)
+fn to_u16(value: Float) -> Result<u16, SubsurfaceError> {
+    if !(value > 0. && value <= u16::MAX as Float) {
+        return Err(SubsurfaceError::InvalidValue);
+    }
+    Ok(value as u16)
+}
+
+// "mm:ss min" (minutes may exceed 59) or "hh:mm:ss"
+fn parse_duration(text: &str) -> Result<Time, SubsurfaceError> {
+    let text = text.trim();
+    let text = text.strip_suffix("min").unwrap_or(text).trim();
+    let mut seconds: Float = 0.;
+    for part in text.split(':') {
+        let value = part
+            .trim()
+            .parse::<Float>()
+            .map_err(|_| SubsurfaceError::InvalidValue)?;
+        seconds = seconds * 60. + value;
+    }
+    Ok(Time::from_seconds(seconds))
+}
+
+// cylinder / event mix, air if no O2 given
+fn parse_gas(attrs: &str) -> Result<Gas, SubsurfaceError> {
+    let o2 = match attr(attrs, "o2") {
+        Some(o2) => parse_value(o2, "%")? / 100.,
+        None => return Ok(Gas::air()),
+    };
+    let he = match attr(attrs, "he") {
+        Some(he) => parse_value(he, "%")? / 100.,
+        None => 0.,
+    };
+    Gas::try_new(o2, he).map_err(|_| SubsurfaceError::InvalidGas)
+}
+
+// "YYYY-MM-DD" and "HH:MM:SS" to seconds since 1970-01-01
+fn parse_timestamp(date: &str, time: &str) -> Result<i64, SubsurfaceError> {
+    let mut date_parts = date.trim().split('-').map(str::parse::<i64>);
+    let mut time_parts = time.trim().split(':').map(str::parse::<i64>);
+    let next = |parts: &mut dyn Iterator<Item = Result<i64, _>>| {
+        parts
+            .next()
+            .unwrap_or(Ok(0))
+            .map_err(|_| SubsurfaceError::InvalidValue)
+    };
+    let (year, month, day) = (
+        next(&mut date_parts)?,
+        next(&mut date_parts)?,
+        next(&mut date_parts)?,
+    );
+    let (hours, minutes, seconds) = (
+        next(&mut time_parts)?,
+        next(&mut time_parts)?,
+        next(&mut time_parts)?,
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(SubsurfaceError::InvalidValue);
+    }
+
+    // days from civil date
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Ok(days * 86_400 + hours * 3_600 + minutes * 60 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45:30 min"), Ok(Time::from_seconds(2730.)));
+        assert_eq!(parse_duration("0:10 min"), Ok(Time::from_seconds(10.)));
+        assert_eq!(parse_duration("1:02:03"), Ok(Time::from_seconds(3723.)));
+        assert_eq!(parse_duration("x min"), Err(SubsurfaceError::InvalidValue));
+    }
+
+    #[test]
+    fn test_to_u16() {
+        assert_eq!(to_u16(1030.), Ok(1030));
+        assert_eq!(to_u16(0.), Err(SubsurfaceError::InvalidValue));
+        assert_eq!(to_u16(-1030.), Err(SubsurfaceError::InvalidValue));
+        assert_eq!(to_u16(70_000.), Err(SubsurfaceError::InvalidValue));
+        assert_eq!(to_u16(Float::NAN), Err(SubsurfaceError::InvalidValue));
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01", "00:00:00"), Ok(0));
+        assert_eq!(parse_timestamp("2024-02-29", "10:12:30"), Ok(1_709_201_550));
+        assert_eq!(
+            parse_timestamp("2024-13-01", "10:12:30"),
+            Err(SubsurfaceError::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn test_parse_gas() {
+        assert_eq!(parse_gas("size='11.1 l'"), Ok(Gas::air()));
+        assert_eq!(parse_gas("o2='32.0%'"), Ok(Gas::new(0.32, 0.)));
+        assert_eq!(parse_gas("o2='18.0%' he='45.0%'"), Ok(Gas::new(0.18, 0.45)));
+        assert_eq!(
+            parse_gas("o2='80.0%' he='45.0%'"),
+            Err(SubsurfaceError::InvalidGas)
+        );
+    }
+}
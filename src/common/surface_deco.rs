@@ -0,0 +1,350 @@
+use crate::common::deco::{Deco, DecoCalculationError, DecoStage, DecoStageBuffer, DecoStageType};
+use crate::common::{
+    AscentRatePerMinute, Cns, DecoModel, DecoModelConfig, Depth, Gas, Otu, Sim, Time,
+    MAX_DECO_STAGE,
+};
+
+// max O2 periods in chamber
+const MAX_O2_PERIODS: usize = 16;
+// surface interval, descent, O2 periods with air breaks in between and ascent
+const MAX_CHAMBER_STAGE: usize = 2 * MAX_O2_PERIODS + 2;
+
+/// surface decompression on oxygen (SurDO2) procedure config
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SurfaceDecoConfig {
+    // in-water stops at or above given depth are omitted
+    pub water_stops_limit: Depth,
+    // ascent from last in-water stop to the surface
+    pub surface_ascent_rate: AscentRatePerMinute,
+    // time at the surface (1 atm, air) before recompression
+    pub surface_interval: Time,
+    pub chamber_depth: Depth,
+    pub chamber_descent_rate: AscentRatePerMinute,
+    pub chamber_ascent_rate: AscentRatePerMinute,
+    pub o2_period: Time,
+    pub air_break: Time,
+    // chamber breathing gas during O2 periods and recompression
+    pub o2_gas: Gas,
+    pub break_gas: Gas,
+}
+
+impl Default for SurfaceDecoConfig {
+    fn default() -> Self {
+        Self {
+            water_stops_limit: Depth::from_meters(12.),
+            surface_ascent_rate: 9.,
+            surface_interval: Time::from_minutes(3.),
+            chamber_depth: Depth::from_meters(15.),
+            chamber_descent_rate: 30.,
+            chamber_ascent_rate: 9.,
+            o2_period: Time::from_minutes(30.),
+            air_break: Time::from_minutes(5.),
+            o2_gas: Gas::new(1., 0.),
+            break_gas: Gas::air(),
+        }
+    }
+}
+
+impl SurfaceDecoConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_water_stops_limit(mut self, water_stops_limit: Depth) -> Self {
+        self.water_stops_limit = water_stops_limit;
+        self
+    }
+
+    pub fn with_surface_interval(mut self, surface_interval: Time) -> Self {
+        self.surface_interval = surface_interval;
+        self
+    }
+
+    pub fn with_chamber_depth(mut self, chamber_depth: Depth) -> Self {
+        self.chamber_depth = chamber_depth;
+        self
+    }
+
+    /// O2 period and air break durations
+    pub fn with_o2_periods(mut self, o2_period: Time, air_break: Time) -> Self {
+        self.o2_period = o2_period;
+        self.air_break = air_break;
+        self
+    }
+
+    /// ascent rates from the water and from the chamber, chamber descent rate
+    pub fn with_travel_rates(
+        mut self,
+        surface_ascent_rate: AscentRatePerMinute,
+        chamber_descent_rate: AscentRatePerMinute,
+        chamber_ascent_rate: AscentRatePerMinute,
+    ) -> Self {
+        self.surface_ascent_rate = surface_ascent_rate;
+        self.chamber_descent_rate = chamber_descent_rate;
+        self.chamber_ascent_rate = chamber_ascent_rate;
+        self
+    }
+
+    pub fn with_gases(mut self, o2_gas: Gas, break_gas: Gas) -> Self {
+        self.o2_gas = o2_gas;
+        self.break_gas = break_gas;
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ChamberStageType {
+    SurfaceInterval,
+    Descent,
+    O2Period,
+    AirBreak,
+    Ascent,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChamberStage {
+    pub stage_type: ChamberStageType,
+    pub start_depth: Depth,
+    pub end_depth: Depth,
+    pub duration: Time,
+    pub gas: Gas,
+}
+
+impl Default for ChamberStage {
+    fn default() -> Self {
+        Self {
+            stage_type: ChamberStageType::SurfaceInterval,
+            start_depth: Depth::zero(),
+            end_depth: Depth::zero(),
+            duration: Time::zero(),
+            gas: Gas::air(),
+        }
+    }
+}
+
+/// chamber schedule (surface interval, recompression, O2 periods with air breaks, ascent)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChamberSchedule {
+    stages: [ChamberStage; MAX_CHAMBER_STAGE],
+    len: usize,
+}
+
+impl Default for ChamberSchedule {
+    fn default() -> Self {
+        Self {
+            stages: [ChamberStage::default(); MAX_CHAMBER_STAGE],
+            len: 0,
+        }
+    }
+}
+
+impl ChamberSchedule {
+    pub fn stages(&self) -> &[ChamberStage] {
+        &self.stages[..self.len]
+    }
+
+    /// number of O2 periods
+    pub fn o2_periods(&self) -> usize {
+        self.stages()
+            .iter()
+            .filter(|stage| stage.stage_type == ChamberStageType::O2Period)
+            .count()
+    }
+
+    /// total chamber time (surface interval excluded)
+    pub fn chamber_time(&self) -> Time {
+        self.stages()
+            .iter()
+            .filter(|stage| stage.stage_type != ChamberStageType::SurfaceInterval)
+            .fold(Time::zero(), |total, stage| total + stage.duration)
+    }
+
+    fn push(&mut self, stage: ChamberStage) -> Result<(), DecoCalculationError> {
+        if self.len >= MAX_CHAMBER_STAGE {
+            return Err(DecoCalculationError::StageBufferOverflow);
+        }
+        self.stages[self.len] = stage;
+        self.len += 1;
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SurfaceDecoRuntime<S: DecoStageBuffer = [DecoStage; MAX_DECO_STAGE]> {
+    // in-water stages incl. final ascent to the surface
+    pub water_stages: S,
+    pub chamber: ChamberSchedule,
+    // time from current state to the end of chamber decompression
+    pub tts: Time,
+    pub cns: Cns,
+    pub otu: Otu,
+}
+
+impl<S: DecoStageBuffer> SurfaceDecoRuntime<S> {
+    /// in-water deco stages
+    pub fn stages(&self) -> &[DecoStage] {
+        self.water_stages.stages()
+    }
+
+    /// chamber stages
+    pub fn chamber_stages(&self) -> &[ChamberStage] {
+        self.chamber.stages()
+    }
+}
+
+/// surface decompression planner on top of in-water deco runtime
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SurfaceDeco {
+    config: SurfaceDecoConfig,
+}
+
+impl SurfaceDeco {
+    pub fn new(config: SurfaceDecoConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> SurfaceDecoConfig {
+        self.config
+    }
+
+    /// in-water stages deeper than stops limit, surface interval and chamber schedule
+    pub fn calc<T: DecoModel + Clone + Sim, S: DecoStageBuffer>(
+        &self,
+        deco_model: &T,
+        gas_mixes: &[Gas],
+    ) -> Result<SurfaceDecoRuntime<S>, DecoCalculationError> {
+        let config = self.config;
+        let water_runtime = Deco::<S>::new_sim().calc(deco_model.fork(), gas_mixes)?;
+        let mut sim_model = deco_model.fork();
+        let start_time = sim_model.dive_state().time;
+        let ascent_rate = sim_model.config().deco_ascent_rate();
+
+        // in-water stages deeper than the stops limit
+        let mut water_stages = S::empty();
+        for stage in water_runtime.stages() {
+            if stage.start_depth <= config.water_stops_limit {
+                break;
+            }
+            match stage.stage_type {
+                DecoStageType::Ascent => {
+                    sim_model.try_record_travel_with_rate(
+                        stage.end_depth,
+                        ascent_rate,
+                        &stage.gas,
+                    )?;
+                }
+                _ => sim_model.try_record(stage.end_depth, stage.duration, &stage.gas)?,
+            }
+            water_stages.push(*stage)?;
+            if stage.end_depth <= Depth::zero() {
+                break;
+            }
+        }
+
+        // ascent to the surface from last in-water stop
+        let surfacing_state = sim_model.dive_state();
+        if surfacing_state.depth > Depth::zero() {
+            let stage_gf = sim_model.gradient_factor_at(Depth::zero());
+            sim_model.try_record_travel_with_rate(
+                Depth::zero(),
+                config.surface_ascent_rate,
+                &surfacing_state.gas,
+            )?;
+            let surface_pressure = sim_model.config().surface_pressure();
+            water_stages.push(DecoStage {
+                stage_type: DecoStageType::Ascent,
+                start_depth: surfacing_state.depth,
+                end_depth: Depth::zero(),
+                duration: sim_model.dive_state().time - surfacing_state.time,
+                gas: surfacing_state.gas,
+                runtime: sim_model.dive_state().time,
+                pp_o2: surfacing_state
+                    .gas
+                    .partial_pressures(Depth::zero(), surface_pressure)
+                    .o2,
+                end: Depth::zero(),
+                gf: stage_gf,
+                cns: sim_model.cns(),
+                otu: sim_model.otu(),
+                valid: true,
+            })?;
+        }
+
+        let mut chamber = ChamberSchedule::default();
+        // surface interval at 1 atm
+        sim_model.try_record(Depth::zero(), config.surface_interval, &config.break_gas)?;
+        chamber.push(ChamberStage {
+            stage_type: ChamberStageType::SurfaceInterval,
+            start_depth: Depth::zero(),
+            end_depth: Depth::zero(),
+            duration: config.surface_interval,
+            gas: config.break_gas,
+        })?;
+
+        // recompression
+        let pre_descent_time = sim_model.dive_state().time;
+        sim_model.try_record_travel_with_rate(
+            config.chamber_depth,
+            config.chamber_descent_rate,
+            &config.o2_gas,
+        )?;
+        chamber.push(ChamberStage {
+            stage_type: ChamberStageType::Descent,
+            start_depth: Depth::zero(),
+            end_depth: config.chamber_depth,
+            duration: sim_model.dive_state().time - pre_descent_time,
+            gas: config.o2_gas,
+        })?;
+
+        // O2 periods separated by air breaks until ascent to the surface is cleared
+        let mut o2_periods = 0;
+        loop {
+            sim_model.try_record(config.chamber_depth, config.o2_period, &config.o2_gas)?;
+            chamber.push(ChamberStage {
+                stage_type: ChamberStageType::O2Period,
+                start_depth: config.chamber_depth,
+                end_depth: config.chamber_depth,
+                duration: config.o2_period,
+                gas: config.o2_gas,
+            })?;
+            o2_periods += 1;
+            if sim_model.ceiling() <= Depth::zero() {
+                break;
+            }
+            if o2_periods >= MAX_O2_PERIODS {
+                return Err(DecoCalculationError::RuntimeLimitExceeded);
+            }
+            sim_model.try_record(config.chamber_depth, config.air_break, &config.break_gas)?;
+            chamber.push(ChamberStage {
+                stage_type: ChamberStageType::AirBreak,
+                start_depth: config.chamber_depth,
+                end_depth: config.chamber_depth,
+                duration: config.air_break,
+                gas: config.break_gas,
+            })?;
+        }
+
+        // chamber ascent on O2
+        let pre_ascent_time = sim_model.dive_state().time;
+        sim_model.try_record_travel_with_rate(
+            Depth::zero(),
+            config.chamber_ascent_rate,
+            &config.o2_gas,
+        )?;
+        chamber.push(ChamberStage {
+            stage_type: ChamberStageType::Ascent,
+            start_depth: config.chamber_depth,
+            end_depth: Depth::zero(),
+            duration: sim_model.dive_state().time - pre_ascent_time,
+            gas: config.o2_gas,
+        })?;
+
+        Ok(SurfaceDecoRuntime {
+            water_stages,
+            chamber,
+            tts: sim_model.dive_state().time - start_time,
+            cns: sim_model.cns(),
+            otu: sim_model.otu(),
+        })
+    }
+}
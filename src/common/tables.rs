@@ -0,0 +1,326 @@
+use core::{cmp::Ordering, fmt};
+
+use super::{
+    math, ConfigValidationErr, Deco, DecoCalculationError, DecoModel, DecoRuntime, DecoStage,
+    DecoStageType, Depth, DepthType, Gas, Sim, Time,
+};
+
+/// table output format
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TableFormat {
+    Csv,
+    Markdown,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TableError {
+    InvalidDepthRange,
+    InvalidBottomTime,
+    Calculation(DecoCalculationError),
+    Format,
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::InvalidDepthRange => write!(
+                f,
+                "Depth range must be non-negative with positive step and end not shallower than start"
+            ),
+            TableError::InvalidBottomTime => {
+                write!(f, "Bottom time must be a non-negative number")
+            }
+            TableError::Calculation(err) => write!(f, "{}", err),
+            TableError::Format => write!(f, "Table output write failed"),
+        }
+    }
+}
+
+impl From<DecoCalculationError> for TableError {
+    fn from(err: DecoCalculationError) -> Self {
+        TableError::Calculation(err)
+    }
+}
+
+impl From<fmt::Error> for TableError {
+    fn from(_: fmt::Error) -> Self {
+        TableError::Format
+    }
+}
+
+/// depths from start to end (inclusive) with given step
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthRange {
+    pub from: Depth,
+    pub to: Depth,
+    pub step: Depth,
+}
+
+impl DepthRange {
+    pub fn new(from: Depth, to: Depth, step: Depth) -> Self {
+        Self { from, to, step }
+    }
+
+    pub fn validate(&self) -> Result<(), TableError> {
+        let (from, to, step) = (
+            self.from.as_meters(),
+            self.to.as_meters(),
+            self.step.as_meters(),
+        );
+        if !(from.is_finite() && to.is_finite() && step.is_finite()) {
+            return Err(TableError::InvalidDepthRange);
+        }
+        if from < 0. || step <= 0. || to < from {
+            return Err(TableError::InvalidDepthRange);
+        }
+
+        Ok(())
+    }
+
+    /// depths in range, empty if range invalid
+    pub fn depths(&self) -> impl Iterator<Item = Depth> {
+        let (from, to, step) = (
+            self.from.as_meters(),
+            self.to.as_meters(),
+            self.step.as_meters(),
+        );
+        let count = match self.validate() {
+            // small tolerance for float steps not adding up exactly to range end
+            Ok(()) => math::floor((to - from) / step + 1e-3) as usize + 1,
+            Err(_) => 0,
+        };
+        (0..count).map(move |i| Depth::from_meters(from + step * i as DepthType))
+    }
+}
+
+/// NDL table cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NdlTableRow {
+    pub depth: Depth,
+    pub ndl: Time,
+}
+
+/// deco table cell
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecoTableRow {
+    pub depth: Depth,
+    pub bottom_time: Time,
+    pub runtime: DecoRuntime,
+}
+
+impl DecoTableRow {
+    /// deco stops (ascents and gas switches excluded)
+    pub fn stops(&self) -> impl Iterator<Item = &DecoStage> {
+        self.runtime
+            .stages()
+            .iter()
+            .filter(|stage| stage.stage_type == DecoStageType::DecoStop)
+    }
+
+    /// first deco stop depth, none if no deco obligation
+    pub fn first_stop(&self) -> Option<Depth> {
+        self.stops().next().map(|stage| stage.start_depth)
+    }
+}
+
+/// NDL and deco tables for a single gas, instantaneous descent to each depth
+#[derive(Clone, Debug)]
+pub struct DiveTables<'a, T: DecoModel + Clone + Sim> {
+    model: T,
+    depths: DepthRange,
+    bottom_times: &'a [Time],
+    gas: Gas,
+}
+
+impl<'a, T: DecoModel + Clone + Sim> DiveTables<'a, T> {
+    pub fn new(
+        config: T::ConfigType,
+        depths: DepthRange,
+        bottom_times: &'a [Time],
+        gas: Gas,
+    ) -> Self {
+        match Self::try_new(config, depths, bottom_times, gas) {
+            Ok(tables) => tables,
+            Err(e) => panic!("Config error [{:?}]: {:?}", e.field, e.reason),
+        }
+    }
+
+    pub fn try_new(
+        config: T::ConfigType,
+        depths: DepthRange,
+        bottom_times: &'a [Time],
+        gas: Gas,
+    ) -> Result<Self, ConfigValidationErr> {
+        Ok(Self {
+            model: T::try_new(config)?,
+            depths,
+            bottom_times,
+            gas,
+        })
+    }
+
+    /// NDL at given depth
+    pub fn ndl_row(&self, depth: Depth) -> Result<NdlTableRow, TableError> {
+        let mut sim_model = self.model.fork();
+        sim_model.try_record(depth, Time::zero(), &self.gas)?;
+        Ok(NdlTableRow {
+            depth,
+            ndl: sim_model.ndl(),
+        })
+    }
+
+    /// deco runtime after given bottom time at depth
+    pub fn deco_row(&self, depth: Depth, bottom_time: Time) -> Result<DecoTableRow, TableError> {
+        match bottom_time.as_seconds().partial_cmp(&0.) {
+            Some(Ordering::Greater | Ordering::Equal) => (),
+            _ => return Err(TableError::InvalidBottomTime),
+        }
+        let mut sim_model = self.model.fork();
+        sim_model.try_record(depth, bottom_time, &self.gas)?;
+        let mut deco: Deco = Deco::new_sim();
+        let runtime = deco.calc(sim_model, &[self.gas])?;
+        Ok(DecoTableRow {
+            depth,
+            bottom_time,
+            runtime,
+        })
+    }
+
+    /// NDL table, NDL rounded down to whole minutes
+    pub fn write_ndl_table<W: fmt::Write>(
+        &self,
+        out: &mut W,
+        format: TableFormat,
+    ) -> Result<(), TableError> {
+        self.depths.validate()?;
+        write_header(out, format, &["Depth (m)", "NDL (min)"])?;
+        for depth in self.depths.depths() {
+            let row = self.ndl_row(depth)?;
+            let ndl = math::floor(row.ndl.as_minutes()) as u32;
+            write_row(out, format, &[&row.depth.as_meters(), &ndl])?;
+        }
+
+        Ok(())
+    }
+
+    /// deco table, stops and TTS rounded up to whole minutes
+    pub fn write_deco_table<W: fmt::Write>(
+        &self,
+        out: &mut W,
+        format: TableFormat,
+    ) -> Result<(), TableError> {
+        self.depths.validate()?;
+        write_header(
+            out,
+            format,
+            &[
+                "Depth (m)",
+                "Bottom time (min)",
+                "First stop (m)",
+                "Stops",
+                "TTS (min)",
+            ],
+        )?;
+        for depth in self.depths.depths() {
+            for bottom_time in self.bottom_times {
+                let row = self.deco_row(depth, *bottom_time)?;
+                let first_stop = OptionalCell(row.first_stop().map(|depth| depth.as_meters()));
+                let tts = math::ceil(row.runtime.tts.as_minutes()) as u32;
+                write_row(
+                    out,
+                    format,
+                    &[
+                        &row.depth.as_meters(),
+                        &row.bottom_time.as_minutes(),
+                        &first_stop,
+                        &StopsCell(&row),
+                        &tts,
+                    ],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// "-" for missing values
+struct OptionalCell<V: fmt::Display>(Option<V>);
+
+impl<V: fmt::Display> fmt::Display for OptionalCell<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(value) => write!(f, "{}", value),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+// deco stops as "6m 2min; 3m 5min" (no CSV / Markdown separators)
+struct StopsCell<'a>(&'a DecoTableRow);
+
+impl fmt::Display for StopsCell<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stops = self.0.stops().peekable();
+        if stops.peek().is_none() {
+            return write!(f, "-");
+        }
+        for (i, stop) in stops.enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            let duration = math::ceil(stop.duration.as_minutes()) as u32;
+            write!(f, "{}m {}min", stop.start_depth.as_meters(), duration)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_header<W: fmt::Write>(out: &mut W, format: TableFormat, headers: &[&str]) -> fmt::Result {
+    match format {
+        TableFormat::Csv => {
+            for (i, header) in headers.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ",")?;
+                }
+                write!(out, "{}", header)?;
+            }
+            writeln!(out)
+        }
+        TableFormat::Markdown => {
+            for header in headers {
+                write!(out, "| {} ", header)?;
+            }
+            writeln!(out, "|")?;
+            for _ in headers {
+                write!(out, "| --- ")?;
+            }
+            writeln!(out, "|")
+        }
+    }
+}
+
+fn write_row<W: fmt::Write>(
+    out: &mut W,
+    format: TableFormat,
+    cells: &[&dyn fmt::Display],
+) -> fmt::Result {
+    match format {
+        TableFormat::Csv => {
+            for (i, cell) in cells.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ",")?;
+                }
+                write!(out, "{}", cell)?;
+            }
+            writeln!(out)
+        }
+        TableFormat::Markdown => {
+            for cell in cells {
+                write!(out, "| {} ", cell)?;
+            }
+            writeln!(out, "|")
+        }
+    }
+}
@@ -3,9 +3,14 @@ use core::{
     ops::{Add, AddAssign, Div, Mul, Sub},
 };
 
+use super::{
+    real::{self, Real},
+    Float,
+};
+
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub struct Time {
-    s: f32,
+    s: Real,
 }
 
 impl Add for Time {
@@ -35,7 +40,7 @@ impl Mul<u8> for Time {
     type Output = Self;
     fn mul(self, rhs: u8) -> Self::Output {
         Self {
-            s: self.s * rhs as f32,
+            s: self.s * real::from_int(rhs as i32),
         }
     }
 }
@@ -53,20 +58,30 @@ impl PartialOrd for Time {
 }
 
 impl Time {
-    pub fn from_seconds(val: f32) -> Self {
-        Self { s: val }
+    pub fn from_seconds(val: Float) -> Self {
+        Self {
+            s: real::from_float(val),
+        }
     }
-    pub fn from_minutes(val: f32) -> Self {
-        Self { s: val * 60. }
+    pub fn from_minutes(val: Float) -> Self {
+        Self {
+            s: real::from_float(val * 60.),
+        }
     }
     pub fn zero() -> Self {
-        Self { s: 0. }
+        Self { s: real::zero() }
+    }
+    pub fn as_seconds(&self) -> Float {
+        real::to_float(self.s)
+    }
+    pub fn as_minutes(&self) -> Float {
+        real::to_float(self.s / real::from_int(60))
     }
-    pub fn as_seconds(&self) -> f32 {
+    pub(crate) fn as_real_seconds(&self) -> Real {
         self.s
     }
-    pub fn as_minutes(&self) -> f32 {
-        self.s / 60.
+    pub(crate) fn as_real_minutes(&self) -> Real {
+        self.s / real::from_int(60)
     }
 }
 
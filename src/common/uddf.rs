@@ -0,0 +1,488 @@
+use core::fmt;
+
+use super::xml::{attr, fuse, XmlEvent, XmlReader, XmlSyntaxError};
+use super::{
+    DecoRuntime, DecoStageBuffer, Depth, Float, Gas, GasNotation, ProfileSample, ReplayPoint, Time,
+    MAX_GASSES,
+};
+
+const UDDF_VERSION: &str = "3.2.1";
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum UddfError {
+    InvalidXml,
+    NotUddf,
+    MissingValue,
+    InvalidValue,
+    InvalidGas,
+    UnknownMix,
+    TooManyMixes,
+    Format,
+}
+
+impl fmt::Display for UddfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UddfError::InvalidXml => write!(f, "Malformed XML"),
+            UddfError::NotUddf => write!(f, "Root element isn't <uddf>"),
+            UddfError::MissingValue => write!(f, "Required UDDF element or attribute missing"),
+            UddfError::InvalidValue => write!(f, "UDDF value isn't a valid number"),
+            UddfError::InvalidGas => write!(f, "UDDF mix isn't a valid gas"),
+            UddfError::UnknownMix => write!(f, "Referenced mix not found in gas definitions"),
+            UddfError::TooManyMixes => write!(f, "Gas mixes exceed max gas mixes count"),
+            UddfError::Format => write!(f, "UDDF output write failed"),
+        }
+    }
+}
+
+impl From<XmlSyntaxError> for UddfError {
+    fn from(_: XmlSyntaxError) -> Self {
+        UddfError::InvalidXml
+    }
+}
+
+impl From<fmt::Error> for UddfError {
+    fn from(_: fmt::Error) -> Self {
+        UddfError::Format
+    }
+}
+
+/// UDDF gas definition
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UddfMix<'a> {
+    pub id: &'a str,
+    pub gas: Gas,
+}
+
+/// UDDF document (SI units: meters, seconds, fractions), parsed lazily without allocations
+#[derive(Clone, Copy, Debug)]
+pub struct UddfDocument<'a> {
+    xml: &'a str,
+}
+
+impl<'a> UddfDocument<'a> {
+    pub fn parse(xml: &'a str) -> Result<Self, UddfError> {
+        let mut reader = XmlReader::new(xml);
+        match reader.next() {
+            Some(Ok(XmlEvent::Start { name: "uddf", .. })) => Ok(Self { xml }),
+            Some(Ok(_)) => Err(UddfError::NotUddf),
+            Some(Err(_)) | None => Err(UddfError::InvalidXml),
+        }
+    }
+
+    /// gas definitions
+    pub fn gas_mixes(&self) -> UddfMixes<'a> {
+        UddfMixes {
+            reader: XmlReader::new(self.xml),
+            done: false,
+        }
+    }
+
+    /// gas definition by mix id
+    pub fn gas(&self, id: &str) -> Result<Gas, UddfError> {
+        for mix in self.gas_mixes() {
+            let mix = mix?;
+            if mix.id == id {
+                return Ok(mix.gas);
+            }
+        }
+        Err(UddfError::UnknownMix)
+    }
+
+    /// logged and planned dives (all repetition groups)
+    pub fn dives(&self) -> UddfDives<'a> {
+        UddfDives {
+            document: *self,
+            reader: XmlReader::new(self.xml),
+            done: false,
+        }
+    }
+}
+
+/// gas definitions iterator
+#[derive(Clone, Debug)]
+pub struct UddfMixes<'a> {
+    reader: XmlReader<'a>,
+    done: bool,
+}
+
+impl<'a> UddfMixes<'a> {
+    fn next_mix(&mut self) -> Result<Option<UddfMix<'a>>, UddfError> {
+        while let Some(event) = self.reader.next() {
+            if let XmlEvent::Start {
+                name: "mix",
+                attrs,
+                empty: false,
+            } = event?
+            {
+                let id = attr(attrs, "id").ok_or(UddfError::MissingValue)?;
+                return self.read_mix(id).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_mix(&mut self, id: &'a str) -> Result<UddfMix<'a>, UddfError> {
+        let (mut o2, mut he) = (None, 0.);
+        loop {
+            match self.reader.next().ok_or(UddfError::InvalidXml)?? {
+                XmlEvent::Start { empty: true, .. } | XmlEvent::Text(_) => (),
+                XmlEvent::Start { name: "o2", .. } => {
+                    o2 = Some(parse_number(self.reader.element_text()?)?);
+                }
+                XmlEvent::Start { name: "he", .. } => {
+                    he = parse_number(self.reader.element_text()?)?;
+                }
+                XmlEvent::Start { .. } => self.reader.skip_element()?,
+                XmlEvent::End { .. } => break,
+            }
+        }
+        let o2 = o2.ok_or(UddfError::MissingValue)?;
+        let gas = Gas::try_new(o2, he).map_err(|_| UddfError::InvalidGas)?;
+        Ok(UddfMix { id, gas })
+    }
+}
+
+impl<'a> Iterator for UddfMixes<'a> {
+    type Item = Result<UddfMix<'a>, UddfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_mix();
+        fuse(&mut self.done, next)
+    }
+}
+
+/// UDDF dive
+#[derive(Clone, Copy, Debug)]
+pub struct UddfDive<'a> {
+    pub id: Option<&'a str>,
+    document: UddfDocument<'a>,
+    xml: &'a str,
+}
+
+impl<'a> UddfDive<'a> {
+    /// logged profile (samples waypoints)
+    pub fn samples(&self) -> UddfSamples<'a> {
+        UddfSamples::new(*self, "samples")
+    }
+
+    /// planned profile (before dive information), e.g. exported deco plan
+    pub fn planned_samples(&self) -> UddfSamples<'a> {
+        UddfSamples::new(*self, "plannedprofile")
+    }
+}
+
+/// dives iterator
+#[derive(Clone, Debug)]
+pub struct UddfDives<'a> {
+    document: UddfDocument<'a>,
+    reader: XmlReader<'a>,
+    done: bool,
+}
+
+impl<'a> UddfDives<'a> {
+    fn next_dive(&mut self) -> Result<Option<UddfDive<'a>>, UddfError> {
+        while let Some(event) = self.reader.next() {
+            if let XmlEvent::Start {
+                name: "dive",
+                attrs,
+                empty: false,
+            } = event?
+            {
+                let start = self.reader.position();
+                self.reader.skip_element()?;
+                let xml = &self.document.xml[start..self.reader.position()];
+                return Ok(Some(UddfDive {
+                    id: attr(attrs, "id"),
+                    document: self.document,
+                    xml,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Iterator for UddfDives<'a> {
+    type Item = Result<UddfDive<'a>, UddfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_dive();
+        fuse(&mut self.done, next)
+    }
+}
+
+/// replay-ready samples of a dive profile, air assumed until first mix switch
+#[derive(Clone, Debug)]
+pub struct UddfSamples<'a> {
+    document: UddfDocument<'a>,
+    reader: XmlReader<'a>,
+    section: &'static str,
+    in_section: bool,
+    gas: Gas,
+    done: bool,
+}
+
+impl<'a> UddfSamples<'a> {
+    fn new(dive: UddfDive<'a>, section: &'static str) -> Self {
+        Self {
+            document: dive.document,
+            reader: XmlReader::new(dive.xml),
+            section,
+            in_section: false,
+            gas: Gas::air(),
+            done: false,
+        }
+    }
+
+    fn next_sample(&mut self) -> Result<Option<ProfileSample>, UddfError> {
+        while !self.in_section {
+            match self.reader.next() {
+                None => return Ok(None),
+                Some(event) => {
+                    if let XmlEvent::Start { name, attrs, empty } = event? {
+                        if name != self.section {
+                            continue;
+                        }
+                        if empty {
+                            return Ok(None);
+                        }
+                        if let Some(start_mix) = attr(attrs, "startmix") {
+                            self.gas = self.document.gas(start_mix)?;
+                        }
+                        self.in_section = true;
+                    }
+                }
+            }
+        }
+
+        loop {
+            match self.reader.next().ok_or(UddfError::InvalidXml)?? {
+                XmlEvent::Start {
+                    name: "waypoint",
+                    empty: false,
+                    ..
+                } => return self.read_waypoint().map(Some),
+                XmlEvent::Start { empty: false, .. } => self.reader.skip_element()?,
+                XmlEvent::End { .. } => return Ok(None),
+                _ => (),
+            }
+        }
+    }
+
+    fn read_waypoint(&mut self) -> Result<ProfileSample, UddfError> {
+        let (mut depth, mut time) = (None, None);
+        loop {
+            match self.reader.next().ok_or(UddfError::InvalidXml)?? {
+                XmlEvent::Start {
+                    name: "switchmix",
+                    attrs,
+                    empty,
+                } => {
+                    let mix = attr(attrs, "ref").ok_or(UddfError::MissingValue)?;
+                    self.gas = self.document.gas(mix)?;
+                    if !empty {
+                        self.reader.skip_element()?;
+                    }
+                }
+                XmlEvent::Start { empty: true, .. } | XmlEvent::Text(_) => (),
+                XmlEvent::Start { name: "depth", .. } => {
+                    depth = Some(parse_number(self.reader.element_text()?)?);
+                }
+                XmlEvent::Start {
+                    name: "divetime", ..
+                } => {
+                    time = Some(parse_number(self.reader.element_text()?)?);
+                }
+                XmlEvent::Start { .. } => self.reader.skip_element()?,
+                XmlEvent::End { .. } => break,
+            }
+        }
+        Ok(ProfileSample::new(
+            Time::from_seconds(time.ok_or(UddfError::MissingValue)?),
+            Depth::from_meters(depth.ok_or(UddfError::MissingValue)?),
+            self.gas,
+        ))
+    }
+}
+
+impl Iterator for UddfSamples<'_> {
+    type Item = Result<ProfileSample, UddfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_sample();
+        fuse(&mut self.done, next)
+    }
+}
+
+/// UDDF document with a single dive plan (planned profile) from deco runtime starting at given dive time
+pub fn write_uddf_plan<W: fmt::Write, S: DecoStageBuffer>(
+    out: &mut W,
+    runtime: &DecoRuntime<S>,
+    start_time: Time,
+) -> Result<(), UddfError> {
+    let stages = runtime.stages();
+    let mut mixes = UddfMixList::default();
+    for stage in stages {
+        mixes.add(stage.gas)?;
+    }
+
+    write_document_start(out, &mixes)?;
+    match stages.first() {
+        Some(first_stage) => {
+            let start_mix = mixes.position(&first_stage.gas)?;
+            writeln!(
+                out,
+                "<informationbeforedive><plannedprofile startmix=\"mix{}\">",
+                start_mix
+            )?;
+            let mut time = start_time;
+            write_waypoint_start(out, first_stage.start_depth, time)?;
+            writeln!(out, "</waypoint>")?;
+            let mut gas = first_stage.gas;
+            for stage in stages {
+                time += stage.duration;
+                write_waypoint_start(out, stage.end_depth, time)?;
+                if stage.gas != gas {
+                    gas = stage.gas;
+                    writeln!(out, "<switchmix ref=\"mix{}\"/>", mixes.position(&gas)?)?;
+                }
+                writeln!(out, "</waypoint>")?;
+            }
+            writeln!(out, "</plannedprofile></informationbeforedive>")?;
+        }
+        None => writeln!(
+            out,
+            "<informationbeforedive><plannedprofile/></informationbeforedive>"
+        )?,
+    }
+    write_document_end(out)
+}
+
+/// UDDF document with a single dive profile from replayed samples and calculated model outputs
+pub fn write_uddf_profile<W: fmt::Write, T>(
+    out: &mut W,
+    points: &[ReplayPoint<T>],
+) -> Result<(), UddfError> {
+    let mut mixes = UddfMixList::default();
+    for point in points {
+        mixes.add(point.gas)?;
+    }
+
+    write_document_start(out, &mixes)?;
+    writeln!(out, "<samples>")?;
+    let mut gas = None;
+    for point in points {
+        write_waypoint_start(out, point.depth, point.time)?;
+        if gas != Some(point.gas) {
+            gas = Some(point.gas);
+            writeln!(
+                out,
+                "<switchmix ref=\"mix{}\"/>",
+                mixes.position(&point.gas)?
+            )?;
+        }
+        // ppO2 in pascal, CNS in percent, NDL in seconds (if replayed)
+        writeln!(
+            out,
+            "<calculatedpo2>{}</calculatedpo2>",
+            point.pp_o2 * 100_000.
+        )?;
+        writeln!(out, "<cns>{}</cns>", point.cns)?;
+        writeln!(out, "<otu>{}</otu>", point.otu)?;
+        if let Some(ndl) = point.ndl {
+            writeln!(out, "<nodecotime>{}</nodecotime>", ndl.as_seconds())?;
+        }
+        writeln!(out, "</waypoint>")?;
+    }
+    writeln!(out, "</samples>")?;
+    write_document_end(out)
+}
+
+// distinct gases of exported dive, "mix{position}" ids
+#[derive(Default)]
+struct UddfMixList {
+    gases: [Option<Gas>; MAX_GASSES],
+}
+
+impl UddfMixList {
+    fn add(&mut self, gas: Gas) -> Result<(), UddfError> {
+        for slot in self.gases.iter_mut() {
+            match slot {
+                Some(existing) if *existing == gas => return Ok(()),
+                Some(_) => (),
+                None => {
+                    *slot = Some(gas);
+                    return Ok(());
+                }
+            }
+        }
+        Err(UddfError::TooManyMixes)
+    }
+
+    fn position(&self, gas: &Gas) -> Result<usize, UddfError> {
+        self.gases
+            .iter()
+            .position(|existing| existing.as_ref() == Some(gas))
+            .map(|position| position + 1)
+            .ok_or(UddfError::UnknownMix)
+    }
+}
+
+fn write_document_start<W: fmt::Write>(out: &mut W, mixes: &UddfMixList) -> fmt::Result {
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+    writeln!(out, "<uddf version=\"{}\">", UDDF_VERSION)?;
+    writeln!(
+        out,
+        "<generator><name>dive-deco</name><version>{}</version></generator>",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(out, "<gasdefinitions>")?;
+    for (i, gas) in mixes.gases.iter().flatten().enumerate() {
+        let (o2, he) = gas.id();
+        writeln!(
+            out,
+            "<mix id=\"mix{}\"><name>{}</name><o2>{}</o2><n2>{}</n2><he>{}</he></mix>",
+            i + 1,
+            gas.display(GasNotation::Ean),
+            o2,
+            1. - o2 - he,
+            he
+        )?;
+    }
+    writeln!(out, "</gasdefinitions>")?;
+    writeln!(
+        out,
+        "<profiledata><repetitiongroup id=\"rg1\"><dive id=\"dive1\">"
+    )
+}
+
+fn write_document_end<W: fmt::Write>(out: &mut W) -> Result<(), UddfError> {
+    writeln!(out, "</dive></repetitiongroup></profiledata>")?;
+    writeln!(out, "</uddf>")?;
+    Ok(())
+}
+
+fn write_waypoint_start<W: fmt::Write>(out: &mut W, depth: Depth, time: Time) -> fmt::Result {
+    writeln!(
+        out,
+        "<waypoint><depth>{}</depth><divetime>{}</divetime>",
+        depth.as_meters(),
+        time.as_seconds()
+    )
+}
+
+fn parse_number(text: &str) -> Result<Float, UddfError> {
+    text.trim()
+        .parse::<Float>()
+        .map_err(|_| UddfError::InvalidValue)
+}
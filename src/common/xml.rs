@@ -0,0 +1,267 @@
+// minimal zero-copy XML pull reader (elements, attributes and text) for dive log formats.
+// Comments, processing instructions and DOCTYPE are skipped, entities aren't expanded.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum XmlEvent<'a> {
+    Start {
+        name: &'a str,
+        attrs: &'a str,
+        empty: bool,
+    },
+    End {
+        name: &'a str,
+    },
+    Text(&'a str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct XmlSyntaxError;
+
+#[derive(Clone, Debug)]
+pub(crate) struct XmlReader<'a> {
+    xml: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlReader<'a> {
+    pub fn new(xml: &'a str) -> Self {
+        Self { xml, pos: 0 }
+    }
+
+    /// byte offset of the next event
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// skip to the end of element which start tag was just read
+    pub fn skip_element(&mut self) -> Result<(), XmlSyntaxError> {
+        let mut depth = 1;
+        for event in self.by_ref() {
+            match event? {
+                XmlEvent::Start { empty: false, .. } => depth += 1,
+                XmlEvent::End { .. } => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => (),
+            }
+        }
+        Err(XmlSyntaxError)
+    }
+
+    /// text content of element which start tag was just read (first text node)
+    pub fn element_text(&mut self) -> Result<&'a str, XmlSyntaxError> {
+        let mut text = "";
+        let mut depth = 1;
+        for event in self.by_ref() {
+            match event? {
+                XmlEvent::Start { empty: false, .. } => depth += 1,
+                XmlEvent::Text(t) if depth == 1 && text.is_empty() => text = t,
+                XmlEvent::End { .. } => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(text);
+                    }
+                }
+                _ => (),
+            }
+        }
+        Err(XmlSyntaxError)
+    }
+
+    fn skip_past(&mut self, terminator: &str) -> Result<&'a str, XmlSyntaxError> {
+        let rest = &self.xml[self.pos..];
+        let end = rest.find(terminator).ok_or(XmlSyntaxError)?;
+        self.pos += end + terminator.len();
+        Ok(&rest[..end])
+    }
+
+    fn read_tag(&mut self) -> Result<XmlEvent<'a>, XmlSyntaxError> {
+        let rest = &self.xml[self.pos..];
+        // tag end outside of quoted attribute values
+        let mut quote = None;
+        let mut tag_end = None;
+        for (i, c) in rest.char_indices().skip(1) {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), _) if c == q => quote = None,
+                (None, '>') => {
+                    tag_end = Some(i);
+                    break;
+                }
+                _ => (),
+            }
+        }
+        let tag_end = tag_end.ok_or(XmlSyntaxError)?;
+        self.pos += tag_end + 1;
+
+        let tag = &rest[1..tag_end];
+        if let Some(name) = tag.strip_prefix('/') {
+            return Ok(XmlEvent::End { name: name.trim() });
+        }
+        let (tag, empty) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag, false),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        if name_end == 0 {
+            return Err(XmlSyntaxError);
+        }
+        Ok(XmlEvent::Start {
+            name: &tag[..name_end],
+            attrs: tag[name_end..].trim(),
+            empty,
+        })
+    }
+}
+
+impl<'a> Iterator for XmlReader<'a> {
+    type Item = Result<XmlEvent<'a>, XmlSyntaxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = &self.xml[self.pos..];
+            if rest.is_empty() {
+                return None;
+            }
+            if !rest.starts_with('<') {
+                let text_end = rest.find('<').unwrap_or(rest.len());
+                self.pos += text_end;
+                let text = rest[..text_end].trim();
+                if text.is_empty() {
+                    continue;
+                }
+                return Some(Ok(XmlEvent::Text(text)));
+            }
+
+            let skipped = if rest.starts_with("<!--") {
+                self.skip_past("-->")
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                return Some(self.skip_past("]]>").map(XmlEvent::Text));
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")
+            } else {
+                let tag = self.read_tag();
+                if tag.is_err() {
+                    self.pos = self.xml.len();
+                }
+                return Some(tag);
+            };
+            if let Err(e) = skipped {
+                // don't loop on malformed input
+                self.pos = self.xml.len();
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// attribute value by name from raw start tag attributes
+pub(crate) fn attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attrs;
+    while let Some(eq) = rest.find('=') {
+        let attr_name = rest[..eq].trim();
+        let value_part = rest[eq + 1..].trim_start();
+        let quote = value_part.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value_end = value_part[1..].find(quote)?;
+        let value = &value_part[1..value_end + 1];
+        if attr_name == name {
+            return Some(value);
+        }
+        rest = &value_part[value_end + 2..];
+    }
+    None
+}
+
+/// iterator item from fallible step, done after last item or first error
+pub(crate) fn fuse<T, E>(done: &mut bool, next: Result<Option<T>, E>) -> Option<Result<T, E>> {
+    match next {
+        Ok(Some(item)) => Some(Ok(item)),
+        Ok(None) => {
+            *done = true;
+            None
+        }
+        Err(e) => {
+            *done = true;
+            Some(Err(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let xml = r#"<?xml version="1.0"?>
+            <!-- comment -->
+            <root a="1 > 0" b='x'>
+                <item/>
+                <value>12.5</value>
+            </root>"#;
+        let events: [XmlEvent; 6] = [
+            XmlEvent::Start {
+                name: "root",
+                attrs: r#"a="1 > 0" b='x'"#,
+                empty: false,
+            },
+            XmlEvent::Start {
+                name: "item",
+                attrs: "",
+                empty: true,
+            },
+            XmlEvent::Start {
+                name: "value",
+                attrs: "",
+                empty: false,
+            },
+            XmlEvent::Text("12.5"),
+            XmlEvent::End { name: "value" },
+            XmlEvent::End { name: "root" },
+        ];
+        let mut reader = XmlReader::new(xml);
+        for expected in events {
+            assert_eq!(reader.next(), Some(Ok(expected)));
+        }
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn test_attr() {
+        let attrs = r#"id="mix1" name = 'EAN 32' empty="""#;
+        assert_eq!(attr(attrs, "id"), Some("mix1"));
+        assert_eq!(attr(attrs, "name"), Some("EAN 32"));
+        assert_eq!(attr(attrs, "empty"), Some(""));
+        assert_eq!(attr(attrs, "missing"), None);
+    }
+
+    #[test]
+    fn test_element_text_and_skip() {
+        let xml = "<a><b><c>1</c></b><d>text</d></a>";
+        let mut reader = XmlReader::new(xml);
+        reader.next(); // <a>
+        reader.next(); // <b>
+        assert_eq!(reader.skip_element(), Ok(()));
+        reader.next(); // <d>
+        assert_eq!(reader.element_text(), Ok("text"));
+        assert_eq!(reader.next(), Some(Ok(XmlEvent::End { name: "a" })));
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let mut reader = XmlReader::new("<a attr=\"unterminated>");
+        assert_eq!(reader.next(), Some(Err(XmlSyntaxError)));
+        let mut reader = XmlReader::new("<!-- unterminated");
+        assert_eq!(reader.next(), Some(Err(XmlSyntaxError)));
+        assert_eq!(reader.next(), None);
+    }
+}
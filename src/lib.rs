@@ -1,11 +1,44 @@
 #![no_std]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+// float precision agnostic comparison (f32 / f64 / fixed-point)
+#[cfg(test)]
+macro_rules! assert_close_to_abs {
+    ($a:expr, $b:expr, $tolerance:expr) => {
+        if ($a - $b).abs() > $tolerance {
+            panic!(
+                "{} is not close to {} with tolerance of {}",
+                $a, $b, $tolerance
+            );
+        }
+    };
+}
+
 mod buehlmann;
 mod common;
 
-pub use buehlmann::{BuehlmannConfig, BuehlmannModel, Compartment, Supersaturation};
+pub use buehlmann::{
+    BuehlmannCheckpoint, BuehlmannConfig, BuehlmannModel, Compartment, CompartmentLoad,
+    CompartmentSupersaturation, DiveSummary, GasUse, HistoryRecorder, LoggedAlarm, MValueLine,
+    RestPeriod, SatAscentRate, Saturation, SaturationConfig, SaturationError, SaturationRuntime,
+    SummaryRecorder, Supersaturation, TissueHistory, TissueSnapshot, DEFAULT_HISTORY_CAPACITY,
+    MAX_SAT_DECO_STAGE, MAX_SUMMARY_ALARMS,
+};
 
 pub use common::{
-    CeilingType, Deco, DecoCalculationError, DecoModel, DecoRuntime, DecoStage, DecoStageType,
-    Depth, DepthType, DiveState, Gas, GradientFactors, NDLType, Pressure, RecordData, Sim, Time,
-    Unit, Units,
+    write_uddf_plan, write_uddf_profile, Alarm, AlarmConfig, AlarmEvent, AlarmEvents, AlarmKind,
+    AlarmMonitor, CeilingType, ChamberSchedule, ChamberStage, ChamberStageType,
+    ConfigValidationErr, ConfigValidationErrorField, ConfigValidationErrorReason, Deco,
+    DecoCalculationError, DecoModel, DecoRuntime, DecoStage, DecoStageBuffer, DecoStageType,
+    DecoTableRow, DecoTracker, DecoTrackerConfig, Depth, DepthRange, DepthType, DiveCounters,
+    DiveLifecycle, DiveLifecycleConfig, DivePhase, DiveState, DiveTables, Float, Gas, GasDensity,
+    GasDisplay, GasNotation, GasParseError, GasSuitability, GasUseLimits, GradientFactors,
+    LifecycleEvent, NDLType, NdlTableRow, Pressure, ProfileReplay, ProfileSample, RecordData,
+    RepetitiveSamples, ReplayError, ReplayPoint, SafetyStop, SafetyStopConfig, SafetyStopExit,
+    SafetyStopState, SampleDepth, Sim, SubsurfaceDive, SubsurfaceDives, SubsurfaceError,
+    SubsurfaceLog, SubsurfaceSamples, SubsurfaceTrip, SubsurfaceTrips, SurfaceDeco,
+    SurfaceDecoConfig, SurfaceDecoRuntime, TableError, TableFormat, Time, UddfDive, UddfDives,
+    UddfDocument, UddfError, UddfMix, UddfMixes, UddfSamples, Unit, Units, MAX_DECO_STAGE,
+    MAX_GASSES,
 };
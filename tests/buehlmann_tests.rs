@@ -1,15 +1,14 @@
+#![allow(clippy::excessive_precision)]
 use dive_deco::{
-    BuehlmannConfig, BuehlmannModel, CeilingType, DecoModel, Depth, Gas, Supersaturation, Time,
+    BuehlmannConfig, BuehlmannModel, CeilingType, ConfigValidationErrorField, DecoCalculationError,
+    DecoModel, DecoStage, Depth, Float, Gas, NDLType, Supersaturation, Time,
 };
 pub mod fixtures;
 
-const MAX_GASSES: usize = 16;
-fn build_gasses(gas: Gas) -> [Gas; MAX_GASSES] {
-    let mut gasses = [Gas::default(); MAX_GASSES];
-    gasses[0] = gas;
-    gasses
-}
+// f32 / f64 / fixed-point results agreement
+const PRECISION_TOLERANCE_PERCENT: Float = 0.0001;
 
+const MAX_GASSES: usize = 16;
 fn build_2gasses(gas: Gas, gas2: Gas) -> [Gas; MAX_GASSES] {
     let mut gasses = [Gas::default(); MAX_GASSES];
     gasses[0] = gas;
@@ -17,14 +16,6 @@ fn build_2gasses(gas: Gas, gas2: Gas) -> [Gas; MAX_GASSES] {
     gasses
 }
 
-fn build_3gasses(gas: Gas, gas2: Gas, gas3: Gas) -> [Gas; MAX_GASSES] {
-    let mut gasses = [Gas::default(); MAX_GASSES];
-    gasses[0] = gas;
-    gasses[1] = gas2;
-    gasses[2] = gas3;
-    gasses
-}
-
 // general high-level model tests
 #[test]
 #[should_panic]
@@ -37,6 +28,56 @@ fn test_should_panic_on_invalid_depth() {
     );
 }
 
+#[test]
+fn test_try_new_invalid_config() {
+    let config = BuehlmannConfig::default().with_gradient_factors(90, 30);
+    let err = BuehlmannModel::try_new(config).unwrap_err();
+    assert_eq!(err.field, ConfigValidationErrorField::GradientFactors);
+}
+
+#[test]
+fn test_try_record_invalid_input() {
+    let mut model = fixtures::model_default();
+    let air = fixtures::gas_air();
+    assert_eq!(
+        model.try_record(Depth::from_meters(-10.), Time::from_seconds(1.), &air),
+        Err(DecoCalculationError::InvalidDepth)
+    );
+    assert_eq!(
+        model.try_record_travel_with_rate(Depth::from_meters(20.), 0., &air),
+        Err(DecoCalculationError::InvalidTravelRate)
+    );
+    for invalid_time in [-1., Float::NAN, Float::INFINITY] {
+        assert_eq!(
+            model.try_record(
+                Depth::from_meters(10.),
+                Time::from_seconds(invalid_time),
+                &air
+            ),
+            Err(DecoCalculationError::InvalidTime)
+        );
+        assert_eq!(
+            model.try_record_travel(
+                Depth::from_meters(10.),
+                Time::from_seconds(invalid_time),
+                &air
+            ),
+            Err(DecoCalculationError::InvalidTime)
+        );
+    }
+    assert_eq!(
+        model.try_record(
+            Depth::from_meters(10.),
+            Time::from_seconds(1.),
+            &Gas::default()
+        ),
+        Err(DecoCalculationError::InvalidGas)
+    );
+    // model state untouched by rejected records
+    assert_eq!(model.dive_state().depth, Depth::zero());
+    assert_eq!(model.dive_state().time, Time::zero());
+}
+
 #[test]
 fn test_ceiling() {
     let mut model = fixtures::model_default();
@@ -57,22 +98,71 @@ fn test_gfs() {
     let air = Gas::new(0.21, 0.);
 
     model.record(Depth::from_meters(50.), Time::from_minutes(20.), &air);
-    assert_eq!(
-        model.supersaturation(),
-        Supersaturation {
-            gf_99: 0.,
-            gf_surf: 193.8554997961134
-        }
-    );
+    let Supersaturation { gf_99, gf_surf } = model.supersaturation();
+    assert_eq!(gf_99, 0.);
+    assert_close_to_percent!(gf_surf, 193.8555, PRECISION_TOLERANCE_PERCENT);
 
     model.record(Depth::from_meters(40.), Time::from_minutes(10.), &air);
-    assert_eq!(
-        model.supersaturation(),
-        Supersaturation {
-            gf_99: 0.,
-            gf_surf: 208.00431699178796
-        }
-    );
+    let Supersaturation { gf_99, gf_surf } = model.supersaturation();
+    assert_eq!(gf_99, 0.);
+    assert_close_to_percent!(gf_surf, 208.0043, PRECISION_TOLERANCE_PERCENT);
+}
+
+#[test]
+fn test_compartments_supersaturation() {
+    let mut model = fixtures::model_gf((30, 70));
+    let air = Gas::new(0.21, 0.);
+    model.record(Depth::from_meters(40.), Time::from_minutes(30.), &air);
+    model.record(Depth::from_meters(9.), Time::zero(), &air);
+
+    let compartments = model.compartments_supersaturation();
+    let Supersaturation { gf_99, gf_surf } = model.supersaturation();
+    let max_gf_99 = compartments
+        .iter()
+        .fold(0., |acc: Float, comp| acc.max(comp.gf_99));
+    let max_gf_surf = compartments
+        .iter()
+        .fold(0., |acc: Float, comp| acc.max(comp.gf_surf));
+    assert_eq!(max_gf_99, gf_99);
+    assert_eq!(max_gf_surf, gf_surf);
+
+    for (i, comp) in compartments.iter().enumerate() {
+        let tissue = model.compartments[i];
+        assert_eq!(comp.no, tissue.no);
+        assert_eq!(comp.total_ip, tissue.total_ip());
+        assert_eq!(comp.m_value_raw, tissue.m_value_raw());
+        assert_eq!(comp.m_value_calc, tissue.m_value_calc());
+        assert_close_to_abs!(comp.ambient_pressure, 1.013 + 0.9, 1e-5);
+        // GF99 as loading fraction between ambient pressure and raw M-value
+        assert_close_to_abs!(
+            comp.gf_99,
+            (comp.total_ip - comp.ambient_pressure) / (comp.m_value_raw - comp.ambient_pressure)
+                * 100.,
+            1e-3
+        );
+    }
+}
+
+#[test]
+fn test_m_value_lines() {
+    let mut model = fixtures::model_gf((30, 70));
+    let air = Gas::new(0.21, 0.);
+    model.record(Depth::from_meters(40.), Time::from_minutes(30.), &air);
+
+    // GF low below first stop, GF high at the surface
+    assert_eq!(model.gradient_factor_at(Depth::from_meters(40.)), 30);
+    assert_eq!(model.gradient_factor_at(Depth::zero()), 70);
+
+    let surface_pressure = model.config().surface_pressure;
+    for (i, (raw_line, gf_line)) in model.m_value_lines(Depth::zero()).iter().enumerate() {
+        let tissue = model.compartments[i];
+        assert_close_to_abs!(
+            raw_line.m_value_at_depth(Depth::from_meters(40.), surface_pressure),
+            tissue.m_value_raw(),
+            1e-5
+        );
+        assert!(gf_line.m_value(1.) < raw_line.m_value(1.));
+    }
 }
 
 #[test]
@@ -126,13 +216,13 @@ fn test_actual_ndl_calculation() {
     let air = Gas::new(0.21, 0.);
     let depth = Depth::from_meters(30.);
 
-    // with 21/00 at 30m expect NDL 16
+    // with 21/00 at 30m expect NDL 16:32
     model.record(depth, Time::zero(), &air);
-    assert_eq!(model.ndl(), Time::from_minutes(16.));
+    assert_eq!(model.ndl(), Time::from_seconds(992.));
 
-    // expect NDL 15 after 1 min
+    // expect NDL 15:32 after 1 min
     model.record(depth, Time::from_minutes(1.), &air);
-    assert_eq!(model.ndl(), Time::from_minutes(15.));
+    assert_eq!(model.ndl(), Time::from_seconds(932.));
 }
 
 #[test]
@@ -143,13 +233,37 @@ fn test_adaptive_ndl_calculation() {
     let air = Gas::new(0.21, 0.);
     let depth = Depth::from_meters(30.);
 
-    // with 21/00 at 30m expect NDL 19
+    // with 21/00 at 30m expect NDL 19:12
     model.record(depth, Time::zero(), &air);
-    assert_eq!(model.ndl(), Time::from_minutes(19.));
+    assert_eq!(model.ndl(), Time::from_seconds(1152.));
 
-    // expect NDL 18 after 1 min
+    // expect NDL 18:12 after 1 min
     model.record(depth, Time::from_minutes(1.), &air);
-    assert_eq!(model.ndl(), Time::from_minutes(18.));
+    assert_eq!(model.ndl(), Time::from_seconds(1092.));
+}
+
+#[test]
+fn test_ndl_type_independent_of_ceiling_type() {
+    let air = Gas::new(0.21, 0.);
+    let depth = Depth::from_meters(30.);
+    let ndl_by_config = |config: BuehlmannConfig| {
+        let mut model = BuehlmannModel::new(config);
+        model.record(depth, Time::zero(), &air);
+        model.ndl()
+    };
+
+    let by_ceiling = BuehlmannConfig::default().with_ndl_type(NDLType::ByCeiling);
+    let actual = BuehlmannConfig::default().with_ndl_type(NDLType::Actual);
+    for ceiling_type in [CeilingType::Actual, CeilingType::Adaptive] {
+        assert_eq!(
+            ndl_by_config(by_ceiling.with_ceiling_type(ceiling_type)),
+            Time::from_seconds(992.)
+        );
+        assert_eq!(
+            ndl_by_config(actual.with_ceiling_type(ceiling_type)),
+            Time::from_seconds(1152.)
+        );
+    }
 }
 
 #[test]
@@ -164,6 +278,21 @@ fn test_ndl_cut_off() {
     assert_eq!(model.ndl(), Time::from_minutes(99.));
 }
 
+#[test]
+fn test_configurable_ndl_cut_off() {
+    let air = Gas::new(0.21, 0.);
+    let config = BuehlmannConfig::default().with_ndl_cut_off(Time::from_minutes(10.));
+    let mut model = BuehlmannModel::new(config);
+
+    // cut-off below actual NDL
+    model.record(Depth::from_meters(30.), Time::zero(), &air);
+    assert_eq!(model.ndl(), Time::from_minutes(10.));
+
+    // NDL within cut-off
+    model.record(Depth::from_meters(30.), Time::from_minutes(10.), &air);
+    assert_eq!(model.ndl(), Time::from_seconds(392.));
+}
+
 #[test]
 fn test_multi_gas_ndl() {
     let mut model =
@@ -172,13 +301,13 @@ fn test_multi_gas_ndl() {
     let ean_28 = Gas::new(0.28, 0.);
 
     model.record(Depth::from_meters(30.), Time::zero(), &air);
-    assert_eq!(model.ndl(), Time::from_minutes(16.));
+    assert_eq!(model.ndl(), Time::from_seconds(992.));
 
     model.record(Depth::from_meters(30.), Time::from_minutes(10.), &air);
-    assert_eq!(model.ndl(), Time::from_minutes(6.));
+    assert_eq!(model.ndl(), Time::from_seconds(392.));
 
     model.record(Depth::from_meters(30.), Time::zero(), &ean_28);
-    assert_eq!(model.ndl(), Time::from_minutes(10.));
+    assert_eq!(model.ndl(), Time::from_seconds(631.));
 }
 
 #[test]
@@ -186,7 +315,7 @@ fn test_ndl_with_gf() {
     let mut model = fixtures::model_gf((70, 70));
     let air = Gas::new(0.21, 0.);
     model.record(Depth::from_meters(20.), Time::zero(), &air);
-    assert_eq!(model.ndl(), Time::from_minutes(21.));
+    assert_eq!(model.ndl(), Time::from_seconds(1310.));
 }
 
 #[test]
@@ -195,7 +324,7 @@ fn test_altitude() {
     let air = Gas::new(0.21, 0.);
     model.record(Depth::from_meters(40.), Time::from_minutes(60.), &air);
     let Supersaturation { gf_surf, .. } = model.supersaturation();
-    assert_eq!(gf_surf, 299.023204474694);
+    assert_close_to_percent!(gf_surf, 299.0232, PRECISION_TOLERANCE_PERCENT);
 }
 
 #[test]
@@ -210,7 +339,11 @@ fn test_example_ceiling_start() {
 
     // instant drop to 40m on air for 10min
     model.record(Depth::from_meters(40.), Time::from_minutes(10.), &air);
-    assert_eq!(model.ceiling().as_meters(), 12.85312294790554);
+    assert_close_to_percent!(
+        model.ceiling().as_meters(),
+        12.853123,
+        PRECISION_TOLERANCE_PERCENT
+    );
 }
 
 #[test]
@@ -227,7 +360,11 @@ fn test_example_ceiling() {
     model.record(Depth::from_meters(40.), Time::from_minutes(40.), &air);
     model.record(Depth::from_meters(30.), Time::from_minutes(3.), &air);
     model.record(Depth::from_meters(21.), Time::from_minutes(10.), &ean_50);
-    assert_eq!(model.ceiling().as_meters(), 12.455491216740299);
+    assert_close_to_percent!(
+        model.ceiling().as_meters(),
+        12.455491,
+        PRECISION_TOLERANCE_PERCENT
+    );
 }
 
 #[test]
@@ -244,8 +381,16 @@ fn test_example_ceiling_feet() {
     model.record(Depth::from_feet(131.234), Time::from_minutes(40.), &air);
     model.record(Depth::from_feet(98.4252), Time::from_minutes(3.), &air);
     model.record(Depth::from_feet(68.8976), Time::from_minutes(10.), &ean_50);
-    assert_eq!(model.ceiling().as_feet(), 40.864609154666);
-    assert_eq!(model.ceiling().as_meters(), 12.455532471765158);
+    assert_close_to_percent!(
+        model.ceiling().as_feet(),
+        40.864609,
+        PRECISION_TOLERANCE_PERCENT
+    );
+    assert_close_to_percent!(
+        model.ceiling().as_meters(),
+        12.455533,
+        PRECISION_TOLERANCE_PERCENT
+    );
 }
 
 #[test]
@@ -275,7 +420,10 @@ fn test_gradual_ascent_with_deco() {
             break;
         }
         model.record_travel_with_rate(depth - Depth::from_meters(3.), 10., &air);
-        model.deco(build_2gasses(air, ean_50)).unwrap();
+        // more stages than default MAX_DECO_STAGE capacity
+        model
+            .deco_with_buffer::<[DecoStage; 32]>(&build_2gasses(air, ean_50))
+            .unwrap();
     }
 }
 
@@ -13,7 +13,7 @@ fn test_cns() {
 
     let cns = model.cns();
 
-    assert_close_to_abs!(cns as f32, 12., 1.);
+    assert_close_to_abs!(cns, 12., 1.);
 }
 
 #[test]
@@ -28,3 +28,34 @@ fn test_cns_multi_stage() {
     let cns = model.cns();
     assert_close_to_abs!(cns, 26., 1.);
 }
+
+#[test]
+fn test_checkpoint_restore() {
+    let mut model = fixtures::model_gf((30, 70));
+    let air = Gas::air();
+    model.record(Depth::from_meters(40.), Time::from_minutes(25.), &air);
+    let checkpoint = model.checkpoint();
+    let pre_segment_model = model.clone();
+
+    // try segment and roll back
+    model.record(
+        Depth::from_meters(40.),
+        Time::from_minutes(15.),
+        &Gas::new(0.32, 0.),
+    );
+    model.record_travel_with_rate(Depth::from_meters(6.), 9., &air);
+    assert_ne!(model.ceiling(), pre_segment_model.ceiling());
+    model.restore(&checkpoint);
+
+    assert_eq!(model.compartments, pre_segment_model.compartments);
+    assert_eq!(model.dive_state().time, pre_segment_model.dive_state().time);
+    assert_eq!(model.dive_state().depth, Depth::from_meters(40.));
+    assert_eq!(model.dive_state().gas, air);
+    assert_eq!(model.cns(), pre_segment_model.cns());
+    assert_eq!(model.ceiling(), pre_segment_model.ceiling());
+    assert_eq!(model.ndl(), pre_segment_model.ndl());
+    assert_eq!(
+        model.deco([air; 16]).unwrap(),
+        pre_segment_model.deco([air; 16]).unwrap()
+    );
+}
@@ -1,6 +1,6 @@
 use dive_deco::{
-    BuehlmannConfig, BuehlmannModel, CeilingType, DecoModel, DecoRuntime, DecoStage, DecoStageType,
-    Depth, Gas, Time,
+    BuehlmannConfig, BuehlmannModel, CeilingType, DecoCalculationError, DecoModel, DecoRuntime,
+    DecoStage, DecoStageType, Depth, Gas, SafetyStopConfig, SafetyStopState, Time, MAX_DECO_STAGE,
 };
 
 pub mod fixtures;
@@ -11,10 +11,9 @@ fn test_deco_ascent_no_deco() {
     let mut model = fixtures::model_default();
     model.record(Depth::from_meters(20.), Time::from_minutes(5.), &air);
 
-    let DecoRuntime {
-        deco_stages, tts, ..
-    } = model.deco(build_gasses(air)).unwrap();
-    assert_eq!(deco_stages.len(), 1); // single continuous ascent
+    let deco_runtime = model.deco(build_gasses(air)).unwrap();
+    let DecoRuntime { tts, .. } = deco_runtime;
+    assert_eq!(deco_runtime.stages().len(), 1); // single continuous ascent
     assert_eq!(tts, Time::from_minutes(2.)); // tts in minutes
 }
 
@@ -24,12 +23,11 @@ fn test_deco_single_gas() {
     let mut model = BuehlmannModel::new(BuehlmannConfig::default().with_deco_ascent_rate(9.));
     model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
 
-    let DecoRuntime {
-        deco_stages, tts, ..
-    } = model.deco(build_gasses(air)).unwrap();
+    let deco_runtime = model.deco(build_gasses(air)).unwrap();
+    let DecoRuntime { tts, .. } = deco_runtime;
 
     assert_eq!(tts, Time::from_seconds(754.));
-    assert_eq!(deco_stages.len(), 5);
+    assert_eq!(deco_runtime.stages().len(), 5);
 
     let expected_deco_stages = vec![
         DecoStage {
@@ -39,6 +37,7 @@ fn test_deco_single_gas() {
             duration: Time::from_seconds(226.),
             gas: air,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::DecoStop,
@@ -47,6 +46,7 @@ fn test_deco_single_gas() {
             duration: Time::from_seconds(88.),
             gas: air,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::Ascent,
@@ -55,6 +55,7 @@ fn test_deco_single_gas() {
             duration: Time::from_seconds(20.),
             gas: air,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::DecoStop,
@@ -63,6 +64,7 @@ fn test_deco_single_gas() {
             duration: Time::from_seconds(400.),
             gas: air,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::Ascent,
@@ -71,10 +73,11 @@ fn test_deco_single_gas() {
             duration: Time::from_seconds(20.),
             gas: air,
             valid: true,
+            ..DecoStage::default()
         },
     ];
 
-    assert_deco_stages_eq(deco_stages.to_vec(), expected_deco_stages);
+    assert_deco_stages_eq(deco_runtime.stages().to_vec(), expected_deco_stages);
 }
 
 const MAX_GASSES: usize = 16;
@@ -109,9 +112,8 @@ fn test_deco_multi_gas() {
 
     model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
 
-    let DecoRuntime {
-        deco_stages, tts, ..
-    } = model.deco(build_2gasses(air, ean_50)).unwrap();
+    let deco_runtime = model.deco(build_2gasses(air, ean_50)).unwrap();
+    let DecoRuntime { tts, .. } = deco_runtime;
 
     let expected_deco_stages = vec![
         DecoStage {
@@ -121,6 +123,7 @@ fn test_deco_multi_gas() {
             duration: Time::from_seconds(120.),
             gas: air,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::GasSwitch,
@@ -129,6 +132,7 @@ fn test_deco_multi_gas() {
             duration: Time::zero(),
             gas: ean_50,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::Ascent,
@@ -137,6 +141,7 @@ fn test_deco_multi_gas() {
             duration: Time::from_seconds(106.),
             gas: ean_50,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::DecoStop,
@@ -145,6 +150,7 @@ fn test_deco_multi_gas() {
             duration: Time::from_seconds(34.),
             gas: ean_50,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::Ascent,
@@ -153,6 +159,7 @@ fn test_deco_multi_gas() {
             duration: Time::from_seconds(20.),
             gas: ean_50,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::DecoStop,
@@ -161,6 +168,7 @@ fn test_deco_multi_gas() {
             duration: Time::from_seconds(291.),
             gas: ean_50,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::Ascent,
@@ -169,10 +177,11 @@ fn test_deco_multi_gas() {
             duration: Time::from_seconds(20.),
             gas: ean_50,
             valid: true,
+            ..DecoStage::default()
         },
     ];
 
-    assert_deco_stages_eq(deco_stages.to_vec(), expected_deco_stages);
+    assert_deco_stages_eq(deco_runtime.stages().to_vec(), expected_deco_stages);
     assert_eq!(tts, Time::from_seconds(591.));
 }
 
@@ -184,9 +193,8 @@ fn test_deco_with_deco_mod_at_bottom() {
 
     model.record(Depth::from_meters(30.), Time::from_minutes(30.), &air);
 
-    let DecoRuntime {
-        deco_stages, tts, ..
-    } = model.deco(build_2gasses(air, ean_36)).unwrap();
+    let deco_runtime = model.deco(build_2gasses(air, ean_36)).unwrap();
+    let DecoRuntime { tts, .. } = deco_runtime;
 
     let expected_deco_stages = vec![
         DecoStage {
@@ -196,6 +204,7 @@ fn test_deco_with_deco_mod_at_bottom() {
             duration: Time::zero(),
             gas: ean_36,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::Ascent,
@@ -204,6 +213,7 @@ fn test_deco_with_deco_mod_at_bottom() {
             duration: Time::from_seconds(180.),
             gas: ean_36,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::DecoStop,
@@ -212,6 +222,7 @@ fn test_deco_with_deco_mod_at_bottom() {
             duration: Time::from_seconds(268.),
             gas: ean_36,
             valid: true,
+            ..DecoStage::default()
         },
         DecoStage {
             stage_type: DecoStageType::Ascent,
@@ -220,9 +231,10 @@ fn test_deco_with_deco_mod_at_bottom() {
             duration: Time::from_seconds(20.),
             gas: ean_36,
             valid: true,
+            ..DecoStage::default()
         },
     ];
-    assert_deco_stages_eq(deco_stages.to_vec(), expected_deco_stages);
+    assert_deco_stages_eq(deco_runtime.stages().to_vec(), expected_deco_stages);
     assert_eq!(tts, Time::from_seconds(468.));
 }
 
@@ -233,7 +245,7 @@ fn test_tts_delta() {
     let ean_50 = Gas::new(0.5, 0.);
     let gas_mixes = build_2gasses(air, ean_50);
     model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
-    let deco_1 = model.deco(gas_mixes.clone()).unwrap();
+    let deco_1 = model.deco(gas_mixes).unwrap();
     model.record(Depth::from_meters(40.), Time::from_minutes(5.), &air);
     let deco_2 = model.deco(gas_mixes).unwrap();
     assert_eq!(deco_1.tts_at_5, deco_2.tts);
@@ -259,18 +271,18 @@ fn test_runtime_on_missed_stop() {
         let mut model = BuehlmannModel::new(config);
         model.record(Depth::from_meters(40.), Time::from_minutes(30.), &air);
         model.record(Depth::from_meters(22.), Time::zero(), &air);
-        let initial_deco = model.deco(available_gas_mixes.clone()).unwrap();
+        let initial_deco = model.deco(available_gas_mixes).unwrap();
         // 21
         let initial_deco_stop_depth = get_first_deco_stop_depth(initial_deco);
 
         // between stop and ceiling (18 - 21)
         model.record(Depth::from_meters(20.), Time::zero(), &air);
-        let between_deco = model.deco(available_gas_mixes.clone()).unwrap();
+        let between_deco = model.deco(available_gas_mixes).unwrap();
         let between_deco_stop_depth = get_first_deco_stop_depth(between_deco);
 
         // below
         model.record(Depth::from_meters(15.), Time::zero(), &air);
-        let below_deco = model.deco(available_gas_mixes.clone()).unwrap();
+        let below_deco = model.deco(available_gas_mixes).unwrap();
         let below_deco_stop_depth = get_first_deco_stop_depth(below_deco);
 
         assert_eq!(
@@ -296,7 +308,7 @@ fn test_deco_runtime_integrity() {
     model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
 
     let deco_runtime = model.deco(build_3gasses(air, ean_50, oxygen)).unwrap();
-    let deco_stages = deco_runtime.deco_stages;
+    let deco_stages = deco_runtime.stages();
 
     deco_stages.iter().reduce(|a, b| {
         // validate depth order
@@ -317,10 +329,136 @@ fn test_deco_runtime_integrity() {
     });
 }
 
+#[test]
+fn test_deco_gas_density_exceeded() {
+    let air = Gas::air();
+    let gas_mixes = build_gasses(air);
+
+    let mut model = fixtures::model_gf((30, 70));
+    model.record(Depth::from_meters(45.), Time::from_minutes(20.), &air);
+    let deco_runtime = model.deco(gas_mixes).unwrap();
+    assert!(
+        !deco_runtime.gas_density_exceeded,
+        "no max density configured"
+    );
+
+    let mut model = BuehlmannModel::new(
+        BuehlmannConfig::new()
+            .with_gradient_factors(30, 70)
+            .with_max_gas_density(Some(5.2)),
+    );
+    model.record(Depth::from_meters(45.), Time::from_minutes(20.), &air);
+    assert!(model.gas_density_exceeded());
+    let deco_runtime = model.deco(gas_mixes).unwrap();
+    assert!(
+        deco_runtime.gas_density_exceeded,
+        "air at 45m above 5.2 g/L"
+    );
+}
+
+#[test]
+fn test_deco_gas_switch_within_density_limit() {
+    let air = Gas::air();
+    let ean_50 = Gas::new(0.50, 0.);
+    let max_gas_density = 3.;
+
+    let mut model = BuehlmannModel::new(
+        BuehlmannConfig::new()
+            .with_gradient_factors(30, 70)
+            .with_max_gas_density(Some(max_gas_density)),
+    );
+    model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+    let deco_runtime = model.deco(build_2gasses(air, ean_50)).unwrap();
+
+    let gas_switch = deco_runtime
+        .stages()
+        .iter()
+        .find(|stage| stage.stage_type == DecoStageType::GasSwitch)
+        .unwrap();
+    assert_eq!(gas_switch.gas, ean_50);
+    assert!(ean_50.density(gas_switch.start_depth, 1013) <= max_gas_density);
+    assert!(gas_switch.start_depth < Depth::from_meters(22.));
+}
+
+#[test]
+fn test_deco_gas_switch_within_deco_max_ppo2() {
+    let air = Gas::air();
+    let ean_50 = Gas::new(0.50, 0.);
+
+    let mut model = BuehlmannModel::new(
+        BuehlmannConfig::new()
+            .with_gradient_factors(30, 70)
+            .with_deco_max_ppo2(1.4),
+    );
+    model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+    let deco_runtime = model.deco(build_2gasses(air, ean_50)).unwrap();
+
+    let gas_switch = deco_runtime
+        .stages()
+        .iter()
+        .find(|stage| stage.stage_type == DecoStageType::GasSwitch)
+        .unwrap();
+    assert_eq!(gas_switch.gas, ean_50);
+    assert_eq!(gas_switch.start_depth, Depth::from_meters(18.));
+}
+
+#[test]
+fn test_deco_stages_buffer_capacity() {
+    let air = Gas::air();
+    let ean_50 = Gas::new(0.50, 0.);
+    let mut model = fixtures::model_gf((30, 70));
+    model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+
+    let deco_runtime = model.deco(build_2gasses(air, ean_50)).unwrap();
+    let stages_count = deco_runtime.stages().len();
+
+    // slice of gas mixes, stages buffer with custom capacity
+    let deco_runtime_custom = model
+        .deco_with_buffer::<[DecoStage; 32]>(&[air, ean_50])
+        .unwrap();
+    assert_eq!(deco_runtime_custom.stages(), deco_runtime.stages());
+    assert_eq!(deco_runtime_custom.tts, deco_runtime.tts);
+
+    // overflow reported explicitly
+    let overflow_res = model.deco_with_buffer::<[DecoStage; 8]>(&[air, ean_50]);
+    assert!(stages_count > 8);
+    assert_eq!(overflow_res, Err(DecoCalculationError::StageBufferOverflow));
+
+    // longer deco exceeding default capacity
+    model.record(Depth::from_meters(45.), Time::from_minutes(10.), &air);
+    assert_eq!(
+        model.deco(build_2gasses(air, ean_50)),
+        Err(DecoCalculationError::StageBufferOverflow)
+    );
+    let deco_runtime_custom = model
+        .deco_with_buffer::<[DecoStage; 32]>(&[air, ean_50])
+        .unwrap();
+    assert!(deco_runtime_custom.stages().len() > MAX_DECO_STAGE);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_deco_vec() {
+    let air = Gas::air();
+    let ean_50 = Gas::new(0.50, 0.);
+    let oxygen = Gas::new(1., 0.);
+    let mut model = fixtures::model_gf((30, 70));
+    model.record(Depth::from_meters(60.), Time::from_minutes(40.), &air);
+
+    // growable stages list beyond default MAX_DECO_STAGE capacity
+    let deco_runtime = model
+        .deco_with_buffer::<[DecoStage; 64]>(&[air, ean_50, oxygen])
+        .unwrap();
+    assert!(deco_runtime.stages().len() > MAX_DECO_STAGE);
+    let deco_runtime_vec = model.deco_vec(&[air, ean_50, oxygen]).unwrap();
+    assert_eq!(deco_runtime_vec.deco_stages, deco_runtime.stages().to_vec());
+    assert_eq!(deco_runtime_vec.tts, deco_runtime.tts);
+}
+
 fn get_first_deco_stop_depth(deco: DecoRuntime) -> Option<Depth> {
     let first_stop = deco
-        .deco_stages
-        .into_iter()
+        .stages()
+        .iter()
         .find(|stage| stage.stage_type == DecoStageType::DecoStop);
     if let Some(stop) = first_stop {
         return Some(stop.start_depth);
@@ -338,3 +476,138 @@ fn assert_deco_stages_eq(deco_stages: Vec<DecoStage>, expected_deco_stages: Vec<
         assert_eq!(deco_stages[i].gas, expected_stage.gas);
     }
 }
+
+#[test]
+fn test_deco_planned_safety_stop() {
+    let air = fixtures::gas_air();
+    let safety_stop = SafetyStopConfig::new().with_planned(true);
+    let mut model = BuehlmannModel::new(BuehlmannConfig::default().with_safety_stop(safety_stop));
+    model.record(Depth::from_meters(20.), Time::from_minutes(5.), &air);
+    assert_eq!(
+        model.safety_stop(),
+        SafetyStopState::Pending {
+            remaining: Time::from_minutes(3.)
+        }
+    );
+
+    let deco_runtime = model.deco(build_gasses(air)).unwrap();
+    let stages = deco_runtime.stages();
+    assert_eq!(stages.len(), 3);
+    assert_eq!(stages[0].stage_type, DecoStageType::Ascent);
+    assert_eq!(stages[0].end_depth, Depth::from_meters(5.));
+    assert_eq!(stages[1].stage_type, DecoStageType::SafetyStop);
+    assert_eq!(stages[1].start_depth, Depth::from_meters(5.));
+    assert_eq!(stages[2].end_depth, Depth::zero());
+    assert!(!deco_runtime.is_deco());
+
+    // countdown started during ascent through the stop band (6m -> 5m, band edge within 1s step)
+    assert_close_to_abs!(stages[1].duration.as_seconds(), 174., 1.);
+    assert_eq!(
+        deco_runtime.tts,
+        Time::from_seconds(120.) + stages[1].duration
+    );
+
+    // NDL unaffected by planned safety stop
+    let mut model_without_stop = fixtures::model_default();
+    model_without_stop.record(Depth::from_meters(20.), Time::from_minutes(5.), &air);
+    assert_eq!(model.ndl(), model_without_stop.ndl());
+}
+
+#[test]
+fn test_deco_safety_stop_not_planned() {
+    let air = fixtures::gas_air();
+
+    // not planned by default
+    let mut model = fixtures::model_default();
+    model.record(Depth::from_meters(20.), Time::from_minutes(5.), &air);
+    assert!(model.safety_stop().remaining().is_some());
+    assert_eq!(model.deco(build_gasses(air)).unwrap().stages().len(), 1);
+
+    // not triggered
+    let safety_stop = SafetyStopConfig::new().with_planned(true);
+    let mut model = BuehlmannModel::new(BuehlmannConfig::default().with_safety_stop(safety_stop));
+    model.record(Depth::from_meters(8.), Time::from_minutes(20.), &air);
+    assert_eq!(model.safety_stop(), SafetyStopState::Inactive);
+    assert_eq!(model.deco(build_gasses(air)).unwrap().stages().len(), 1);
+
+    // deco ascent without safety stop
+    model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+    let deco_runtime = model.deco(build_gasses(air)).unwrap();
+    assert!(deco_runtime.is_deco());
+    assert!(deco_runtime
+        .stages()
+        .iter()
+        .all(|stage| stage.stage_type != DecoStageType::SafetyStop));
+}
+
+#[test]
+fn test_safety_stop_countdown_by_records() {
+    let air = fixtures::gas_air();
+    let mut model = fixtures::model_default();
+    model.record(Depth::from_meters(18.), Time::from_minutes(20.), &air);
+    model.record_travel_with_rate(Depth::from_meters(5.), 10., &air);
+    model.record(Depth::from_meters(5.), Time::from_minutes(2.), &air);
+    assert!(matches!(
+        model.safety_stop(),
+        SafetyStopState::InProgress { .. }
+    ));
+    model.record(Depth::from_meters(5.), Time::from_minutes(1.), &air);
+    assert_eq!(model.safety_stop(), SafetyStopState::Completed);
+}
+
+#[test]
+fn test_deco_stage_details() {
+    let mut model = fixtures::model_gf((30, 70));
+    let air = Gas::air();
+    let ean_50 = Gas::new(0.5, 0.);
+    model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+
+    let deco_runtime = model.deco(build_2gasses(air, ean_50)).unwrap();
+    let stages = deco_runtime.stages();
+
+    // dive runtime
+    let dive_time = model.dive_state().time;
+    let mut runtime = dive_time;
+    for stage in stages {
+        runtime += stage.duration;
+        assert_eq!(stage.runtime, runtime);
+    }
+    assert_eq!(stages.last().unwrap().runtime, dive_time + deco_runtime.tts);
+
+    // gas at stage depth
+    let first_stop = stages
+        .iter()
+        .find(|stage| stage.stage_type == DecoStageType::DecoStop)
+        .unwrap();
+    assert_eq!(first_stop.gas, ean_50);
+    let expected_pp_o2 = 0.5 * (1.013 + first_stop.end_depth.as_meters() / 10.);
+    assert_close_to_abs!(first_stop.pp_o2, expected_pp_o2, 1e-4);
+    assert_eq!(first_stop.end, first_stop.end_depth);
+
+    // GF along the slope, increasing towards the surface
+    let stops_gf: Vec<u8> = stages
+        .iter()
+        .filter(|stage| stage.stage_type == DecoStageType::DecoStop)
+        .map(|stage| stage.gf)
+        .collect();
+    assert!(stops_gf.windows(2).all(|gfs| gfs[0] <= gfs[1]));
+    assert!(stops_gf.iter().all(|gf| (30..70).contains(gf)));
+
+    // oxygen toxicity matches replayed profile
+    let mut replay_model = model.clone();
+    for stage in stages {
+        match stage.stage_type {
+            DecoStageType::Ascent => replay_model.record_travel_with_rate(
+                stage.end_depth,
+                model.config().deco_ascent_rate,
+                &stage.gas,
+            ),
+            _ => replay_model.record(stage.end_depth, stage.duration, &stage.gas),
+        }
+        assert_close_to_abs!(stage.cns, replay_model.cns(), 1e-3);
+        assert_close_to_abs!(stage.otu, replay_model.otu(), 1e-3);
+    }
+    assert!(deco_runtime.cns > model.cns());
+    assert_eq!(deco_runtime.cns, stages.last().unwrap().cns);
+    assert_eq!(deco_runtime.otu, stages.last().unwrap().otu);
+}
@@ -0,0 +1,141 @@
+use dive_deco::{DecoModel, Depth, Gas, HistoryRecorder, Time};
+pub mod fixtures;
+
+#[test]
+fn test_snapshots_interval() {
+    let air = fixtures::gas_air();
+    let mut recorder: HistoryRecorder = HistoryRecorder::new(fixtures::model_default());
+    recorder
+        .record(Depth::from_meters(30.), Time::from_minutes(10.), &air)
+        .unwrap();
+    recorder
+        .record(Depth::from_meters(30.), Time::from_seconds(30.), &air)
+        .unwrap();
+
+    // initial snapshot and one per minute
+    let history = recorder.history();
+    assert_eq!(history.len(), 11);
+    for (i, snapshot) in history.iter().enumerate() {
+        assert_eq!(snapshot.time, Time::from_minutes(i as _));
+    }
+    assert_eq!(history.first().unwrap().depth, Depth::zero());
+    assert_eq!(history.last().unwrap().depth, Depth::from_meters(30.));
+}
+
+#[test]
+fn test_recorded_model_matches_plain_records() {
+    let air = fixtures::gas_air();
+    let ean50 = Gas::new(0.5, 0.);
+    let mut model = fixtures::model_default();
+    let mut recorder: HistoryRecorder =
+        HistoryRecorder::new(fixtures::model_default()).with_interval(Time::from_seconds(45.));
+
+    model.record_travel_with_rate(Depth::from_meters(40.), 20., &air);
+    model.record(Depth::from_meters(40.), Time::from_minutes(25.), &air);
+    model.record_travel(Depth::from_meters(21.), Time::from_seconds(110.), &air);
+    model.record(Depth::from_meters(21.), Time::from_minutes(3.), &ean50);
+    recorder
+        .record_travel_with_rate(Depth::from_meters(40.), 20., &air)
+        .unwrap();
+    recorder
+        .record(Depth::from_meters(40.), Time::from_minutes(25.), &air)
+        .unwrap();
+    recorder
+        .record_travel(Depth::from_meters(21.), Time::from_seconds(110.), &air)
+        .unwrap();
+    recorder
+        .record(Depth::from_meters(21.), Time::from_minutes(3.), &ean50)
+        .unwrap();
+
+    let recorded_model = recorder.model();
+    assert_eq!(recorded_model.dive_state().time, model.dive_state().time);
+    assert_eq!(recorded_model.dive_state().depth, model.dive_state().depth);
+    for (recorded, plain) in recorded_model.compartments.iter().zip(model.compartments) {
+        assert_close_to_abs!(recorded.n2_ip(), plain.n2_ip(), 1e-4);
+        assert_close_to_abs!(recorded.he_ip(), plain.he_ip(), 1e-4);
+    }
+
+    // snapshots of model state at snapshot times
+    let last_snapshot = recorder.history().last().unwrap();
+    assert_eq!(last_snapshot.time, Time::from_seconds(1890.));
+    assert!(last_snapshot.ceiling > Depth::zero());
+    assert!(last_snapshot.supersaturation.gf_surf > 100.);
+    assert_eq!(
+        last_snapshot.he_ip(),
+        recorded_model.compartments.map(|comp| comp.he_ip())
+    );
+    assert_eq!(recorder.history().iter().count(), 1 + 1890 / 45);
+
+    // model and history handed over after recording
+    let (mut inner_model, history) = recorder.into_inner();
+    assert_eq!(history.len(), 1 + 1890 / 45);
+    inner_model.record(Depth::from_meters(21.), Time::from_minutes(1.), &ean50);
+    assert_eq!(
+        inner_model.dive_state().time,
+        model.dive_state().time + Time::from_minutes(1.)
+    );
+}
+
+#[test]
+fn test_bounded_history() {
+    let air = fixtures::gas_air();
+    let mut recorder: HistoryRecorder<10> = HistoryRecorder::new(fixtures::model_default());
+    recorder
+        .record(Depth::from_meters(20.), Time::from_minutes(30.), &air)
+        .unwrap();
+
+    // oldest snapshots overwritten
+    let history = recorder.history();
+    assert_eq!(history.len(), 10);
+    assert_eq!(history.first().unwrap().time, Time::from_minutes(21.));
+    assert_eq!(history.last().unwrap().time, Time::from_minutes(30.));
+    let last_snapshot = history.last().unwrap();
+    assert_eq!(
+        last_snapshot.n2_ip()[0],
+        recorder.model().compartments[0].n2_ip()
+    );
+}
+
+#[test]
+fn test_rewind() {
+    let air = fixtures::gas_air();
+    let mut recorder: HistoryRecorder = HistoryRecorder::new(fixtures::model_default());
+    recorder
+        .record(Depth::from_meters(30.), Time::from_minutes(10.), &air)
+        .unwrap();
+    let model_at_10 = recorder.model().clone();
+    recorder
+        .record(Depth::from_meters(30.), Time::from_minutes(10.), &air)
+        .unwrap();
+    recorder
+        .record_travel_with_rate(Depth::from_meters(5.), 9., &air)
+        .unwrap();
+
+    // rewind to latest snapshot at or before given time
+    assert_eq!(
+        recorder.rewind(Time::from_seconds(630.)),
+        Some(Time::from_minutes(10.))
+    );
+    let model = recorder.model();
+    assert_eq!(model.compartments, model_at_10.compartments);
+    assert_eq!(model.dive_state().time, Time::from_minutes(10.));
+    assert_eq!(model.dive_state().depth, Depth::from_meters(30.));
+    assert_eq!(recorder.history().len(), 11);
+
+    // recording continues from rewound state
+    recorder
+        .record(Depth::from_meters(30.), Time::from_minutes(1.), &air)
+        .unwrap();
+    assert_eq!(
+        recorder.history().last().unwrap().time,
+        Time::from_minutes(11.)
+    );
+
+    // rewind before the oldest snapshot
+    let mut bounded: HistoryRecorder<2> = HistoryRecorder::new(fixtures::model_default());
+    bounded
+        .record(Depth::from_meters(10.), Time::from_minutes(5.), &air)
+        .unwrap();
+    assert_eq!(bounded.rewind(Time::from_minutes(2.)), None);
+    assert_eq!(bounded.model().dive_state().time, Time::from_minutes(5.));
+}
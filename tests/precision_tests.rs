@@ -0,0 +1,93 @@
+use dive_deco::{BuehlmannConfig, BuehlmannModel, DecoModel, DecoStage, Depth, Float, Gas, Time};
+pub mod fixtures;
+
+// end-to-end agreement of f32 / fixed-point numeric cores with the f64 path
+const CEILING_TOLERANCE_METERS: Float = 0.001;
+const OX_TOX_TOLERANCE: Float = 0.001;
+const TIME_TOLERANCE_SECONDS: Float = 60.;
+
+// model, bottom gas, depth, bottom time, deco gases, f64 reference (ceiling, TTS, CNS, OTU)
+type DecoCase<'a> = (
+    BuehlmannModel,
+    Gas,
+    Float,
+    usize,
+    &'a [Gas],
+    (Float, Float, Float, Float),
+);
+
+// bottom segment recorded in 1s samples
+fn bottom_segment(
+    mut model: BuehlmannModel,
+    gas: Gas,
+    depth: Float,
+    minutes: usize,
+) -> BuehlmannModel {
+    for _ in 0..minutes * 60 {
+        model.record(Depth::from_meters(depth), Time::from_seconds(1.), &gas);
+    }
+    model
+}
+
+#[test]
+fn test_deco_precision() {
+    let test_cases: [DecoCase; 3] = [
+        (
+            fixtures::model_default(),
+            Gas::air(),
+            40.,
+            20,
+            &[Gas::air()],
+            (5.191727, 729., 7.239499, 21.310274),
+        ),
+        (
+            fixtures::model_gf((30, 70)),
+            Gas::trimix(21, 35),
+            60.,
+            25,
+            &[Gas::trimix(21, 35), Gas::ean(50), Gas::oxygen()],
+            (30.848916, 3573., 18.920602, 43.037808),
+        ),
+        (
+            BuehlmannModel::new(BuehlmannConfig::new().with_gradient_factors(50, 80)),
+            Gas::air(),
+            30.,
+            40,
+            &[Gas::air(), Gas::ean(50)],
+            (10.266521, 1475., 9.447481, 28.262228),
+        ),
+    ];
+
+    for (model, gas, depth, minutes, deco_gases, expected) in test_cases {
+        let (ceiling, tts, cns, otu) = expected;
+        let model = bottom_segment(model, gas, depth, minutes);
+        let runtime = model
+            .deco_with_buffer::<[DecoStage; 32]>(deco_gases)
+            .unwrap();
+
+        assert_close_to_abs!(
+            model.ceiling().as_meters(),
+            ceiling,
+            CEILING_TOLERANCE_METERS
+        );
+        assert_close_to_abs!(runtime.tts.as_seconds(), tts, TIME_TOLERANCE_SECONDS);
+        assert_close_to_abs!(model.cns(), cns, OX_TOX_TOLERANCE);
+        assert_close_to_abs!(model.otu(), otu, OX_TOX_TOLERANCE);
+    }
+}
+
+#[test]
+fn test_ndl_precision() {
+    // gas, depth, f64 reference NDL (seconds) after 5 minutes at depth
+    let test_cases = [
+        (Gas::air(), 20., 2471.),
+        (Gas::ean(32), 30., 1348.),
+        (Gas::air(), 40., 250.),
+    ];
+
+    for (gas, depth, ndl) in test_cases {
+        let mut model = fixtures::model_default();
+        model.record(Depth::from_meters(depth), Time::from_minutes(5.), &gas);
+        assert_close_to_abs!(model.ndl().as_seconds(), ndl, TIME_TOLERANCE_SECONDS);
+    }
+}
@@ -0,0 +1,241 @@
+use dive_deco::{
+    BuehlmannModel, DecoCalculationError, DecoModel, Depth, DepthType, Float, Gas, ProfileReplay,
+    ProfileSample, ReplayError, SampleDepth, Sim, Time, MAX_GASSES,
+};
+pub mod fixtures;
+
+fn sample(minutes: Float, meters: DepthType, gas: Gas) -> ProfileSample {
+    ProfileSample::new(Time::from_minutes(minutes), Depth::from_meters(meters), gas)
+}
+
+#[test]
+fn test_replay_matches_model_records() {
+    let air = fixtures::gas_air();
+    let samples = [
+        sample(2., 30., air),
+        sample(20., 30., air),
+        sample(23., 6., air),
+    ];
+
+    let mut replay = ProfileReplay::new(fixtures::model_gf((30, 70)))
+        .with_ndl(true)
+        .with_tts(true);
+    let points: Vec<_> = replay.replay(&samples).collect::<Result<_, _>>().unwrap();
+    assert_eq!(points.len(), 3);
+
+    let mut model = fixtures::model_gf((30, 70));
+    model.record_travel(Depth::from_meters(30.), Time::from_minutes(2.), &air);
+    model.record_travel(Depth::from_meters(30.), Time::from_minutes(18.), &air);
+    model.record_travel(Depth::from_meters(6.), Time::from_minutes(3.), &air);
+
+    let last_point = points[2];
+    assert_eq!(last_point.time, Time::from_minutes(23.));
+    assert_eq!(last_point.depth, Depth::from_meters(6.));
+    assert_eq!(last_point.ceiling, model.ceiling());
+    assert_eq!(last_point.ndl, Some(model.ndl()));
+    assert_eq!(
+        last_point.tts,
+        Some(model.deco([air; MAX_GASSES]).unwrap().tts)
+    );
+    assert_eq!(last_point.tts_at_5, None);
+    assert_eq!(last_point.cns, model.cns());
+    assert_eq!(last_point.otu, model.otu());
+    assert_eq!(last_point.details, ());
+}
+
+#[test]
+fn test_replay_time_series() {
+    let air = fixtures::gas_air();
+    let samples = [
+        sample(1., 20., air),
+        sample(15., 20., air),
+        sample(60., 20., air),
+    ];
+    let mut replay = ProfileReplay::new(fixtures::model_default())
+        .with_ndl(true)
+        .with_tts(true);
+    let points: Vec<_> = replay.replay(&samples).map(Result::unwrap).collect();
+
+    assert!(points[0].ndl > points[1].ndl);
+    assert_eq!(points[1].tts, points[0].tts);
+    assert!(points[2].ceiling > Depth::zero());
+    assert!(points[2].tts > points[1].tts);
+    assert!(points[2].cns > points[1].cns);
+    assert!(points[2].otu > points[1].otu);
+}
+
+#[test]
+fn test_irregular_fractional_intervals() {
+    let air = fixtures::gas_air();
+    let samples = [
+        ProfileSample::new(Time::from_seconds(2.5), Depth::from_meters(1.), air),
+        ProfileSample::new(Time::from_seconds(9.75), Depth::from_meters(4.), air),
+        ProfileSample::new(Time::from_seconds(30.), Depth::from_meters(10.), air),
+    ];
+    let mut replay = ProfileReplay::new(fixtures::model_default());
+    for sample in &samples {
+        let point = replay.replay_sample(sample).unwrap();
+        assert_eq!(point.time, sample.time);
+        assert_close_to_abs!(
+            replay.model().dive_state().time.as_seconds(),
+            sample.time.as_seconds(),
+            0.0001
+        );
+    }
+}
+
+#[test]
+fn test_pressure_samples() {
+    let air = fixtures::gas_air();
+    // 1013 mbar surface pressure
+    let pressure_sample = ProfileSample::from_pressure(Time::from_minutes(1.), 4.013, air);
+    assert_eq!(pressure_sample.depth, SampleDepth::Pressure(4.013));
+    assert_close_to_abs!(pressure_sample.depth(1013).as_meters(), 30., 0.0001);
+
+    // below surface pressure clamped to surface
+    let surface_sample = ProfileSample::from_pressure(Time::from_minutes(1.), 0.9, air);
+    assert_eq!(surface_sample.depth(1013), Depth::zero());
+
+    let mut replay = ProfileReplay::new(fixtures::model_default());
+    let point = replay.replay_sample(&pressure_sample).unwrap();
+    assert_close_to_abs!(point.depth.as_meters(), 30., 0.0001);
+    assert_close_to_abs!(point.pp_o2, 0.21 * 4.013, 0.0001);
+}
+
+#[test]
+fn test_gas_switch() {
+    let air = fixtures::gas_air();
+    let ean_50 = Gas::new(0.5, 0.);
+    let samples = [
+        sample(2., 40., air),
+        sample(25., 40., air),
+        sample(28., 21., ean_50),
+    ];
+    let mut replay = ProfileReplay::new(fixtures::model_default())
+        .with_tts(true)
+        .with_gas_mixes(&[ean_50])
+        .unwrap();
+    let points: Vec<_> = replay.replay(&samples).map(Result::unwrap).collect();
+
+    // ascent breathed previous sample's gas, switched at sample
+    let mut model = fixtures::model_default();
+    model.record_travel(Depth::from_meters(40.), Time::from_minutes(2.), &air);
+    model.record_travel(Depth::from_meters(40.), Time::from_minutes(23.), &air);
+    model.record_travel(Depth::from_meters(21.), Time::from_minutes(3.), &air);
+    model.record(Depth::from_meters(21.), Time::zero(), &ean_50);
+
+    assert_eq!(points[2].gas, ean_50);
+    assert_eq!(points[2].ceiling, model.ceiling());
+    assert_close_to_abs!(points[2].pp_o2, 0.5 * 3.113, 0.0001);
+
+    // deco gas used for TTS before switch
+    let mut gasses = [air; MAX_GASSES];
+    gasses[1] = ean_50;
+    let mut model = fixtures::model_default();
+    model.record_travel(Depth::from_meters(40.), Time::from_minutes(2.), &air);
+    assert_eq!(points[0].tts, Some(model.deco(gasses).unwrap().tts));
+}
+
+#[test]
+fn test_non_monotonic_time() {
+    let air = fixtures::gas_air();
+    let samples = [
+        sample(2., 20., air),
+        sample(1., 20., air),
+        sample(3., 20., air),
+    ];
+    let mut replay = ProfileReplay::new(fixtures::model_default());
+    let points: Vec<_> = replay.replay(&samples).collect();
+    assert_eq!(points.len(), 2);
+    assert!(points[0].is_ok());
+    assert_eq!(points[1], Err(ReplayError::NonMonotonicTime));
+}
+
+#[test]
+fn test_replay_details() {
+    let air = fixtures::gas_air();
+    let mut replay =
+        ProfileReplay::new(fixtures::model_default()).with_details(BuehlmannModel::tissues);
+    let point = replay.replay_sample(&sample(10., 30., air)).unwrap();
+    assert_eq!(point.details, replay.model().tissues());
+
+    let mut replay = ProfileReplay::new(fixtures::model_default())
+        .with_details(|model: &BuehlmannModel| model.supersaturation());
+    let point = replay.replay_sample(&sample(10., 30., air)).unwrap();
+    assert_eq!(point.details, replay.model().supersaturation());
+}
+
+#[test]
+fn test_outputs_opt_in() {
+    let air = fixtures::gas_air();
+    let mut replay = ProfileReplay::new(fixtures::model_default());
+    let point = replay.replay_sample(&sample(20., 40., air)).unwrap();
+    assert_eq!(point.ndl, None);
+    assert_eq!(point.tts, None);
+    assert_eq!(point.tts_at_5, None);
+    assert_eq!(point.ceiling, replay.model().ceiling());
+}
+
+#[test]
+fn test_replay_generic_model() {
+    // any simulation capable deco model
+    fn replay_last<T: DecoModel + Clone + Sim>(model: T, samples: &[ProfileSample]) -> Time {
+        let mut replay = ProfileReplay::new(model).with_tts(true);
+        let points: Vec<_> = replay.replay(samples).map(Result::unwrap).collect();
+        points.last().unwrap().tts.unwrap()
+    }
+    let air = fixtures::gas_air();
+    let samples = [sample(2., 40., air), sample(20., 40., air)];
+    let mut model = fixtures::model_default();
+    model.record_travel(Depth::from_meters(40.), Time::from_minutes(2.), &air);
+    model.record_travel(Depth::from_meters(40.), Time::from_minutes(18.), &air);
+    assert_eq!(
+        replay_last(fixtures::model_default(), &samples),
+        model.deco([air; MAX_GASSES]).unwrap().tts
+    );
+}
+
+#[test]
+fn test_replay_tts_at_5() {
+    let air = fixtures::gas_air();
+    let mut replay = ProfileReplay::new(fixtures::model_default())
+        .with_tts(true)
+        .with_tts_at_5(true);
+    let point = replay.replay_sample(&sample(20., 40., air)).unwrap();
+    let runtime = replay.model().deco([air; MAX_GASSES]).unwrap();
+    assert_eq!(point.tts, Some(runtime.tts));
+    assert_eq!(point.tts_at_5, Some(runtime.tts_at_5));
+}
+
+#[test]
+fn test_gas_mixes_overflow() {
+    let gas_mixes: Vec<Gas> = (0..MAX_GASSES as u8).map(|i| Gas::ean(22 + i)).collect();
+    let res = ProfileReplay::new(fixtures::model_default()).with_gas_mixes(&gas_mixes);
+    assert!(res.is_ok());
+    let mut replay = res.unwrap();
+
+    // sampled gas beyond MAX_GASSES, model not advanced
+    let point = replay.replay_sample(&sample(1., 10., fixtures::gas_air()));
+    assert_eq!(
+        point,
+        Err(ReplayError::Calculation(
+            DecoCalculationError::GasListOverflow
+        ))
+    );
+    assert_eq!(replay.model().dive_state().time, Time::zero());
+
+    let gas_mixes: Vec<Gas> = (0..=MAX_GASSES as u8).map(|i| Gas::ean(22 + i)).collect();
+    let res = ProfileReplay::new(fixtures::model_default()).with_gas_mixes(&gas_mixes);
+    assert!(res.is_err());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_replay_vec() {
+    let air = fixtures::gas_air();
+    let samples = [sample(1., 20., air), sample(15., 20., air)];
+    let mut replay = ProfileReplay::new(fixtures::model_default());
+    let points = replay.replay_vec(&samples).unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].time, Time::from_minutes(15.));
+}
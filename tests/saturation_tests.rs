@@ -0,0 +1,151 @@
+use dive_deco::{
+    BuehlmannConfig, DecoModel, DecoStageType, Depth, DepthType, RestPeriod, Saturation,
+    SaturationConfig, SaturationError, Time, MAX_DECO_STAGE, MAX_SAT_DECO_STAGE,
+};
+pub mod fixtures;
+
+fn saturation(storage_meters: DepthType) -> Saturation {
+    let config = SaturationConfig::default().with_storage_depth(Depth::from_meters(storage_meters));
+    Saturation::new(BuehlmannConfig::default(), config)
+}
+
+#[test]
+fn test_storage_depth_equilibrium() {
+    let sat = saturation(30.);
+    let storage_gas = sat.storage_gas();
+    let pp_o2 = storage_gas
+        .partial_pressures(Depth::from_meters(30.), 1013)
+        .o2;
+    assert_close_to_abs!(pp_o2, 0.44, 0.001);
+    assert_eq!(storage_gas.gas_pressures_compound(1.).n2, 0.);
+
+    let inspired = storage_gas.inspired_partial_pressures(Depth::from_meters(30.), 1013);
+    for compartment in sat.model().compartments.iter() {
+        assert_close_to_abs!(compartment.he_ip(), inspired.he, 0.0001);
+    }
+}
+
+#[test]
+fn test_excursion_limits() {
+    let sat = saturation(30.);
+    let (upward_limit, downward_limit) = sat.excursion_limits();
+    assert!(upward_limit > Depth::zero() && upward_limit < Depth::from_meters(30.));
+    assert!(downward_limit > Depth::from_meters(30.));
+    assert_eq!(upward_limit, sat.model().ceiling());
+
+    assert_eq!(sat.check_excursion(Depth::from_meters(30.)), Ok(()));
+    assert_eq!(
+        sat.check_excursion(upward_limit - Depth::from_meters(1.)),
+        Err(SaturationError::UpwardExcursionLimit)
+    );
+    assert_eq!(
+        sat.check_excursion(downward_limit + Depth::from_meters(1.)),
+        Err(SaturationError::DownwardExcursionLimit)
+    );
+}
+
+#[test]
+fn test_excursion_records() {
+    let mut sat = saturation(30.);
+    let (_, downward_limit) = sat.excursion_limits();
+    let gas = sat.storage_gas();
+    assert_eq!(
+        sat.record(
+            downward_limit + Depth::from_meters(1.),
+            Time::from_minutes(60.),
+            &gas
+        ),
+        Err(SaturationError::DownwardExcursionLimit)
+    );
+    let (storage_upward_limit, _) = sat.excursion_limits();
+    sat.record(Depth::from_meters(40.), Time::from_minutes(60.), &gas)
+        .unwrap();
+    assert_eq!(sat.model().dive_state().depth, Depth::from_meters(40.));
+
+    // upward limit follows ceiling after excursion, downward limit from storage equilibrium
+    let (upward_limit, excursion_downward_limit) = sat.excursion_limits();
+    assert_eq!(upward_limit, sat.model().ceiling());
+    assert!(upward_limit > storage_upward_limit);
+    assert_eq!(excursion_downward_limit, downward_limit);
+    assert_eq!(
+        sat.check_excursion(storage_upward_limit),
+        Err(SaturationError::UpwardExcursionLimit)
+    );
+}
+
+#[test]
+fn test_continuous_decompression() {
+    let sat = saturation(30.);
+    let runtime = sat.deco(Time::from_minutes(8. * 60.)).unwrap();
+    let stages = runtime.stages();
+
+    // continuous ascent from storage depth to the surface, rest periods as holds
+    assert_eq!(stages.first().unwrap().start_depth, Depth::from_meters(30.));
+    assert_eq!(stages.last().unwrap().end_depth, Depth::zero());
+    assert_eq!(stages.first().unwrap().stage_type, DecoStageType::Ascent);
+    // first ascent from 08:00 until the 14:00 rest period
+    assert_eq!(stages[0].duration, Time::from_minutes(6. * 60.));
+    assert_eq!(stages[1].stage_type, DecoStageType::DecoStop);
+    assert_eq!(stages[1].duration, Time::from_minutes(2. * 60.));
+    // 1.22 m/h between 30.5 and 15.2 m
+    assert_close_to_abs!(stages[0].end_depth.as_meters(), 30. - 1.22 * 6., 0.01);
+
+    let total = stages
+        .iter()
+        .fold(Time::zero(), |total, stage| total + stage.duration);
+    assert_eq!(runtime.tts, total);
+    assert!(runtime.tts > Time::from_minutes(24. * 60.));
+    assert_close_to_abs!(
+        runtime.end_clock.as_minutes(),
+        (8. * 60. + runtime.tts.as_minutes()) % (24. * 60.),
+        0.01
+    );
+}
+
+#[test]
+fn test_rest_periods_disabled() {
+    let config = SaturationConfig::default()
+        .with_storage_depth(Depth::from_meters(30.))
+        .with_rest_periods([RestPeriod::new(0., 0.), RestPeriod::new(0., 0.)]);
+    let sat = Saturation::new(BuehlmannConfig::default(), config);
+    let runtime = sat.deco(Time::zero()).unwrap();
+    assert!(runtime
+        .stages()
+        .iter()
+        .all(|stage| stage.stage_type == DecoStageType::Ascent));
+    assert_eq!(runtime.stages().len(), 1);
+}
+
+#[test]
+fn test_invalid_config() {
+    let config = SaturationConfig::default().with_pp_o2(0.1, 0.5);
+    assert_eq!(
+        Saturation::try_new(BuehlmannConfig::default(), config).err(),
+        Some(SaturationError::InvalidConfig)
+    );
+    let config = SaturationConfig::default().with_max_o2_fraction(0.1);
+    assert!(Saturation::try_new(BuehlmannConfig::default(), config).is_err());
+}
+
+#[test]
+fn test_deep_storage_stage_buffer() {
+    let sat = saturation(200.);
+    let runtime = sat.deco(Time::from_minutes(8. * 60.)).unwrap();
+    let stages = runtime.stages();
+
+    // an ascent and a hold stage per rest period over ~7.6 days
+    assert_eq!(
+        stages.first().unwrap().start_depth,
+        Depth::from_meters(200.)
+    );
+    assert_eq!(stages.last().unwrap().end_depth, Depth::zero());
+    assert_eq!(stages.len(), 31);
+    assert!(stages.len() > MAX_DECO_STAGE);
+    assert!(stages.len() <= MAX_SAT_DECO_STAGE);
+    // GF set on every closed stage
+    assert!(stages.iter().all(|stage| stage.gf == 100));
+
+    // max storage depth still fits the default buffer
+    let runtime = saturation(700.).deco(Time::from_minutes(8. * 60.)).unwrap();
+    assert_eq!(runtime.stages().last().unwrap().end_depth, Depth::zero());
+}
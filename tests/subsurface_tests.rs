@@ -0,0 +1,219 @@
+use dive_deco::{
+    BuehlmannConfig, BuehlmannModel, DecoModel, Depth, Gas, ProfileReplay, ProfileSample,
+    SampleDepth, SubsurfaceError, SubsurfaceLog, Time,
+};
+pub mod fixtures;
+
+const SUBSURFACE_LOG: &str = r#"<divelog program='subsurface' version='3'>
+<settings>
+  <divecomputerid model='Shearwater Perdix' deviceid='12345678' />
+</settings>
+<divesites>
+  <site uuid='1a2b3c4d' name='Reef'></site>
+</divesites>
+<dives>
+<trip date='2024-06-01' time='09:00:00' location='Red Sea'>
+<dive number='1' date='2024-06-01' time='09:30:00' duration='32:00 min'>
+  <cylinder size='11.1 l' workpressure='207.0 bar' description='AL80' o2='21.0%' />
+  <cylinder size='5.7 l' workpressure='207.0 bar' description='AL40' o2='50.0%' />
+  <divecomputer model='Shearwater Perdix' deviceid='12345678'>
+  <depth max='30.5 m' mean='22.1 m' />
+  <temperature water='24.0 C' />
+  <surface pressure='1.013 bar' />
+  <water salinity='1030 g/l' />
+  <event time='0:00 min' type='25' flags='1' name='gaschange' cylinder='0' />
+  <event time='22:00 min' type='25' flags='2' name='gaschange' cylinder='1' />
+  <sample time='0:10 min' depth='3.0 m' temp='24.0 C' />
+  <sample time='2:00 min' depth='30.5 m' />
+  <sample time='20:00 min' depth='30.0 m' />
+  <sample time='20:10 min' />
+  <sample time='22:00 min' depth='21.0 m' />
+  <sample time='30:00 min' depth='5.0 m' />
+  <sample time='32:00 min' depth='0.0 m' />
+  </divecomputer>
+  <divecomputer model='Backup'>
+  <sample time='0:10 min' depth='99.0 m' />
+  </divecomputer>
+</dive>
+<dive number='2' date='2024-06-01' time='11:30:00' duration='40:00 min'>
+  <cylinder size='11.1 l' o2='32.0%' />
+  <divecomputer model='Shearwater Perdix'>
+  <sample time='2:00 min' depth='18.0 m' />
+  <sample time='38:00 min' depth='18.0 m' />
+  <sample time='40:00 min' depth='0.0 m' />
+  </divecomputer>
+</dive>
+</trip>
+<dive number='3' date='2024-06-03' time='10:00:00' duration='10:00 min'>
+  <divecomputer model='Shearwater Perdix'>
+  <event time='1:00 min' name='gaschange' value='50' />
+  <sample time='0:30 min' depth='10.0 m' />
+  <sample time='1:30 min' depth='10.0 m' />
+  </divecomputer>
+</dive>
+</dives>
+</divelog>"#;
+
+#[test]
+fn test_dive_header() {
+    let log = SubsurfaceLog::parse(SUBSURFACE_LOG).unwrap();
+    let dives: Vec<_> = log.dives().map(Result::unwrap).collect();
+    assert_eq!(dives.len(), 3);
+
+    let dive = dives[0];
+    assert_eq!(dive.number, Some(1));
+    assert_eq!(dive.start, Some(1_717_234_200));
+    assert_eq!(dive.duration, Some(Time::from_minutes(32.)));
+    assert_eq!(dive.surface_pressure, Some(1013));
+    assert_eq!(dive.salinity, Some(1030));
+    assert_eq!(dive.gas_mixes(), &[Gas::air(), Gas::new(0.5, 0.)]);
+
+    // no cylinders nor environment logged
+    assert_eq!(dives[2].gas_mixes(), &[]);
+    assert_eq!(dives[2].salinity, None);
+}
+
+#[test]
+fn test_trips() {
+    let log = SubsurfaceLog::parse(SUBSURFACE_LOG).unwrap();
+    let trips: Vec<_> = log.trips().map(Result::unwrap).collect();
+    assert_eq!(trips.len(), 1);
+    assert_eq!(trips[0].location, Some("Red Sea"));
+    assert_eq!(trips[0].dives().count(), 2);
+}
+
+#[test]
+fn test_dive_samples() {
+    let log = SubsurfaceLog::parse(SUBSURFACE_LOG).unwrap();
+    let dive = log.dives().next().unwrap().unwrap();
+    let samples: Vec<_> = dive.samples().map(Result::unwrap).collect();
+
+    // first dive computer only
+    assert_eq!(samples.len(), 7);
+    assert_eq!(samples[0].time, Time::from_seconds(10.));
+
+    // depth converted to pressure with logged salinity
+    let SampleDepth::Pressure(pressure) = samples[1].depth else {
+        panic!("pressure sample expected");
+    };
+    assert_close_to_abs!(pressure, 1.013 + 30.5 * 1030. * 9.80665 / 100_000., 0.0001);
+
+    // missing depth carried over
+    assert_eq!(samples[3].depth, samples[2].depth);
+
+    // gas change applied at event time
+    assert_eq!(samples[3].gas, Gas::air());
+    assert_eq!(samples[4].gas, Gas::new(0.5, 0.));
+    assert_eq!(samples[6].gas, Gas::new(0.5, 0.));
+}
+
+#[test]
+fn test_gas_change_value_and_depth_samples() {
+    let log = SubsurfaceLog::parse(SUBSURFACE_LOG).unwrap();
+    let dive = log.dives().nth(2).unwrap().unwrap();
+    let samples: Vec<_> = dive.samples().map(Result::unwrap).collect();
+    assert_eq!(
+        samples,
+        vec![
+            ProfileSample::new(Time::from_seconds(30.), Depth::from_meters(10.), Gas::air()),
+            ProfileSample::new(
+                Time::from_seconds(90.),
+                Depth::from_meters(10.),
+                Gas::new(0.5, 0.)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_repetitive_samples() {
+    let log = SubsurfaceLog::parse(SUBSURFACE_LOG).unwrap();
+    let trip = log.trips().next().unwrap().unwrap();
+    let samples: Vec<_> = trip.samples().map(Result::unwrap).collect();
+
+    // surface sample at each dive start and end
+    assert_eq!(samples.len(), 2 + 7 + 2 + 3);
+    assert_eq!(samples[0].time, Time::zero());
+    assert_eq!(samples[8].gas, Gas::air());
+    assert_eq!(samples[9].depth(1013), Depth::zero());
+
+    // second dive offset by dive start difference
+    let second_start = Time::from_minutes(120.);
+    assert_eq!(samples[9].time, second_start);
+    assert_eq!(samples[9].gas, Gas::new(0.32, 0.));
+    assert_eq!(samples[10].time, second_start + Time::from_minutes(2.));
+    assert_eq!(samples[13].time, second_start + Time::from_minutes(40.));
+
+    let times_ordered = samples.windows(2).all(|w| w[0].time <= w[1].time);
+    assert!(times_ordered);
+}
+
+#[test]
+fn test_replay_with_gradient_factors() {
+    let log = SubsurfaceLog::parse(SUBSURFACE_LOG).unwrap();
+    let trip = log.trips().next().unwrap().unwrap();
+    let samples: Vec<_> = trip.samples().map(Result::unwrap).collect();
+
+    let replay_gf = |gf: (u8, u8)| {
+        let config = BuehlmannConfig::default().with_gradient_factors(gf.0, gf.1);
+        let mut replay = ProfileReplay::new(BuehlmannModel::new(config))
+            .with_ndl(true)
+            .with_tts(true);
+        let points: Vec<_> = replay.replay(&samples).map(Result::unwrap).collect();
+        assert_eq!(points.len(), samples.len());
+        points
+    };
+    let conservative = replay_gf((30, 70));
+    let liberal = replay_gf((85, 85));
+
+    // second dive bottom, residual nitrogen from first dive
+    let conservative_point = conservative[11];
+    let liberal_point = liberal[11];
+    assert!(conservative_point.ndl < liberal_point.ndl);
+    assert!(conservative_point.tts >= liberal_point.tts);
+
+    let mut single_dive = ProfileReplay::new(fixtures::model_gf((85, 85))).with_ndl(true);
+    let second_dive = log.dives().nth(1).unwrap().unwrap();
+    let second_dive_samples: Vec<_> = second_dive.samples().map(Result::unwrap).collect();
+    let fresh_points: Vec<_> = single_dive
+        .replay(&second_dive_samples)
+        .map(Result::unwrap)
+        .collect();
+    assert!(liberal_point.ndl < fresh_points[1].ndl);
+}
+
+#[test]
+fn test_invalid_logs() {
+    assert_eq!(
+        SubsurfaceLog::parse("<uddf></uddf>").err(),
+        Some(SubsurfaceError::NotSubsurface)
+    );
+    assert_eq!(
+        SubsurfaceLog::parse("").err(),
+        Some(SubsurfaceError::InvalidXml)
+    );
+
+    let unknown_cylinder = r#"<divelog><dives><dive>
+        <cylinder o2='32.0%' />
+        <divecomputer><event time='1:00 min' name='gaschange' cylinder='3' />
+        <sample time='2:00 min' depth='10.0 m' /></divecomputer>
+        </dive></dives></divelog>"#;
+    let log = SubsurfaceLog::parse(unknown_cylinder).unwrap();
+    let dive = log.dives().next().unwrap().unwrap();
+    let samples: Vec<_> = dive.samples().collect();
+    assert_eq!(samples, vec![Err(SubsurfaceError::UnknownCylinder)]);
+
+    // repetitive sequence requires dive start
+    let log = SubsurfaceLog::parse(unknown_cylinder).unwrap();
+    let samples: Vec<_> = log.samples().collect();
+    assert_eq!(samples, vec![Err(SubsurfaceError::MissingValue)]);
+
+    let invalid_gas = r#"<divelog><dives><dive>
+        <cylinder o2='80.0%' he='40.0%' /><divecomputer></divecomputer>
+        </dive></dives></divelog>"#;
+    let log = SubsurfaceLog::parse(invalid_gas).unwrap();
+    assert_eq!(
+        log.dives().next().unwrap().err(),
+        Some(SubsurfaceError::InvalidGas)
+    );
+}
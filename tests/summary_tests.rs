@@ -0,0 +1,169 @@
+use dive_deco::{
+    Alarm, AlarmConfig, AlarmEvent, AlarmKind, DecoModel, Depth, Float, Gas, SummaryRecorder, Time,
+    MAX_GASSES,
+};
+pub mod fixtures;
+
+#[test]
+fn test_no_deco_dive_summary() {
+    let air = fixtures::gas_air();
+    let mut recorder = SummaryRecorder::new(fixtures::model_default());
+    recorder
+        .record_travel(Depth::from_meters(20.), Time::from_minutes(2.), &air)
+        .unwrap();
+    recorder
+        .record(Depth::from_meters(20.), Time::from_minutes(20.), &air)
+        .unwrap();
+    recorder
+        .record_travel(Depth::zero(), Time::from_minutes(2.), &air)
+        .unwrap();
+
+    let summary = recorder.summary();
+    assert_eq!(summary.max_depth, Depth::from_meters(20.));
+    assert_eq!(summary.runtime, Time::from_minutes(24.));
+    assert_close_to_abs!(
+        summary.avg_depth.as_meters(),
+        (10. * 2. + 20. * 20. + 10. * 2.) / 24.,
+        0.001
+    );
+    assert_eq!(summary.deco_time, Time::zero());
+    assert!(summary.surfaced);
+    assert_eq!(summary.surfacing_ceiling, Depth::zero());
+    assert_eq!(summary.surfacing_cns, recorder.model().cns());
+    assert_eq!(summary.surfacing_otu, recorder.model().otu());
+    assert!(summary.max_supersaturation.gf_surf >= summary.final_supersaturation.gf_surf);
+    assert_eq!(
+        summary.final_supersaturation,
+        recorder.model().supersaturation()
+    );
+    assert_eq!(summary.alarms().count(), 0);
+
+    let gases: Vec<_> = summary.gases().collect();
+    assert_eq!(gases.len(), 1);
+    assert_eq!(gases[0].gas, air);
+    assert_eq!(gases[0].time, Time::from_minutes(24.));
+}
+
+#[test]
+fn test_deco_dive_summary() {
+    let air = fixtures::gas_air();
+    let ean50 = Gas::new(0.5, 0.);
+    let mut recorder = SummaryRecorder::new(fixtures::model_gf((30, 70)))
+        .with_alarm_config(AlarmConfig::default().with_max_cns(1000.));
+    recorder
+        .record(Depth::from_meters(40.), Time::from_minutes(30.), &air)
+        .unwrap();
+    recorder
+        .record_travel_with_rate(Depth::from_meters(21.), 9., &air)
+        .unwrap();
+    recorder
+        .record(Depth::from_meters(21.), Time::from_minutes(1.), &ean50)
+        .unwrap();
+    recorder
+        .record_travel_with_rate(Depth::from_meters(6.), 9., &ean50)
+        .unwrap();
+
+    // not surfaced yet, current values
+    let summary = recorder.summary();
+    assert!(!summary.surfaced);
+    assert_eq!(summary.surfacing_ceiling, recorder.model().ceiling());
+    assert!(summary.deco_time > Time::zero());
+    assert!(summary.max_supersaturation.gf_99 > 0.);
+
+    // missed stops, ceiling at surfacing
+    recorder
+        .record_travel_with_rate(Depth::zero(), 9., &ean50)
+        .unwrap();
+    let summary = recorder.summary();
+    assert!(summary.surfaced);
+    assert!(summary.surfacing_ceiling > Depth::zero());
+
+    let gases: Vec<_> = summary.gases().map(|gas_use| gas_use.gas).collect();
+    assert_eq!(gases, vec![air, ean50]);
+    let total_gas_time = summary
+        .gases()
+        .fold(Time::zero(), |total, gas_use| total + gas_use.time);
+    assert_eq!(total_gas_time, summary.runtime);
+
+    // alarm events with model time
+    let alarms: Vec<_> = summary.alarms().collect();
+    assert!(alarms
+        .iter()
+        .any(|logged| logged.event == AlarmEvent::Raised(Alarm::Deco)
+            && logged.time == Time::from_minutes(30.)));
+    assert!(alarms.iter().any(|logged| matches!(
+        logged.event,
+        AlarmEvent::Raised(alarm) if alarm.kind() == AlarmKind::CeilingBreach
+    )));
+    assert_eq!(summary.alarms_dropped, 0);
+}
+
+#[test]
+fn test_deco_time_within_records() {
+    let air = fixtures::gas_air();
+    let mut recorder = SummaryRecorder::new(fixtures::model_gf((30, 70)));
+    recorder
+        .record(Depth::from_meters(40.), Time::from_minutes(30.), &air)
+        .unwrap();
+    recorder
+        .record(Depth::from_meters(6.), Time::from_minutes(120.), &air)
+        .unwrap();
+
+    // 1s stepping reference
+    let mut model = fixtures::model_gf((30, 70));
+    let mut deco_seconds = 0;
+    for (depth, minutes) in [(40., 30), (6., 120)] {
+        for _ in 0..(minutes * 60) {
+            model.record(Depth::from_meters(depth), Time::from_seconds(1.), &air);
+            if model.in_deco() {
+                deco_seconds += 1;
+            }
+        }
+    }
+    assert!(!model.in_deco());
+
+    let deco_time = recorder.summary().deco_time;
+    assert!(deco_time < Time::from_minutes(150.));
+    assert_close_to_abs!(deco_time.as_seconds(), deco_seconds as Float, 2.);
+}
+
+#[test]
+fn test_deco_time_sampled_records() {
+    let air = fixtures::gas_air();
+    let mut recorder = SummaryRecorder::new(fixtures::model_gf((30, 70)));
+    let mut model = fixtures::model_gf((30, 70));
+    let mut deco_seconds = 0;
+
+    // 10s samples, deco entered at 40m and cleared at 6m
+    for (depth, minutes) in [(40., 30), (6., 120)] {
+        for _ in 0..(minutes * 6) {
+            recorder
+                .record(Depth::from_meters(depth), Time::from_seconds(10.), &air)
+                .unwrap();
+            for _ in 0..10 {
+                model.record(Depth::from_meters(depth), Time::from_seconds(1.), &air);
+                if model.in_deco() {
+                    deco_seconds += 1;
+                }
+            }
+        }
+    }
+
+    let deco_time = recorder.summary().deco_time;
+    assert_close_to_abs!(deco_time.as_seconds(), deco_seconds as Float, 2.);
+}
+
+#[test]
+fn test_gases_dropped() {
+    let mut recorder = SummaryRecorder::new(fixtures::model_default());
+    for i in 0..(MAX_GASSES + 2) {
+        let gas = Gas::new(0.21 + 0.01 * i as Float, 0.);
+        recorder
+            .record(Depth::from_meters(10.), Time::from_minutes(1.), &gas)
+            .unwrap();
+    }
+
+    let summary = recorder.summary();
+    assert_eq!(summary.gases().count(), MAX_GASSES);
+    assert_eq!(summary.gases_dropped, 2);
+}
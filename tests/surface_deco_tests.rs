@@ -0,0 +1,122 @@
+use dive_deco::{
+    BuehlmannConfig, BuehlmannModel, ChamberStageType, DecoModel, DecoStageType, Depth, DepthType,
+    Float, Gas, SurfaceDeco, SurfaceDecoConfig, SurfaceDecoRuntime, Time,
+};
+
+pub mod fixtures;
+
+fn model_after(meters: DepthType, minutes: Float) -> BuehlmannModel {
+    let mut model = BuehlmannModel::new(BuehlmannConfig::default().with_deco_ascent_rate(9.));
+    model.record(
+        Depth::from_meters(meters),
+        Time::from_minutes(minutes),
+        &fixtures::gas_air(),
+    );
+    model
+}
+
+#[test]
+fn test_surface_deco_water_stops_omitted() {
+    let model = model_after(50., 60.);
+    let runtime: SurfaceDecoRuntime = SurfaceDeco::default()
+        .calc(&model, &[fixtures::gas_air()])
+        .unwrap();
+
+    // in-water stops deeper than 12m, direct ascent to the surface from the last one
+    let stages = runtime.stages();
+    assert!(stages
+        .iter()
+        .filter(|stage| stage.stage_type == DecoStageType::DecoStop)
+        .all(|stage| stage.start_depth > Depth::from_meters(12.)));
+    let last_stage = stages.last().unwrap();
+    assert_eq!(last_stage.stage_type, DecoStageType::Ascent);
+    assert_eq!(last_stage.start_depth, Depth::from_meters(12.));
+    assert_eq!(last_stage.end_depth, Depth::zero());
+
+    // in-water part matches regular deco runtime up to the omitted stops
+    let water_runtime = model
+        .deco_with_buffer::<[_; 64]>(&[fixtures::gas_air()])
+        .unwrap();
+    assert_eq!(
+        &stages[..stages.len() - 1],
+        &water_runtime.stages()[..stages.len() - 1]
+    );
+}
+
+#[test]
+fn test_surface_deco_chamber_schedule() {
+    let model = model_after(50., 60.);
+    let runtime: SurfaceDecoRuntime = SurfaceDeco::default()
+        .calc(&model, &[fixtures::gas_air()])
+        .unwrap();
+
+    let chamber_stages = runtime.chamber_stages();
+    let stage_types: Vec<ChamberStageType> = chamber_stages
+        .iter()
+        .map(|stage| stage.stage_type)
+        .collect();
+    assert_eq!(
+        stage_types,
+        vec![
+            ChamberStageType::SurfaceInterval,
+            ChamberStageType::Descent,
+            ChamberStageType::O2Period,
+            ChamberStageType::AirBreak,
+            ChamberStageType::O2Period,
+            ChamberStageType::Ascent,
+        ]
+    );
+    assert_eq!(runtime.chamber.o2_periods(), 2);
+    assert_eq!(chamber_stages[0].duration, Time::from_minutes(3.));
+    assert_eq!(chamber_stages[1].end_depth, Depth::from_meters(15.));
+    assert_eq!(chamber_stages[2].gas, Gas::new(1., 0.));
+    assert_eq!(chamber_stages[3].gas, Gas::air());
+
+    // tts including in-water stages, surface interval and chamber time
+    let water_time = runtime
+        .stages()
+        .iter()
+        .fold(Time::zero(), |total, stage| total + stage.duration);
+    assert_eq!(
+        runtime.tts,
+        water_time + Time::from_minutes(3.) + runtime.chamber.chamber_time()
+    );
+    assert!(runtime.otu > model.otu());
+}
+
+#[test]
+fn test_surface_deco_config() {
+    let model = model_after(50., 60.);
+    let config = SurfaceDecoConfig::new()
+        .with_chamber_depth(Depth::from_meters(12.))
+        .with_o2_periods(Time::from_minutes(15.), Time::from_minutes(5.))
+        .with_surface_interval(Time::from_minutes(4.));
+    let runtime: SurfaceDecoRuntime = SurfaceDeco::new(config)
+        .calc(&model, &[fixtures::gas_air()])
+        .unwrap();
+
+    let chamber_stages = runtime.chamber_stages();
+    assert_eq!(chamber_stages[0].duration, Time::from_minutes(4.));
+    assert!(chamber_stages
+        .iter()
+        .filter(|stage| stage.stage_type == ChamberStageType::O2Period)
+        .all(|stage| stage.start_depth == Depth::from_meters(12.)
+            && stage.duration == Time::from_minutes(15.)));
+    assert!(runtime.chamber.o2_periods() > 2);
+}
+
+#[test]
+fn test_surface_deco_no_water_stops() {
+    let model = model_after(40., 40.);
+    let runtime: SurfaceDecoRuntime = SurfaceDeco::default()
+        .calc(&model, &[fixtures::gas_air()])
+        .unwrap();
+
+    // all in-water stops omitted, at least one O2 period
+    assert!(runtime
+        .stages()
+        .iter()
+        .all(|stage| stage.stage_type == DecoStageType::Ascent));
+    assert_eq!(runtime.stages().last().unwrap().end_depth, Depth::zero());
+    assert_eq!(runtime.chamber.o2_periods(), 1);
+}
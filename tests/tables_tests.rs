@@ -0,0 +1,136 @@
+use dive_deco::{
+    BuehlmannConfig, BuehlmannModel, DecoModel, Depth, DepthRange, DepthType, DiveTables,
+    TableError, TableFormat, Time,
+};
+pub mod fixtures;
+
+fn depth_range(from: DepthType, to: DepthType, step: DepthType) -> DepthRange {
+    DepthRange::new(
+        Depth::from_meters(from),
+        Depth::from_meters(to),
+        Depth::from_meters(step),
+    )
+}
+
+fn tables_gf_30_70(depths: DepthRange, bottom_times: &[Time]) -> DiveTables<'_, BuehlmannModel> {
+    let config = BuehlmannConfig::new().with_gradient_factors(30, 70);
+    DiveTables::new(config, depths, bottom_times, fixtures::gas_air())
+}
+
+#[test]
+fn test_depth_range() {
+    let depths: Vec<Depth> = depth_range(12., 18., 3.).depths().collect();
+    assert_eq!(
+        depths,
+        vec![
+            Depth::from_meters(12.),
+            Depth::from_meters(15.),
+            Depth::from_meters(18.)
+        ]
+    );
+
+    let invalid_ranges = [
+        depth_range(-3., 18., 3.),
+        depth_range(12., 18., 0.),
+        depth_range(18., 12., 3.),
+    ];
+    for invalid_range in invalid_ranges {
+        assert_eq!(invalid_range.validate(), Err(TableError::InvalidDepthRange));
+        assert_eq!(invalid_range.depths().count(), 0);
+    }
+}
+
+#[test]
+fn test_ndl_row_matches_model() {
+    let tables = tables_gf_30_70(depth_range(21., 21., 3.), &[]);
+    let mut model = fixtures::model_gf((30, 70));
+    model.record(Depth::from_meters(21.), Time::zero(), &fixtures::gas_air());
+
+    let row = tables.ndl_row(Depth::from_meters(21.)).unwrap();
+    assert_eq!(row.ndl, model.ndl());
+}
+
+#[test]
+fn test_deco_row() {
+    let tables = tables_gf_30_70(depth_range(30., 30., 3.), &[]);
+
+    // within NDL
+    let row = tables
+        .deco_row(Depth::from_meters(30.), Time::from_minutes(5.))
+        .unwrap();
+    assert_eq!(row.first_stop(), None);
+    assert_eq!(row.stops().count(), 0);
+
+    // deco dive, first stop deepest
+    let row = tables
+        .deco_row(Depth::from_meters(30.), Time::from_minutes(30.))
+        .unwrap();
+    assert_eq!(row.first_stop(), Some(Depth::from_meters(12.)));
+    assert_eq!(row.stops().count(), 4);
+    assert!(row
+        .stops()
+        .all(|stop| stop.start_depth <= Depth::from_meters(12.)));
+
+    assert_eq!(
+        tables.deco_row(Depth::from_meters(30.), Time::from_minutes(-1.)),
+        Err(TableError::InvalidBottomTime)
+    );
+}
+
+#[test]
+fn test_ndl_table_csv() {
+    let tables = tables_gf_30_70(depth_range(15., 21., 3.), &[]);
+    let mut out = String::new();
+    tables.write_ndl_table(&mut out, TableFormat::Csv).unwrap();
+    assert_eq!(out, "Depth (m),NDL (min)\n15,47\n18,29\n21,19\n");
+}
+
+#[test]
+fn test_ndl_table_markdown() {
+    let tables = tables_gf_30_70(depth_range(15., 18., 3.), &[]);
+    let mut out = String::new();
+    tables
+        .write_ndl_table(&mut out, TableFormat::Markdown)
+        .unwrap();
+    assert_eq!(
+        out,
+        "| Depth (m) | NDL (min) |\n| --- | --- |\n| 15 | 47 |\n| 18 | 29 |\n"
+    );
+}
+
+#[test]
+fn test_deco_table() {
+    let bottom_times = [Time::from_minutes(20.), Time::from_minutes(40.)];
+    let tables = tables_gf_30_70(depth_range(21., 21., 3.), &bottom_times);
+
+    let mut csv = String::new();
+    tables.write_deco_table(&mut csv, TableFormat::Csv).unwrap();
+    let csv_lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        csv_lines[0],
+        "Depth (m),Bottom time (min),First stop (m),Stops,TTS (min)"
+    );
+    assert_eq!(csv_lines[1], "21,20,-,-,3");
+    assert!(csv_lines[2].starts_with("21,40,9,9m "));
+    assert_eq!(csv_lines.len(), 3);
+
+    let mut markdown = String::new();
+    tables
+        .write_deco_table(&mut markdown, TableFormat::Markdown)
+        .unwrap();
+    let markdown_lines: Vec<&str> = markdown.lines().collect();
+    assert_eq!(markdown_lines[1], "| --- | --- | --- | --- | --- |");
+    assert_eq!(markdown_lines[2], "| 21 | 20 | - | - | 3 |");
+    assert_eq!(markdown_lines.len(), 4);
+}
+
+#[test]
+fn test_invalid_depth_range_table() {
+    let tables = tables_gf_30_70(depth_range(21., 9., 3.), &[]);
+    let mut out = String::new();
+    assert_eq!(
+        tables.write_ndl_table(&mut out, TableFormat::Csv),
+        Err(TableError::InvalidDepthRange)
+    );
+    assert!(out.is_empty());
+}
@@ -0,0 +1,172 @@
+use dive_deco::{
+    write_uddf_plan, write_uddf_profile, DecoModel, Depth, Gas, ProfileReplay, ProfileSample, Time,
+    UddfDocument, UddfError, MAX_GASSES,
+};
+pub mod fixtures;
+
+const UDDF_LOG: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<uddf version="3.2.1">
+  <generator><name>test</name></generator>
+  <gasdefinitions>
+    <mix id="air"><name>Air</name><o2>0.21</o2><n2>0.79</n2><he>0.0</he></mix>
+    <mix id="ean50"><name>EAN50</name><o2>0.50</o2><n2>0.50</n2></mix>
+    <mix id="tx2135"><name>TX 21/35</name><o2>0.21</o2><n2>0.44</n2><he>0.35</he></mix>
+  </gasdefinitions>
+  <profiledata>
+    <repetitiongroup id="rg1">
+      <dive id="d1">
+        <informationbeforedive><divenumber>1</divenumber></informationbeforedive>
+        <samples>
+          <waypoint><depth>0</depth><divetime>0</divetime><switchmix ref="air"/></waypoint>
+          <waypoint><depth>30.5</depth><divetime>95</divetime><temperature>288.15</temperature></waypoint>
+          <waypoint><divetime>1500</divetime><depth>30</depth></waypoint>
+          <waypoint><depth>21</depth><divetime>1620</divetime><switchmix ref="ean50"/></waypoint>
+        </samples>
+      </dive>
+      <dive id="d2">
+        <samples>
+          <waypoint><depth>20</depth><divetime>60</divetime></waypoint>
+        </samples>
+      </dive>
+    </repetitiongroup>
+  </profiledata>
+</uddf>"#;
+
+#[test]
+fn test_gas_definitions() {
+    let document = UddfDocument::parse(UDDF_LOG).unwrap();
+    let mixes: Vec<_> = document.gas_mixes().map(Result::unwrap).collect();
+    assert_eq!(mixes.len(), 3);
+    assert_eq!(mixes[0].id, "air");
+    assert_eq!(mixes[0].gas, Gas::new(0.21, 0.));
+    assert_eq!(mixes[1].gas, Gas::new(0.5, 0.));
+    assert_eq!(document.gas("tx2135"), Ok(Gas::new(0.21, 0.35)));
+    assert_eq!(document.gas("nx32"), Err(UddfError::UnknownMix));
+}
+
+#[test]
+fn test_dive_samples() {
+    let document = UddfDocument::parse(UDDF_LOG).unwrap();
+    let dives: Vec<_> = document.dives().map(Result::unwrap).collect();
+    assert_eq!(dives.len(), 2);
+    assert_eq!(dives[0].id, Some("d1"));
+
+    let samples: Vec<_> = dives[0].samples().map(Result::unwrap).collect();
+    assert_eq!(
+        samples,
+        vec![
+            ProfileSample::new(Time::zero(), Depth::zero(), Gas::air()),
+            ProfileSample::new(
+                Time::from_seconds(95.),
+                Depth::from_meters(30.5),
+                Gas::air()
+            ),
+            ProfileSample::new(
+                Time::from_seconds(1500.),
+                Depth::from_meters(30.),
+                Gas::air()
+            ),
+            ProfileSample::new(
+                Time::from_seconds(1620.),
+                Depth::from_meters(21.),
+                Gas::new(0.5, 0.)
+            ),
+        ]
+    );
+
+    // air assumed without mix switch
+    let samples: Vec<_> = dives[1].samples().map(Result::unwrap).collect();
+    assert_eq!(samples[0].gas, Gas::air());
+    assert_eq!(dives[1].planned_samples().count(), 0);
+}
+
+#[test]
+fn test_replay_uddf_samples() {
+    let document = UddfDocument::parse(UDDF_LOG).unwrap();
+    let dive = document.dives().next().unwrap().unwrap();
+    let samples: Vec<_> = dive.samples().map(Result::unwrap).collect();
+
+    let mut replay = ProfileReplay::new(fixtures::model_gf((30, 70)));
+    let points: Vec<_> = replay.replay(&samples).map(Result::unwrap).collect();
+    assert_eq!(points.len(), 4);
+    assert!(points[3].ceiling > Depth::zero());
+    assert_eq!(points[3].gas, Gas::new(0.5, 0.));
+}
+
+#[test]
+fn test_invalid_documents() {
+    assert_eq!(
+        UddfDocument::parse("<dives></dives>").err(),
+        Some(UddfError::NotUddf)
+    );
+    assert_eq!(UddfDocument::parse("").err(), Some(UddfError::InvalidXml));
+
+    let missing_depth = r#"<uddf><gasdefinitions/><profiledata><repetitiongroup><dive>
+        <samples><waypoint><divetime>10</divetime></waypoint></samples>
+        </dive></repetitiongroup></profiledata></uddf>"#;
+    let document = UddfDocument::parse(missing_depth).unwrap();
+    let dive = document.dives().next().unwrap().unwrap();
+    let samples: Vec<_> = dive.samples().collect();
+    assert_eq!(samples, vec![Err(UddfError::MissingValue)]);
+
+    let invalid_gas = r#"<uddf><gasdefinitions>
+        <mix id="hypoxic"><o2>0.9</o2><he>0.5</he></mix>
+        </gasdefinitions></uddf>"#;
+    let document = UddfDocument::parse(invalid_gas).unwrap();
+    assert_eq!(document.gas("hypoxic"), Err(UddfError::InvalidGas));
+}
+
+#[test]
+fn test_plan_export_round_trip() {
+    let air = fixtures::gas_air();
+    let ean_50 = Gas::new(0.5, 0.);
+    let mut model = fixtures::model_gf((30, 70));
+    model.record(Depth::from_meters(40.), Time::from_minutes(20.), &air);
+    let mut gas_mixes = [air; MAX_GASSES];
+    gas_mixes[1] = ean_50;
+    let runtime = model.deco(gas_mixes).unwrap();
+
+    let mut uddf = String::new();
+    write_uddf_plan(&mut uddf, &runtime, Time::from_minutes(20.)).unwrap();
+
+    let document = UddfDocument::parse(&uddf).unwrap();
+    let mixes: Vec<_> = document.gas_mixes().map(Result::unwrap).collect();
+    assert_eq!(mixes.len(), 2);
+
+    let dive = document.dives().next().unwrap().unwrap();
+    assert_eq!(dive.samples().count(), 0);
+    let planned: Vec<_> = dive.planned_samples().map(Result::unwrap).collect();
+    let stages = runtime.stages();
+    assert_eq!(planned.len(), stages.len() + 1);
+    assert_eq!(planned[0].depth(1013), Depth::from_meters(40.));
+    assert_eq!(planned[0].time, Time::from_minutes(20.));
+    assert_eq!(planned[0].gas, air);
+
+    let last = planned.last().unwrap();
+    assert_eq!(last.depth(1013), Depth::zero());
+    assert_eq!(last.gas, ean_50);
+    assert_close_to_abs!(
+        last.time.as_seconds(),
+        (Time::from_minutes(20.) + runtime.tts).as_seconds(),
+        0.01
+    );
+}
+
+#[test]
+fn test_profile_export_round_trip() {
+    let document = UddfDocument::parse(UDDF_LOG).unwrap();
+    let dive = document.dives().next().unwrap().unwrap();
+    let samples: Vec<_> = dive.samples().map(Result::unwrap).collect();
+    let mut replay = ProfileReplay::new(fixtures::model_default()).with_ndl(true);
+    let points: Vec<_> = replay.replay(&samples).map(Result::unwrap).collect();
+
+    let mut uddf = String::new();
+    write_uddf_profile(&mut uddf, &points).unwrap();
+    assert!(uddf.contains("<nodecotime>"));
+    assert!(uddf.contains("<calculatedpo2>"));
+
+    let exported = UddfDocument::parse(&uddf).unwrap();
+    let exported_dive = exported.dives().next().unwrap().unwrap();
+    let exported_samples: Vec<_> = exported_dive.samples().map(Result::unwrap).collect();
+    assert_eq!(exported_samples, samples);
+}